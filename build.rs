@@ -0,0 +1,4 @@
+// migration di-embed oleh sqlx::migrate!, compile ulang bila ada file baru
+fn main() {
+    println!("cargo:rerun-if-changed=migrations");
+}
@@ -0,0 +1,7 @@
+CREATE TABLE users (
+    username TEXT PRIMARY KEY,
+    password_hash TEXT NOT NULL,
+    email TEXT UNIQUE,
+    display_name TEXT,
+    locked BOOLEAN NOT NULL DEFAULT FALSE
+);
@@ -0,0 +1,11 @@
+CREATE TABLE role_permissions (
+    role TEXT NOT NULL,
+    permission TEXT NOT NULL,
+    PRIMARY KEY (role, permission)
+);
+
+CREATE TABLE user_roles (
+    username TEXT NOT NULL,
+    role TEXT NOT NULL,
+    PRIMARY KEY (username, role)
+);
@@ -0,0 +1,82 @@
+use std::sync::Arc;
+
+use axum::{
+    middleware::{from_fn, from_fn_with_state},
+    routing::get,
+    Router,
+};
+
+use crate::{
+    auth,
+    cors::{cors_middleware, Cors},
+    error::negotiate_error,
+    files, health,
+    metrics::{metrics_handler, metrics_middleware},
+    middleware::{log_middleware, request_id_middleware},
+    products,
+    rate_limit::rate_limit_middleware,
+    rbac::{self, require_permission},
+    security::{security_headers_middleware, SecurityHeaders},
+    session::session_middleware,
+    state::AppState,
+    users,
+};
+
+// Router utama aplikasi, dipakai oleh main() dan test
+pub fn build_app(state: AppState) -> Router {
+    let features = state.config.features.clone();
+
+    // permission role diatur per route di rbac::router, kunci login dibuka dengan users:unlock
+    let admin = rbac::router(&state)
+        .merge(auth::lockout::router().route_layer(require_permission(&state, "users:unlock")));
+
+    let mut app = Router::new()
+        .route("/", get(|| async { "Hello, World!" }))
+        .merge(health::router())
+        .nest("/api/auth", auth::router())
+        .nest("/api/products", products::router(&state))
+        .nest("/api/users", users::router())
+        .nest("/api/files", files::router())
+        .nest("/api/admin", admin);
+
+    if features.metrics {
+        app = app.route("/metrics", get(metrics_handler));
+    }
+
+    // route_layer agar template route (MatchedPath) tersedia untuk memilih policy
+    let mut app = app
+        .route_layer(from_fn_with_state(state.clone(), rate_limit_middleware))
+        .with_state(state.clone())
+        .layer(from_fn_with_state(state.clone(), session_middleware));
+
+    // di luar session agar response error juga membawa header CORS
+    // dan preflight dijawab tanpa membuat session
+    if let Some(cors) = Cors::from_config(&state.config.cors) {
+        app = app.layer(from_fn_with_state(Arc::new(cors), cors_middleware));
+    }
+
+    // di luar CORS agar preflight yang ditolak juga dirender sesuai Accept dengan request id
+    let mut app = app.layer(from_fn(negotiate_error));
+
+    // di luar CORS agar preflight dan response error juga mendapat header keamanan
+    if let Some(security) = SecurityHeaders::from_config(&state.config.security_headers) {
+        app = app.layer(from_fn_with_state(
+            Arc::new(security),
+            security_headers_middleware,
+        ));
+    }
+
+    if features.metrics {
+        app = app.layer(from_fn_with_state(
+            state.metrics.clone(),
+            metrics_middleware,
+        ));
+    }
+
+    if features.request_logging {
+        app = app.layer(from_fn(log_middleware));
+    }
+
+    // paling luar agar request id tersedia untuk semua layer lain
+    app.layer(from_fn(request_id_middleware))
+}
@@ -0,0 +1,262 @@
+use axum::{
+    extract::{FromRef, FromRequestParts, Request, State},
+    middleware::Next,
+    response::Response,
+};
+use axum_extra::extract::CookieJar;
+use http::{header, request::Parts};
+
+use super::{token::Claims, AuthError};
+use crate::{
+    error::AppError,
+    session::{Session, SESSION_TOKEN_VERSION, SESSION_USER},
+    state::AppState,
+};
+
+// Nama cookie yang berisi token login
+pub const AUTH_COOKIE: &str = "auth_token";
+
+// User yang sudah terverifikasi dari token
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuthUser {
+    pub username: String,
+    // None bila user login lewat session, bukan token
+    pub claims: Option<Claims>,
+}
+
+impl<S> FromRequestParts<S> for AuthUser
+where
+    AppState: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        // sudah diverifikasi oleh require_auth
+        if let Some(user) = parts.extensions.get::<AuthUser>() {
+            return Ok(user.clone());
+        }
+
+        let state = AppState::from_ref(state);
+        let (user, version) = match bearer_token(parts)? {
+            Some(token) => {
+                let claims = state.tokens.verify(&token)?;
+                let version = claims.ver;
+                let user = AuthUser {
+                    username: claims.sub.clone(),
+                    claims: Some(claims),
+                };
+                (user, Some(version))
+            }
+            None => {
+                // tanpa token, pakai session dari session_middleware bila ada
+                let session = parts.extensions.get::<Session>();
+                let username = session
+                    .and_then(|session| session.get::<String>(SESSION_USER))
+                    .ok_or(AuthError::MissingToken)?;
+                let version = session.and_then(|session| session.get(SESSION_TOKEN_VERSION));
+                let user = AuthUser {
+                    username,
+                    claims: None,
+                };
+                (user, version)
+            }
+        };
+
+        // token dan session tetap bertanda tangan setelah password diganti atau akun dihapus,
+        // sehingga versinya dibandingkan dengan user yang tersimpan
+        let current = state.users.find_by_username(&user.username).await?;
+        if current.is_none_or(|current| Some(current.token_version) != version) {
+            if user.claims.is_none() {
+                if let Some(session) = parts.extensions.get::<Session>() {
+                    session.destroy();
+                }
+            }
+            return Err(AuthError::Revoked.into());
+        }
+
+        Ok(user)
+    }
+}
+
+// Middleware untuk melindungi seluruh router, dipakai dengan from_fn_with_state
+pub async fn require_auth(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Result<Response, AppError> {
+    let (mut parts, body) = request.into_parts();
+    let user = AuthUser::from_request_parts(&mut parts, &state).await?;
+    parts.extensions.insert(user);
+
+    Ok(next.run(Request::from_parts(parts, body)).await)
+}
+
+// Authorization: Bearer <token> lebih diutamakan daripada cookie;
+// nama scheme tidak case-sensitive (RFC 7235)
+fn bearer_token(parts: &Parts) -> Result<Option<String>, AppError> {
+    if let Some(value) = parts.headers.get(header::AUTHORIZATION) {
+        let value = value.to_str().map_err(|_| AuthError::MalformedHeader)?;
+        let token = match value.split_once(' ') {
+            Some((scheme, token)) if scheme.eq_ignore_ascii_case("bearer") => token,
+            _ => return Err(AuthError::MalformedHeader.into()),
+        };
+        return Ok(Some(token.trim().to_string()));
+    }
+
+    let jar = CookieJar::from_headers(&parts.headers);
+    Ok(jar
+        .get(AUTH_COOKIE)
+        .map(|cookie| cookie.value().to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{middleware::from_fn_with_state, routing::get, Router};
+    use axum_test::TestServer;
+    use http::StatusCode;
+
+    use super::*;
+    use crate::{
+        auth::{store::User, token::now},
+        test_support::TestApp,
+    };
+
+    fn server() -> (TestServer, AppState) {
+        async fn me(user: AuthUser) -> String {
+            format!("Hello {}", user.username)
+        }
+
+        // versi tetap agar test bisa membuat token yang cocok maupun yang sudah dicabut
+        let state = TestApp::new()
+            .user(User {
+                token_version: 0,
+                ..User::new("aqil", "rahasia").unwrap()
+            })
+            .state();
+        let protected = Router::new()
+            .route("/layer", get(|| async { "Protected" }))
+            .layer(from_fn_with_state(state.clone(), require_auth));
+
+        let app = Router::new()
+            .route("/me", get(me))
+            .merge(protected)
+            .with_state(state.clone());
+
+        (TestServer::new(app).unwrap(), state)
+    }
+
+    // Token valid
+    #[tokio::test]
+    async fn test_auth_user() {
+        let (server, state) = server();
+        let token = state.tokens.issue("aqil", 0);
+
+        let response = server
+            .get("/me")
+            .add_header("Authorization", format!("Bearer {}", token))
+            .await;
+        response.assert_status_ok();
+        response.assert_text("Hello aqil");
+
+        for scheme in ["bearer", "BEARER"] {
+            let response = server
+                .get("/me")
+                .add_header("Authorization", format!("{} {}", scheme, token))
+                .await;
+            response.assert_status_ok();
+            response.assert_text("Hello aqil");
+        }
+
+        let response = server
+            .get("/me")
+            .add_header("Cookie", format!("{}={}", AUTH_COOKIE, token))
+            .await;
+        response.assert_status_ok();
+        response.assert_text("Hello aqil");
+
+        let response = server
+            .get("/layer")
+            .add_header("Authorization", format!("Bearer {}", token))
+            .await;
+        response.assert_status_ok();
+        response.assert_text("Protected");
+    }
+
+    // Token tidak valid
+    #[tokio::test]
+    async fn test_auth_user_rejected() {
+        let (server, state) = server();
+
+        let response = server.get("/me").await;
+        response.assert_status(StatusCode::UNAUTHORIZED);
+        response.assert_text("Missing token");
+
+        let response = server.get("/layer").await;
+        response.assert_status(StatusCode::UNAUTHORIZED);
+
+        let response = server
+            .get("/me")
+            .add_header("Authorization", "Basic abc")
+            .await;
+        response.assert_status(StatusCode::UNAUTHORIZED);
+        response.assert_text("Malformed Authorization header");
+
+        let response = server
+            .get("/me")
+            .add_header("Authorization", "Bearerabc")
+            .await;
+        response.assert_text("Malformed Authorization header");
+
+        let response = server
+            .get("/me")
+            .add_header("Authorization", "Bearer abc")
+            .await;
+        response.assert_status(StatusCode::UNAUTHORIZED);
+        response.assert_text("token is malformed");
+
+        let expired = state
+            .tokens
+            .issue_at("aqil", 0, now() - 2 * state.tokens.ttl().as_secs());
+        let response = server
+            .get("/me")
+            .add_header("Authorization", format!("Bearer {}", expired))
+            .await;
+        response.assert_status(StatusCode::UNAUTHORIZED);
+        response.assert_text("token has expired");
+
+        let token = state.tokens.issue("aqil", 0);
+        let (payload, signature) = token.split_once('.').unwrap();
+        let tampered = format!("{}A.{}", payload, signature);
+        let response = server
+            .get("/me")
+            .add_header("Authorization", format!("Bearer {}", tampered))
+            .await;
+        response.assert_status(StatusCode::UNAUTHORIZED);
+        response.assert_text("token signature is invalid");
+    }
+
+    // Token dengan versi lama atau milik user yang sudah dihapus
+    #[tokio::test]
+    async fn test_auth_user_revoked() {
+        let (server, state) = server();
+        let revoked = "Login is no longer valid, please log in again";
+
+        let token = state.tokens.issue("aqil", 1);
+        let response = server
+            .get("/me")
+            .add_header("Authorization", format!("Bearer {}", token))
+            .await;
+        response.assert_status(StatusCode::UNAUTHORIZED);
+        response.assert_text(revoked);
+
+        let token = state.tokens.issue("aqil", 0);
+        state.users.delete("aqil").await.unwrap();
+        let response = server
+            .get("/layer")
+            .add_header("Authorization", format!("Bearer {}", token))
+            .await;
+        response.assert_status(StatusCode::UNAUTHORIZED);
+        response.assert_text(revoked);
+    }
+}
@@ -0,0 +1,606 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    net::IpAddr,
+    sync::Mutex,
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use axum::{extract::State, routing::delete, Router};
+use http::StatusCode;
+use tokio::time::Instant;
+
+use super::AuthUser;
+use crate::{config::LockoutConfig, error::AppError, extract::PathParams, state::AppState};
+
+// Riwayat gagal login untuk satu kunci (username atau IP)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Attempts {
+    pub failures: u32,
+    pub last_failure: Instant,
+    // percobaan berikutnya baru diterima setelah waktu ini
+    pub retry_at: Instant,
+    // true bila retry_at berasal dari kunci sementara, bukan backoff
+    pub locked: bool,
+}
+
+impl Attempts {
+    /// Adds one failure; reaching `threshold` failures locks the key for `config.duration`.
+    pub fn fail(
+        previous: Option<Attempts>,
+        config: &LockoutConfig,
+        threshold: u32,
+        now: Instant,
+    ) -> Attempts {
+        // counter mulai dari nol bila kegagalan terakhir sudah lebih lama dari durasi kunci
+        let failures = match previous {
+            Some(previous) if now < previous.last_failure + config.duration() => {
+                previous.failures + 1
+            }
+            _ => 1,
+        };
+        let locked = failures >= threshold;
+        let retry_at = if locked {
+            now + config.duration()
+        } else {
+            now + config.delay(failures)
+        };
+
+        Attempts {
+            failures,
+            last_failure: now,
+            retry_at,
+            locked,
+        }
+    }
+
+    /// Takes back one failure added by [`Attempts::fail`], `None` when no failure is left.
+    pub fn refund(self, config: &LockoutConfig, threshold: u32) -> Option<Attempts> {
+        let failures = self
+            .failures
+            .checked_sub(1)
+            .filter(|failures| *failures > 0)?;
+        let locked = failures >= threshold;
+        let retry_at = if locked {
+            self.retry_at
+        } else {
+            self.last_failure + config.delay(failures)
+        };
+
+        Some(Attempts {
+            failures,
+            retry_at,
+            locked,
+            ..self
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Reservation {
+    // percobaan sudah dihitung sebagai gagal
+    Reserved(Attempts),
+    // kunci masih dalam backoff atau terkunci, percobaan tidak dihitung
+    Rejected(Attempts),
+}
+
+// Penyimpanan riwayat gagal login, memakai jam tokio agar bisa dimajukan di test
+#[async_trait]
+pub trait LoginAttemptStore: Send + Sync {
+    async fn get(&self, key: &str) -> anyhow::Result<Option<Attempts>>;
+    /// Atomically counts an attempt as failed before the password is checked, unless the key
+    /// is still backing off, so parallel guesses cannot all pass the check first.
+    async fn reserve(
+        &self,
+        key: &str,
+        config: &LockoutConfig,
+        threshold: u32,
+    ) -> anyhow::Result<Reservation>;
+    /// Takes back an attempt counted by `reserve` whose credentials turned out to be valid.
+    async fn refund(&self, key: &str, config: &LockoutConfig, threshold: u32)
+        -> anyhow::Result<()>;
+    /// Returns false if nothing was recorded for the key.
+    async fn clear(&self, key: &str) -> anyhow::Result<bool>;
+}
+
+// Implementasi in-memory dengan jumlah kunci terbatas, karena username dan IP dipilih
+// oleh client; riwayat yang sudah kedaluwarsa dibuang saat kunci baru masuk
+#[derive(Debug)]
+pub struct InMemoryLoginAttemptStore {
+    max_keys: usize,
+    attempts: Mutex<AttemptMap>,
+}
+
+#[derive(Debug, Default)]
+struct AttemptMap {
+    attempts: HashMap<String, Attempts>,
+    // kunci terurut menurut kegagalan terakhir, agar riwayat yang kedaluwarsa atau
+    // paling lama bisa dibuang tanpa scan seluruh map
+    by_last_failure: BTreeSet<(Instant, String)>,
+}
+
+impl AttemptMap {
+    fn insert(&mut self, key: &str, attempts: Attempts) {
+        self.remove(key);
+        self.by_last_failure
+            .insert((attempts.last_failure, key.to_string()));
+        self.attempts.insert(key.to_string(), attempts);
+    }
+
+    fn remove(&mut self, key: &str) -> Option<Attempts> {
+        let attempts = self.attempts.remove(key)?;
+        self.by_last_failure
+            .remove(&(attempts.last_failure, key.to_string()));
+        Some(attempts)
+    }
+
+    // Riwayat yang lebih lama dari durasi kunci sama dengan tidak ada, aman dibuang;
+    // bila masih penuh, buang riwayat dengan kegagalan terakhir paling lama
+    fn evict(&mut self, max_keys: usize, duration: Duration, now: Instant) {
+        while let Some((last_failure, _)) = self.by_last_failure.first() {
+            if *last_failure + duration > now && self.attempts.len() < max_keys {
+                break;
+            }
+            if let Some((_, key)) = self.by_last_failure.pop_first() {
+                self.attempts.remove(&key);
+            }
+        }
+    }
+}
+
+impl InMemoryLoginAttemptStore {
+    pub fn new(max_keys: usize) -> InMemoryLoginAttemptStore {
+        InMemoryLoginAttemptStore {
+            max_keys,
+            attempts: Mutex::new(AttemptMap::default()),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.attempts.lock().unwrap().attempts.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[async_trait]
+impl LoginAttemptStore for InMemoryLoginAttemptStore {
+    async fn get(&self, key: &str) -> anyhow::Result<Option<Attempts>> {
+        Ok(self.attempts.lock().unwrap().attempts.get(key).copied())
+    }
+
+    async fn reserve(
+        &self,
+        key: &str,
+        config: &LockoutConfig,
+        threshold: u32,
+    ) -> anyhow::Result<Reservation> {
+        let now = Instant::now();
+        let mut attempts = self.attempts.lock().unwrap();
+        let previous = attempts.attempts.get(key).copied();
+        if let Some(previous) = previous.filter(|previous| previous.retry_at > now) {
+            return Ok(Reservation::Rejected(previous));
+        }
+
+        if previous.is_none() {
+            attempts.evict(self.max_keys, config.duration(), now);
+        }
+        let updated = Attempts::fail(previous, config, threshold, now);
+        attempts.insert(key, updated);
+        Ok(Reservation::Reserved(updated))
+    }
+
+    async fn refund(
+        &self,
+        key: &str,
+        config: &LockoutConfig,
+        threshold: u32,
+    ) -> anyhow::Result<()> {
+        let mut attempts = self.attempts.lock().unwrap();
+        if let Some(previous) = attempts.remove(key) {
+            if let Some(refunded) = previous.refund(config, threshold) {
+                attempts.insert(key, refunded);
+            }
+        }
+        Ok(())
+    }
+
+    async fn clear(&self, key: &str) -> anyhow::Result<bool> {
+        Ok(self.attempts.lock().unwrap().remove(key).is_some())
+    }
+}
+
+fn username_key(username: &str) -> String {
+    format!("user:{}", username)
+}
+
+fn ip_key(ip: IpAddr) -> String {
+    format!("ip:{}", ip)
+}
+
+// (kunci, threshold) untuk satu percobaan login; IP tidak dihitung bila tidak diketahui
+fn keys(config: &LockoutConfig, username: &str, ip: Option<IpAddr>) -> Vec<(String, u32)> {
+    let mut keys = vec![(username_key(username), config.username_threshold)];
+    if let Some(ip) = ip {
+        keys.push((ip_key(ip), config.ip_threshold));
+    }
+    keys
+}
+
+fn rejection(attempts: &Attempts, now: Instant) -> AppError {
+    let wait = attempts.retry_at - now;
+    // detik dibulatkan ke atas agar client tidak mencoba terlalu cepat
+    let seconds = wait.as_secs() + u64::from(wait.subsec_nanos() > 0);
+    if attempts.locked {
+        AppError::locked(
+            "login_locked",
+            format!(
+                "Too many failed login attempts, locked for {} seconds",
+                seconds
+            ),
+        )
+    } else {
+        AppError::too_many_requests(
+            "login_backoff",
+            format!(
+                "Too many failed login attempts, retry in {} seconds",
+                seconds
+            ),
+        )
+    }
+}
+
+// Percobaan login yang sudah dihitung gagal oleh reserve, per kunci
+#[derive(Debug)]
+#[must_use]
+pub struct LoginAttempt {
+    reserved: Vec<(String, u32, Attempts)>,
+}
+
+/// Counts the attempt as failed for the username and IP before the password is checked,
+/// or rejects it while either of them is backing off.
+pub async fn reserve(
+    state: &AppState,
+    username: &str,
+    ip: Option<IpAddr>,
+) -> Result<LoginAttempt, AppError> {
+    let config = &state.config.lockout;
+    let mut attempt = LoginAttempt {
+        reserved: Vec::new(),
+    };
+    if !config.enabled {
+        return Ok(attempt);
+    }
+
+    for (key, threshold) in keys(config, username, ip) {
+        match state
+            .login_attempts
+            .reserve(&key, config, threshold)
+            .await?
+        {
+            Reservation::Reserved(attempts) => attempt.reserved.push((key, threshold, attempts)),
+            Reservation::Rejected(attempts) => {
+                // kunci yang sudah dihitung dikembalikan, percobaan ini tidak diproses
+                refund(state, attempt).await?;
+                return Err(rejection(&attempts, Instant::now()));
+            }
+        }
+    }
+    Ok(attempt)
+}
+
+/// Keeps the reserved failure and audits keys that it locked.
+pub fn record_failure(state: &AppState, attempt: LoginAttempt) {
+    for (key, threshold, attempts) in attempt.reserved {
+        if attempts.locked && attempts.failures == threshold {
+            tracing::warn!(
+                target: "audit",
+                event = "login_locked",
+                key = %key,
+                failures = attempts.failures,
+                duration = state.config.lockout.duration,
+                "login locked after repeated failures"
+            );
+        }
+    }
+}
+
+/// Takes back every failure counted by [`reserve`].
+pub async fn refund(state: &AppState, attempt: LoginAttempt) -> anyhow::Result<()> {
+    let config = &state.config.lockout;
+    for (key, threshold, _) in attempt.reserved {
+        state.login_attempts.refund(&key, config, threshold).await?;
+    }
+    Ok(())
+}
+
+// Login berhasil mereset username; untuk IP hanya percobaan ini yang dikembalikan
+// agar satu akun valid tidak bisa dipakai untuk menebak akun lain
+pub async fn record_success(
+    state: &AppState,
+    username: &str,
+    attempt: LoginAttempt,
+) -> anyhow::Result<()> {
+    let username_key = username_key(username);
+    state.login_attempts.clear(&username_key).await?;
+    let reserved = attempt
+        .reserved
+        .into_iter()
+        .filter(|(key, _, _)| *key != username_key)
+        .collect();
+    refund(state, LoginAttempt { reserved }).await
+}
+
+// Route /api/admin/lockouts, membuka kunci sebelum waktunya
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/lockouts/users/{username}", delete(unlock_user))
+        .route("/lockouts/ips/{ip}", delete(unlock_ip))
+}
+
+async fn unlock(state: &AppState, admin: &AuthUser, key: String) -> Result<StatusCode, AppError> {
+    if !state.login_attempts.clear(&key).await? {
+        return Err(AppError::not_found(
+            "lockout_not_found",
+            format!("No failed login attempts recorded for {}", key),
+        ));
+    }
+
+    tracing::info!(
+        target: "audit",
+        event = "login_unlocked",
+        key = %key,
+        by = %admin.username,
+        "login unlocked by admin"
+    );
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn unlock_user(
+    State(state): State<AppState>,
+    admin: AuthUser,
+    PathParams(username): PathParams<String>,
+) -> Result<StatusCode, AppError> {
+    unlock(&state, &admin, username_key(&username)).await
+}
+
+async fn unlock_ip(
+    State(state): State<AppState>,
+    admin: AuthUser,
+    PathParams(ip): PathParams<IpAddr>,
+) -> Result<StatusCode, AppError> {
+    unlock(&state, &admin, ip_key(ip)).await
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use axum_test::TestServer;
+    use serde_json::json;
+
+    use super::*;
+    use crate::{
+        auth::store::User,
+        error::assert_error_text,
+        logging::capture::capture,
+        test_support::{bearer, TestApp},
+        Config,
+    };
+
+    fn config() -> Config {
+        let mut config = Config::default();
+        config.rbac.admins = vec!["admin".to_string()];
+        config.lockout = LockoutConfig {
+            enabled: true,
+            free_attempts: 2,
+            base_delay: 10,
+            max_delay: 40,
+            username_threshold: 5,
+            ip_threshold: 8,
+            duration: 300,
+            max_keys: 100,
+        };
+        config
+    }
+
+    async fn login(server: &TestServer, username: &str, password: &str) -> axum_test::TestResponse {
+        server
+            .post("/api/auth/login")
+            .json(&json!({"username": username, "password": password}))
+            .await
+    }
+
+    #[test]
+    fn test_backoff() {
+        let config = config().lockout;
+        assert_eq!(config.delay(2), Duration::ZERO);
+        assert_eq!(config.delay(3), Duration::from_secs(10));
+        assert_eq!(config.delay(4), Duration::from_secs(20));
+        assert_eq!(config.delay(6), Duration::from_secs(40));
+        assert_eq!(config.delay(u32::MAX), Duration::from_secs(40));
+
+        let now = Instant::now();
+        let mut attempts = None;
+        for _ in 0..4 {
+            attempts = Some(Attempts::fail(attempts, &config, 5, now));
+        }
+        let attempts = attempts.unwrap();
+        assert_eq!(attempts.failures, 4);
+        assert_eq!(attempts.retry_at, now + Duration::from_secs(20));
+        assert!(!attempts.locked);
+
+        let locked = Attempts::fail(Some(attempts), &config, 5, now);
+        assert!(locked.locked);
+        assert_eq!(locked.retry_at, now + Duration::from_secs(300));
+
+        // counter direset setelah durasi kunci lewat
+        let later = now + Duration::from_secs(300);
+        assert_eq!(Attempts::fail(Some(locked), &config, 5, later).failures, 1);
+    }
+
+    // Reserve menolak selama backoff, refund mengembalikan satu kegagalan
+    #[tokio::test(start_paused = true)]
+    async fn test_reserve_and_refund() {
+        let config = config().lockout;
+        let store = InMemoryLoginAttemptStore::new(100);
+
+        for failures in 1..=3 {
+            let reservation = store.reserve("user:aqil", &config, 5).await.unwrap();
+            assert!(
+                matches!(reservation, Reservation::Reserved(attempts) if attempts.failures == failures)
+            );
+        }
+        let reservation = store.reserve("user:aqil", &config, 5).await.unwrap();
+        assert!(matches!(reservation, Reservation::Rejected(attempts) if attempts.failures == 3));
+
+        store.refund("user:aqil", &config, 5).await.unwrap();
+        let attempts = store.get("user:aqil").await.unwrap().unwrap();
+        assert_eq!(attempts.failures, 2);
+        assert_eq!(attempts.retry_at, attempts.last_failure);
+
+        store.refund("user:aqil", &config, 5).await.unwrap();
+        store.refund("user:aqil", &config, 5).await.unwrap();
+        assert!(store.is_empty());
+    }
+
+    // Jumlah kunci dibatasi: riwayat kedaluwarsa dibuang dulu, lalu yang paling lama
+    #[tokio::test(start_paused = true)]
+    async fn test_store_max_keys() {
+        let config = config().lockout;
+        let store = InMemoryLoginAttemptStore::new(2);
+
+        store.reserve("ip:10.0.0.1", &config, 8).await.unwrap();
+        tokio::time::advance(Duration::from_secs(1)).await;
+        store.reserve("ip:10.0.0.2", &config, 8).await.unwrap();
+        store.reserve("ip:10.0.0.3", &config, 8).await.unwrap();
+        assert_eq!(store.len(), 2);
+        assert!(store.get("ip:10.0.0.1").await.unwrap().is_none());
+
+        tokio::time::advance(Duration::from_secs(300)).await;
+        store.reserve("ip:10.0.0.4", &config, 8).await.unwrap();
+        assert_eq!(store.len(), 1);
+    }
+
+    // Tebakan paralel tidak bisa melewati backoff karena dihitung sebelum password diverifikasi
+    #[tokio::test]
+    async fn test_parallel_guesses() {
+        let (server, _) = TestApp::with_config(config())
+            .user(User::new("aqil", "rahasia").unwrap())
+            .server();
+
+        let responses =
+            futures::future::join_all((0..10).map(|_| login(&server, "aqil", "salah"))).await;
+        let status = |status: StatusCode| {
+            responses
+                .iter()
+                .filter(|response| response.status_code() == status)
+                .count()
+        };
+        assert_eq!(status(StatusCode::UNAUTHORIZED), 3);
+        assert_eq!(status(StatusCode::TOO_MANY_REQUESTS), 7);
+    }
+
+    // Backoff, kunci sementara, audit event dan unlock oleh admin
+    #[tokio::test(start_paused = true)]
+    async fn test_lockout() {
+        let (server, state) = TestApp::with_config(config())
+            .user(User::new("admin", "rahasia").unwrap())
+            .user(User::new("dewi", "rahasia").unwrap())
+            .server();
+        let (events, _guard) = capture();
+        let locked = "login locked after repeated failures";
+
+        for _ in 0..3 {
+            login(&server, "andi", "salah")
+                .await
+                .assert_status(StatusCode::UNAUTHORIZED);
+        }
+        let response = login(&server, "andi", "salah").await;
+        response.assert_status(StatusCode::TOO_MANY_REQUESTS);
+        assert_error_text(
+            &response,
+            "Too many failed login attempts, retry in 10 seconds",
+        );
+
+        tokio::time::advance(Duration::from_secs(10)).await;
+        login(&server, "andi", "salah")
+            .await
+            .assert_status(StatusCode::UNAUTHORIZED);
+        tokio::time::advance(Duration::from_secs(20)).await;
+        assert!(events.find(locked).is_none());
+        login(&server, "andi", "salah")
+            .await
+            .assert_status(StatusCode::UNAUTHORIZED);
+
+        let response = login(&server, "andi", "salah").await;
+        response.assert_status(StatusCode::LOCKED);
+        assert_error_text(
+            &response,
+            "Too many failed login attempts, locked for 300 seconds",
+        );
+        let event = events.find(locked).unwrap();
+        assert_eq!(event.field("key"), Some("user:andi"));
+        assert_eq!(event.field("failures"), Some("5"));
+
+        // IP yang sama masih dalam backoff untuk username lain
+        let response = login(&server, "budi", "salah").await;
+        response.assert_status(StatusCode::TOO_MANY_REQUESTS);
+        assert_error_text(
+            &response,
+            "Too many failed login attempts, retry in 40 seconds",
+        );
+
+        let admin = bearer(&state, "admin").await;
+        for path in ["users/andi", "ips/10.0.0.1"] {
+            server
+                .delete(&format!("/api/admin/lockouts/{}", path))
+                .authorization(&admin)
+                .await
+                .assert_status(StatusCode::NO_CONTENT);
+        }
+        let event = events.find("login unlocked by admin").unwrap();
+        assert_eq!(event.field("by"), Some("admin"));
+        login(&server, "andi", "salah")
+            .await
+            .assert_status(StatusCode::UNAUTHORIZED);
+
+        let response = server
+            .delete("/api/admin/lockouts/users/citra")
+            .authorization(&admin)
+            .await;
+        response.assert_status_not_found();
+        assert_error_text(
+            &response,
+            "No failed login attempts recorded for user:citra",
+        );
+
+        // user biasa tidak boleh membuka kunci
+        server
+            .delete("/api/admin/lockouts/users/andi")
+            .authorization(bearer(&state, "dewi").await)
+            .await
+            .assert_status(StatusCode::FORBIDDEN);
+    }
+
+    // Login berhasil mereset counter username
+    #[tokio::test(start_paused = true)]
+    async fn test_login_success_resets() {
+        let (server, state) = TestApp::with_config(config())
+            .user(User::new("aqil", "rahasia").unwrap())
+            .server();
+        let attempts = &state.login_attempts;
+
+        login(&server, "aqil", "salah")
+            .await
+            .assert_status(StatusCode::UNAUTHORIZED);
+        assert!(attempts.get("user:aqil").await.unwrap().is_some());
+
+        login(&server, "aqil", "rahasia").await.assert_status_ok();
+        assert!(attempts.get("user:aqil").await.unwrap().is_none());
+        let ip = attempts.get("ip:10.0.0.1").await.unwrap();
+        assert_eq!(ip.unwrap().failures, 1);
+    }
+}
@@ -0,0 +1,226 @@
+pub mod extract;
+pub mod lockout;
+pub mod password;
+pub mod store;
+pub mod token;
+
+use axum::{extract::State, routing::post, Json, Router};
+use http::StatusCode;
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+use crate::{
+    error::AppError,
+    extract::{ClientIp, ValidatedJson},
+    session::{Session, SESSION_TOKEN_VERSION, SESSION_USER},
+    state::AppState,
+    validation::not_blank,
+};
+
+use self::token::TokenError;
+
+pub use self::extract::{require_auth, AuthUser};
+
+// Batas panjang password mencegah hashing input yang sangat besar
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct LoginRequest {
+    #[validate(length(max = 64), custom(function = "not_blank"))]
+    pub username: String,
+    #[validate(length(min = 1, max = 1024))]
+    pub password: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LoginResponse {
+    pub token: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+pub enum AuthError {
+    #[error("Unknown user")]
+    UnknownUser,
+
+    #[error("Wrong password")]
+    WrongPassword,
+
+    #[error("Account is locked")]
+    AccountLocked,
+
+    #[error("Missing token")]
+    MissingToken,
+
+    #[error("Malformed Authorization header")]
+    MalformedHeader,
+
+    #[error("Login is no longer valid, please log in again")]
+    Revoked,
+}
+
+impl From<AuthError> for AppError {
+    fn from(error: AuthError) -> Self {
+        match error {
+            AuthError::UnknownUser => AppError::unauthorized("unknown_user", error.to_string()),
+            AuthError::WrongPassword => AppError::unauthorized("wrong_password", error.to_string()),
+            AuthError::AccountLocked => AppError::locked("account_locked", error.to_string()),
+            AuthError::MissingToken => AppError::unauthorized("missing_token", error.to_string()),
+            AuthError::MalformedHeader => {
+                AppError::unauthorized("malformed_authorization", error.to_string())
+            }
+            AuthError::Revoked => AppError::unauthorized("login_revoked", error.to_string()),
+        }
+    }
+}
+
+impl From<TokenError> for AppError {
+    fn from(error: TokenError) -> Self {
+        let code = match error {
+            TokenError::Malformed => "token_malformed",
+            TokenError::InvalidSignature => "token_invalid_signature",
+            TokenError::Expired => "token_expired",
+        };
+
+        AppError::unauthorized(code, error.to_string())
+    }
+}
+
+// Route /api/auth
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/login", post(login))
+        .route("/logout", post(logout))
+}
+
+async fn login(
+    State(state): State<AppState>,
+    ClientIp(ip): ClientIp,
+    session: Session,
+    ValidatedJson(request): ValidatedJson<LoginRequest>,
+) -> Result<Json<LoginResponse>, AppError> {
+    // percobaan dihitung gagal sebelum password diverifikasi, sehingga tebakan paralel
+    // tidak bisa lolos bersamaan dari backoff; login yang berhasil mengembalikannya
+    let attempt = lockout::reserve(&state, &request.username, ip).await?;
+
+    let Some(user) = state.users.find_by_username(&request.username).await? else {
+        lockout::record_failure(&state, attempt);
+        return Err(AuthError::UnknownUser.into());
+    };
+
+    if !password::verify(request.password, user.password_hash.clone()).await? {
+        lockout::record_failure(&state, attempt);
+        return Err(AuthError::WrongPassword.into());
+    }
+
+    // status locked baru dibuka setelah password benar, agar akun yang dikunci
+    // tidak bisa ditemukan tanpa mengetahui password-nya
+    if user.locked {
+        lockout::refund(&state, attempt).await?;
+        return Err(AuthError::AccountLocked.into());
+    }
+    lockout::record_success(&state, &user.username, attempt).await?;
+
+    // id session baru setelah login mencegah session fixation
+    session.rotate();
+    session.insert(SESSION_USER, &user.username)?;
+    session.insert(SESSION_TOKEN_VERSION, user.token_version)?;
+
+    Ok(Json(LoginResponse {
+        token: state.tokens.issue(&user.username, user.token_version),
+    }))
+}
+
+async fn logout(session: Session) -> StatusCode {
+    session.destroy();
+    StatusCode::NO_CONTENT
+}
+
+#[cfg(test)]
+mod tests {
+    use axum_test::TestServer;
+    use http::StatusCode;
+
+    use super::{store::User, *};
+    use crate::{error::assert_error_text, test_support::TestApp};
+
+    fn server() -> (TestServer, AppState) {
+        TestApp::new()
+            .user(User::new("aqil", "rahasia").unwrap())
+            .user(User {
+                locked: true,
+                ..User::new("budi", "rahasia").unwrap()
+            })
+            .server()
+    }
+
+    fn login_request(username: &str, password: &str) -> LoginRequest {
+        LoginRequest {
+            username: username.to_string(),
+            password: password.to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_login_success() {
+        let (server, state) = server();
+
+        let response = server
+            .post("/api/auth/login")
+            .json(&login_request("aqil", "rahasia"))
+            .await;
+        response.assert_status_ok();
+
+        let body: LoginResponse = response.json();
+        let claims = state.tokens.verify(&body.token).unwrap();
+        assert_eq!(claims.sub, "aqil");
+    }
+
+    #[tokio::test]
+    async fn test_login_error() {
+        let (server, _) = server();
+
+        let response = server
+            .post("/api/auth/login")
+            .json(&login_request("andi", "rahasia"))
+            .await;
+        response.assert_status(StatusCode::UNAUTHORIZED);
+        assert_error_text(&response, "Unknown user");
+
+        let response = server
+            .post("/api/auth/login")
+            .json(&login_request("aqil", "salah"))
+            .await;
+        response.assert_status(StatusCode::UNAUTHORIZED);
+        assert_error_text(&response, "Wrong password");
+
+        // akun yang dikunci tetap 401 selama password-nya salah
+        let response = server
+            .post("/api/auth/login")
+            .json(&login_request("budi", "salah"))
+            .await;
+        response.assert_status(StatusCode::UNAUTHORIZED);
+        assert_error_text(&response, "Wrong password");
+
+        let response = server
+            .post("/api/auth/login")
+            .json(&login_request("budi", "rahasia"))
+            .await;
+        response.assert_status(StatusCode::LOCKED);
+        assert_error_text(&response, "Account is locked");
+    }
+
+    #[tokio::test]
+    async fn test_login_validation() {
+        let (server, _) = server();
+
+        let response = server
+            .post("/api/auth/login")
+            .json(&login_request(" ", ""))
+            .await;
+        response.assert_status(StatusCode::UNPROCESSABLE_ENTITY);
+        assert_error_text(
+            &response,
+            "Request validation failed\n\
+             password: length must be between 1 and 1024\n\
+             username: must not be blank",
+        );
+    }
+}
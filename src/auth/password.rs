@@ -0,0 +1,55 @@
+use argon2::{
+    password_hash::{rand_core::OsRng, PasswordHash, PasswordHasher, PasswordVerifier, SaltString},
+    Argon2,
+};
+
+// Hash password dengan Argon2id dan salt acak, hasilnya string PHC
+pub fn hash_password(password: &str) -> anyhow::Result<String> {
+    let salt = SaltString::generate(&mut OsRng);
+    let hash = Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .map_err(|error| anyhow::anyhow!("failed to hash password: {}", error))?;
+
+    Ok(hash.to_string())
+}
+
+pub fn verify_password(password: &str, hash: &str) -> anyhow::Result<bool> {
+    let hash = PasswordHash::new(hash)
+        .map_err(|error| anyhow::anyhow!("invalid password hash: {}", error))?;
+
+    Ok(Argon2::default()
+        .verify_password(password.as_bytes(), &hash)
+        .is_ok())
+}
+
+// Argon2 cukup berat, handler memakai versi async yang berjalan di thread blocking
+pub async fn hash(password: String) -> anyhow::Result<String> {
+    tokio::task::spawn_blocking(move || hash_password(&password)).await?
+}
+
+pub async fn verify(password: String, hash: String) -> anyhow::Result<bool> {
+    tokio::task::spawn_blocking(move || verify_password(&password, &hash)).await?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hash_and_verify() {
+        let hash = hash_password("rahasia").unwrap();
+
+        assert!(hash.starts_with("$argon2id$"));
+        assert!(verify_password("rahasia", &hash).unwrap());
+        assert!(!verify_password("salah", &hash).unwrap());
+        assert!(verify_password("rahasia", "bukan hash").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_hash_and_verify_async() {
+        let hash = hash("rahasia".to_string()).await.unwrap();
+
+        assert!(verify("rahasia".to_string(), hash.clone()).await.unwrap());
+        assert!(!verify("salah".to_string(), hash).await.unwrap());
+    }
+}
@@ -0,0 +1,405 @@
+use std::{collections::HashMap, sync::RwLock};
+
+use async_trait::async_trait;
+use sqlx::SqlitePool;
+
+use super::password::hash_password;
+use crate::{error::AppError, files::FileId};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct User {
+    pub username: String,
+    pub password_hash: String,
+    pub email: Option<String>,
+    pub display_name: Option<String>,
+    pub locked: bool,
+    pub avatar: Option<FileId>,
+    // acak saat akun dibuat dan setiap password diganti, token dan session dengan versi lain
+    // ditolak; akun baru dengan username yang sama tidak menerima token akun yang sudah dihapus
+    pub token_version: i64,
+}
+
+impl User {
+    pub fn new(username: &str, password: &str) -> anyhow::Result<User> {
+        Ok(User {
+            username: username.to_string(),
+            password_hash: hash_password(password)?,
+            email: None,
+            display_name: None,
+            locked: false,
+            avatar: None,
+            token_version: User::new_token_version(),
+        })
+    }
+
+    pub fn new_token_version() -> i64 {
+        rand::random()
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum UserStoreError {
+    #[error("Username is already taken")]
+    UsernameTaken,
+
+    #[error("Email is already registered")]
+    EmailTaken,
+
+    #[error(transparent)]
+    Other(#[from] anyhow::Error),
+}
+
+impl From<UserStoreError> for AppError {
+    fn from(error: UserStoreError) -> Self {
+        match error {
+            UserStoreError::UsernameTaken => {
+                AppError::conflict("username_taken", error.to_string())
+            }
+            UserStoreError::EmailTaken => AppError::conflict("email_taken", error.to_string()),
+            UserStoreError::Other(error) => AppError::Internal(error),
+        }
+    }
+}
+
+// Sumber data user, bisa diganti dengan database
+#[async_trait]
+pub trait UserStore: Send + Sync {
+    async fn find_by_username(&self, username: &str) -> anyhow::Result<Option<User>>;
+    async fn create(&self, user: User) -> Result<User, UserStoreError>;
+    // Setiap update hanya menulis kolomnya sendiri, supaya update profil atau avatar
+    // yang berjalan bersamaan tidak menimpa password dan token_version yang baru diganti
+    /// Sets email and display name; returns false if there is no such user.
+    async fn update_profile(
+        &self,
+        username: &str,
+        email: Option<&str>,
+        display_name: Option<&str>,
+    ) -> Result<bool, UserStoreError>;
+    /// Sets the avatar; returns false if there is no such user.
+    async fn set_avatar(&self, username: &str, avatar: &FileId) -> anyhow::Result<bool>;
+    /// Sets the password hash together with a new token version; returns false if there is no such user.
+    async fn set_password(
+        &self,
+        username: &str,
+        password_hash: &str,
+        token_version: i64,
+    ) -> anyhow::Result<bool>;
+    async fn delete(&self, username: &str) -> anyhow::Result<bool>;
+}
+
+// Implementasi in-memory dengan key username; email unik dicek dengan scan semua user
+#[derive(Debug, Default)]
+pub struct InMemoryUserStore {
+    users: RwLock<HashMap<String, User>>,
+}
+
+impl InMemoryUserStore {
+    pub fn new() -> InMemoryUserStore {
+        InMemoryUserStore::default()
+    }
+
+    pub fn insert(&self, user: User) {
+        self.users
+            .write()
+            .unwrap()
+            .insert(user.username.clone(), user);
+    }
+}
+
+fn email_taken(users: &HashMap<String, User>, username: &str, email: Option<&str>) -> bool {
+    email.is_some()
+        && users
+            .values()
+            .any(|other| other.username != username && other.email.as_deref() == email)
+}
+
+#[async_trait]
+impl UserStore for InMemoryUserStore {
+    async fn find_by_username(&self, username: &str) -> anyhow::Result<Option<User>> {
+        Ok(self.users.read().unwrap().get(username).cloned())
+    }
+
+    async fn create(&self, user: User) -> Result<User, UserStoreError> {
+        let mut users = self.users.write().unwrap();
+        if users.contains_key(&user.username) {
+            return Err(UserStoreError::UsernameTaken);
+        }
+        if email_taken(&users, &user.username, user.email.as_deref()) {
+            return Err(UserStoreError::EmailTaken);
+        }
+
+        users.insert(user.username.clone(), user.clone());
+        Ok(user)
+    }
+
+    async fn update_profile(
+        &self,
+        username: &str,
+        email: Option<&str>,
+        display_name: Option<&str>,
+    ) -> Result<bool, UserStoreError> {
+        let mut users = self.users.write().unwrap();
+        if email_taken(&users, username, email) {
+            return Err(UserStoreError::EmailTaken);
+        }
+
+        Ok(match users.get_mut(username) {
+            Some(user) => {
+                user.email = email.map(str::to_string);
+                user.display_name = display_name.map(str::to_string);
+                true
+            }
+            None => false,
+        })
+    }
+
+    async fn set_avatar(&self, username: &str, avatar: &FileId) -> anyhow::Result<bool> {
+        Ok(match self.users.write().unwrap().get_mut(username) {
+            Some(user) => {
+                user.avatar = Some(avatar.clone());
+                true
+            }
+            None => false,
+        })
+    }
+
+    async fn set_password(
+        &self,
+        username: &str,
+        password_hash: &str,
+        token_version: i64,
+    ) -> anyhow::Result<bool> {
+        Ok(match self.users.write().unwrap().get_mut(username) {
+            Some(user) => {
+                user.password_hash = password_hash.to_string();
+                user.token_version = token_version;
+                true
+            }
+            None => false,
+        })
+    }
+
+    async fn delete(&self, username: &str) -> anyhow::Result<bool> {
+        Ok(self.users.write().unwrap().remove(username).is_some())
+    }
+}
+
+// Implementasi SQLite, tabel users dibuat oleh migration di migrations/sqlite
+#[derive(Debug, Clone)]
+pub struct SqliteUserStore {
+    pool: SqlitePool,
+}
+
+impl SqliteUserStore {
+    pub fn new(pool: SqlitePool) -> SqliteUserStore {
+        SqliteUserStore { pool }
+    }
+}
+
+type UserRow = (
+    String,
+    String,
+    Option<String>,
+    Option<String>,
+    bool,
+    Option<String>,
+    i64,
+);
+
+// pelanggaran UNIQUE dipetakan ke kolom yang bentrok
+fn unique_violation(error: sqlx::Error) -> UserStoreError {
+    match &error {
+        sqlx::Error::Database(database) if database.is_unique_violation() => {
+            if database.message().contains("email") {
+                UserStoreError::EmailTaken
+            } else {
+                UserStoreError::UsernameTaken
+            }
+        }
+        _ => UserStoreError::Other(error.into()),
+    }
+}
+
+#[async_trait]
+impl UserStore for SqliteUserStore {
+    async fn find_by_username(&self, username: &str) -> anyhow::Result<Option<User>> {
+        let row: Option<UserRow> = sqlx::query_as(
+            "SELECT username, password_hash, email, display_name, locked, avatar, \
+             token_version FROM users WHERE username = ?",
+        )
+        .bind(username)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        let Some((username, password_hash, email, display_name, locked, avatar, token_version)) =
+            row
+        else {
+            return Ok(None);
+        };
+        Ok(Some(User {
+            username,
+            password_hash,
+            email,
+            display_name,
+            locked,
+            avatar: avatar.map(FileId::try_from).transpose()?,
+            token_version,
+        }))
+    }
+
+    async fn create(&self, user: User) -> Result<User, UserStoreError> {
+        sqlx::query(
+            "INSERT INTO users \
+             (username, password_hash, email, display_name, locked, avatar, token_version) \
+             VALUES (?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(&user.username)
+        .bind(&user.password_hash)
+        .bind(&user.email)
+        .bind(&user.display_name)
+        .bind(user.locked)
+        .bind(user.avatar.as_ref().map(FileId::as_str))
+        .bind(user.token_version)
+        .execute(&self.pool)
+        .await
+        .map_err(unique_violation)?;
+
+        Ok(user)
+    }
+
+    async fn update_profile(
+        &self,
+        username: &str,
+        email: Option<&str>,
+        display_name: Option<&str>,
+    ) -> Result<bool, UserStoreError> {
+        let result = sqlx::query("UPDATE users SET email = ?, display_name = ? WHERE username = ?")
+            .bind(email)
+            .bind(display_name)
+            .bind(username)
+            .execute(&self.pool)
+            .await
+            .map_err(unique_violation)?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn set_avatar(&self, username: &str, avatar: &FileId) -> anyhow::Result<bool> {
+        let result = sqlx::query("UPDATE users SET avatar = ? WHERE username = ?")
+            .bind(avatar.as_str())
+            .bind(username)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn set_password(
+        &self,
+        username: &str,
+        password_hash: &str,
+        token_version: i64,
+    ) -> anyhow::Result<bool> {
+        let result =
+            sqlx::query("UPDATE users SET password_hash = ?, token_version = ? WHERE username = ?")
+                .bind(password_hash)
+                .bind(token_version)
+                .bind(username)
+                .execute(&self.pool)
+                .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn delete(&self, username: &str) -> anyhow::Result<bool> {
+        let result = sqlx::query("DELETE FROM users WHERE username = ?")
+            .bind(username)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::check_store_contract;
+
+    fn user(username: &str, email: Option<&str>) -> User {
+        User {
+            username: username.to_string(),
+            password_hash: "hash".to_string(),
+            email: email.map(str::to_string),
+            display_name: None,
+            locked: false,
+            avatar: None,
+            token_version: 0,
+        }
+    }
+
+    // Kontrak UserStore: username dan email unik saat create maupun update,
+    // update/delete user yang tidak ada mengembalikan false
+    async fn exercise(store: &dyn UserStore) {
+        let aqil = store
+            .create(user("aqil", Some("aqil@example.com")))
+            .await
+            .unwrap();
+        store.create(user("budi", None)).await.unwrap();
+        store.create(user("citra", None)).await.unwrap();
+
+        assert!(matches!(
+            store.create(user("aqil", None)).await,
+            Err(UserStoreError::UsernameTaken)
+        ));
+        assert!(matches!(
+            store.create(user("dewi", Some("aqil@example.com"))).await,
+            Err(UserStoreError::EmailTaken)
+        ));
+        assert_eq!(store.find_by_username("aqil").await.unwrap(), Some(aqil));
+
+        // Tiap update hanya mengubah kolomnya sendiri
+        let avatar = FileId::generate();
+        assert!(store.set_password("budi", "hash baru", 3).await.unwrap());
+        assert!(store
+            .update_profile("budi", Some("budi@example.com"), Some("Budi"))
+            .await
+            .unwrap());
+        assert!(store.set_avatar("budi", &avatar).await.unwrap());
+        assert_eq!(
+            store.find_by_username("budi").await.unwrap(),
+            Some(User {
+                password_hash: "hash baru".to_string(),
+                display_name: Some("Budi".to_string()),
+                avatar: Some(avatar.clone()),
+                token_version: 3,
+                ..user("budi", Some("budi@example.com"))
+            })
+        );
+        assert!(matches!(
+            store
+                .update_profile("citra", Some("aqil@example.com"), None)
+                .await,
+            Err(UserStoreError::EmailTaken)
+        ));
+        assert!(!store.update_profile("dewi", None, None).await.unwrap());
+        assert!(!store.set_avatar("dewi", &avatar).await.unwrap());
+        assert!(!store.set_password("dewi", "hash", 1).await.unwrap());
+
+        assert!(store.delete("aqil").await.unwrap());
+        assert!(!store.delete("aqil").await.unwrap());
+        assert_eq!(store.find_by_username("aqil").await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_store_contract() {
+        let pool = crate::db::sqlite_memory().await;
+        check_store_contract::<dyn UserStore>(
+            vec![
+                ("in-memory", Box::new(InMemoryUserStore::new())),
+                ("sqlite", Box::new(SqliteUserStore::new(pool))),
+            ],
+            async |store| exercise(store).await,
+        )
+        .await;
+    }
+}
@@ -0,0 +1,168 @@
+use std::{
+    sync::Arc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use hmac::{Hmac, Mac};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+
+type HmacSha256 = Hmac<Sha256>;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Claims {
+    pub sub: String,
+    // User::token_version saat token dibuat
+    pub ver: i64,
+    pub iat: u64,
+    pub exp: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum TokenError {
+    #[error("token is malformed")]
+    Malformed,
+
+    #[error("token signature is invalid")]
+    InvalidSignature,
+
+    #[error("token has expired")]
+    Expired,
+}
+
+// Token berbentuk <claims base64url>.<hmac-sha256 base64url>
+#[derive(Clone)]
+pub struct TokenSigner {
+    key: Arc<[u8]>,
+    ttl: Duration,
+}
+
+impl TokenSigner {
+    pub fn new(key: &[u8], ttl: Duration) -> TokenSigner {
+        TokenSigner {
+            key: Arc::from(key),
+            ttl,
+        }
+    }
+
+    /// Signer with a random key, tokens do not survive a restart.
+    pub fn random(ttl: Duration) -> TokenSigner {
+        let mut key = [0u8; 32];
+        rand::thread_rng().fill_bytes(&mut key);
+        TokenSigner::new(&key, ttl)
+    }
+
+    pub fn ttl(&self) -> Duration {
+        self.ttl
+    }
+
+    pub fn issue(&self, subject: &str, version: i64) -> String {
+        self.issue_at(subject, version, now())
+    }
+
+    pub fn issue_at(&self, subject: &str, version: i64, now: u64) -> String {
+        let claims = Claims {
+            sub: subject.to_string(),
+            ver: version,
+            iat: now,
+            // ttl dari config sudah dibatasi, saturating_add menjaga TokenSigner yang dibuat langsung
+            exp: now.saturating_add(self.ttl.as_secs()),
+        };
+        let payload = URL_SAFE_NO_PAD.encode(serde_json::to_vec(&claims).unwrap());
+        let signature = URL_SAFE_NO_PAD.encode(self.sign(payload.as_bytes()));
+
+        format!("{}.{}", payload, signature)
+    }
+
+    pub fn verify(&self, token: &str) -> Result<Claims, TokenError> {
+        self.verify_at(token, now())
+    }
+
+    pub fn verify_at(&self, token: &str, now: u64) -> Result<Claims, TokenError> {
+        let (payload, signature) = token.split_once('.').ok_or(TokenError::Malformed)?;
+        let signature = URL_SAFE_NO_PAD
+            .decode(signature)
+            .map_err(|_| TokenError::Malformed)?;
+
+        // verify_slice membandingkan dalam constant time
+        self.mac(payload.as_bytes())
+            .verify_slice(&signature)
+            .map_err(|_| TokenError::InvalidSignature)?;
+
+        let payload = URL_SAFE_NO_PAD
+            .decode(payload)
+            .map_err(|_| TokenError::Malformed)?;
+        let claims: Claims = serde_json::from_slice(&payload).map_err(|_| TokenError::Malformed)?;
+
+        if claims.exp <= now {
+            return Err(TokenError::Expired);
+        }
+
+        Ok(claims)
+    }
+
+    fn mac(&self, data: &[u8]) -> HmacSha256 {
+        let mut mac = HmacSha256::new_from_slice(&self.key).expect("HMAC accepts any key length");
+        mac.update(data);
+        mac
+    }
+
+    fn sign(&self, data: &[u8]) -> Vec<u8> {
+        self.mac(data).finalize().into_bytes().to_vec()
+    }
+}
+
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_issue_and_verify() {
+        let signer = TokenSigner::new(b"secret", Duration::from_secs(60));
+        let token = signer.issue_at("aqil", 7, 1_000);
+
+        let claims = signer.verify_at(&token, 1_030).unwrap();
+        assert_eq!(claims.sub, "aqil");
+        assert_eq!(claims.ver, 7);
+        assert_eq!(claims.exp, 1_060);
+    }
+
+    #[test]
+    fn test_expired_token() {
+        let signer = TokenSigner::new(b"secret", Duration::from_secs(60));
+        let token = signer.issue_at("aqil", 0, 1_000);
+
+        assert_eq!(signer.verify_at(&token, 1_060), Err(TokenError::Expired));
+    }
+
+    // ttl yang terlalu besar tidak membuat exp overflow menjadi token kedaluwarsa
+    #[test]
+    fn test_large_ttl() {
+        let signer = TokenSigner::new(b"secret", Duration::MAX);
+        let token = signer.issue_at("aqil", 0, 1_000);
+
+        assert_eq!(signer.verify_at(&token, 1_000).unwrap().exp, u64::MAX);
+    }
+
+    #[test]
+    fn test_invalid_token() {
+        let signer = TokenSigner::new(b"secret", Duration::from_secs(60));
+        let token = signer.issue_at("aqil", 0, 1_000);
+
+        let other = TokenSigner::new(b"other", Duration::from_secs(60));
+        assert_eq!(
+            other.verify_at(&token, 1_000),
+            Err(TokenError::InvalidSignature)
+        );
+        assert_eq!(signer.verify_at("token", 1_000), Err(TokenError::Malformed));
+    }
+}
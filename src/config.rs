@@ -0,0 +1,1407 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs,
+    net::{IpAddr, SocketAddr},
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use clap::Parser;
+use serde::Deserialize;
+
+use crate::{logging::LogFormat, rbac::Permission};
+
+// Prefix untuk environment variable, contoh: APP_PORT=8080
+pub const ENV_PREFIX: &str = "APP_";
+
+// Batas atas durasi dari config dalam detik (satu tahun), agar penjumlahan dengan
+// waktu sekarang tidak overflow
+pub const MAX_DURATION: u64 = 365 * 24 * 60 * 60;
+
+#[derive(Debug, thiserror::Error)]
+pub enum ConfigError {
+    #[error("failed to read config file {path}: {source}")]
+    Read {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+
+    #[error("failed to parse config file {path}: {source}")]
+    Parse {
+        path: PathBuf,
+        source: toml::de::Error,
+    },
+
+    #[error("invalid value {value:?} for {key}: {reason}")]
+    Env {
+        key: String,
+        value: String,
+        reason: String,
+    },
+
+    #[error("invalid configuration for {field}: {reason}")]
+    Invalid { field: &'static str, reason: String },
+}
+
+// Command line flags, memiliki prioritas paling tinggi
+#[derive(Debug, Default, Clone, Parser)]
+#[command(name = "rust-axum-web", version, about)]
+pub struct Cli {
+    /// Path to a TOML configuration file
+    #[arg(long, short = 'c')]
+    pub config: Option<PathBuf>,
+
+    /// Address to bind, e.g. 0.0.0.0
+    #[arg(long)]
+    pub host: Option<String>,
+
+    /// Port to listen on
+    #[arg(long, short = 'p')]
+    pub port: Option<u16>,
+
+    /// Number of tokio worker threads
+    #[arg(long)]
+    pub workers: Option<usize>,
+
+    /// Seconds to wait for in-flight requests on shutdown
+    #[arg(long)]
+    pub drain_timeout: Option<u64>,
+
+    /// Log output format
+    #[arg(long, value_enum)]
+    pub log_format: Option<LogFormat>,
+
+    /// Log level or filter directive, e.g. info or rust_axum_web=debug
+    #[arg(long)]
+    pub log_level: Option<String>,
+
+    /// Database connection URL, e.g. sqlite://data.db?mode=rwc
+    #[arg(long)]
+    pub database_url: Option<String>,
+
+    /// Enable or disable request logging
+    #[arg(long)]
+    pub request_logging: Option<bool>,
+
+    /// Enable or disable the /metrics endpoint
+    #[arg(long)]
+    pub metrics: Option<bool>,
+
+    /// Apply pending database migrations on startup
+    #[arg(long)]
+    pub auto_migrate: Option<bool>,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, clap::Subcommand)]
+pub enum Command {
+    /// Manage database schema migrations
+    Migrate {
+        #[command(subcommand)]
+        action: MigrateAction,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::Subcommand)]
+pub enum MigrateAction {
+    /// Apply all pending migrations
+    Up,
+    /// Revert the most recently applied migration
+    Down,
+    /// List migrations and whether they are applied
+    Status,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub server: ServerConfig,
+    pub auth: AuthConfig,
+    pub database: DatabaseConfig,
+    pub session: SessionConfig,
+    pub cookie: CookieConfig,
+    pub upload: UploadConfig,
+    pub rbac: RbacConfig,
+    pub rate_limit: RateLimitConfig,
+    pub lockout: LockoutConfig,
+    pub cors: CorsConfig,
+    pub security_headers: SecurityHeadersConfig,
+    pub logging: LoggingConfig,
+    pub health: HealthConfig,
+    pub features: FeaturesConfig,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerConfig {
+    pub host: String,
+    pub port: u16,
+    // None berarti mengikuti jumlah CPU
+    pub workers: Option<usize>,
+    // batas waktu (detik) menunggu request yang sedang berjalan saat shutdown
+    pub drain_timeout: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AuthConfig {
+    // kosong berarti key acak, token tidak berlaku lagi setelah restart
+    pub token_secret: Option<String>,
+    // masa berlaku token dalam detik
+    pub token_ttl: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DatabaseConfig {
+    // skema URL harus sesuai backend yang di-compile (sqlite atau postgres)
+    pub url: String,
+    pub max_connections: u32,
+    pub min_connections: u32,
+    // batas waktu (detik) menunggu koneksi dari pool
+    pub acquire_timeout: u64,
+    // jalankan migration yang tertunda saat server start
+    pub auto_migrate: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SessionConfig {
+    pub cookie_name: String,
+    // masa berlaku session dalam detik
+    pub ttl: u64,
+    // false hanya untuk development tanpa HTTPS
+    pub secure: bool,
+    pub same_site: SameSitePolicy,
+    // jarak (detik) antar pembersihan session kedaluwarsa dari store
+    pub purge_interval: u64,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CookieConfig {
+    // key untuk cookie signed/private, kosong berarti key acak
+    pub key: Option<String>,
+    // key lama yang masih diterima saat verifikasi, cookie baru memakai key
+    pub previous_keys: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct UploadConfig {
+    // direktori untuk LocalFileStorage
+    pub dir: PathBuf,
+    // ukuran maksimal satu file dalam byte
+    pub max_size: u64,
+    // dicocokkan dengan tipe hasil sniffing magic bytes, bukan Content-Type dari client
+    pub allowed_types: Vec<String>,
+    // sisi terpanjang thumbnail dalam pixel, dibuat di background setelah upload foto profil
+    pub thumbnail_sizes: Vec<u32>,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RbacConfig {
+    // role -> permission, contoh: editor = ["products:write"]; role admin selalu memiliki "*"
+    pub roles: BTreeMap<String, Vec<Permission>>,
+    // user yang selalu memiliki role admin, untuk meng-assign role pertama kali
+    pub admins: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RateLimitConfig {
+    pub enabled: bool,
+    // header berisi API key, dipakai oleh policy dengan key = "api_key"
+    pub api_key_header: String,
+    // API key yang dikenal; key lain dihitung per IP agar header acak tidak membuat kuota baru
+    pub api_keys: Vec<String>,
+    // batas jumlah kunci di store in-memory, kunci yang paling lama penuh dibuang lebih dulu
+    pub max_keys: usize,
+    // policy untuk route yang tidak ada di routes, kosong berarti tidak dibatasi
+    pub default: Option<RateLimitPolicy>,
+    // policy per template route, contoh: "/api/auth/login"
+    pub routes: BTreeMap<String, RateLimitPolicy>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RateLimitPolicy {
+    // jumlah request per period, sekaligus ukuran burst
+    pub requests: u32,
+    // panjang window dalam detik
+    pub period: u64,
+    #[serde(default)]
+    pub key: RateLimitKey,
+}
+
+// Dasar pengelompokan request; user dan api_key kembali ke IP bila tidak tersedia
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RateLimitKey {
+    #[default]
+    Ip,
+    User,
+    ApiKey,
+}
+
+// Perlindungan brute-force login, dihitung per username dan per IP
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LockoutConfig {
+    pub enabled: bool,
+    // jumlah gagal login tanpa jeda sebelum backoff dimulai
+    pub free_attempts: u32,
+    // jeda backoff pertama dalam detik, berlipat dua setiap kegagalan berikutnya
+    pub base_delay: u64,
+    pub max_delay: u64,
+    // jumlah gagal sampai username dikunci sementara
+    pub username_threshold: u32,
+    // jumlah gagal dari satu IP sampai IP dikunci sementara, lebih longgar karena NAT
+    pub ip_threshold: u32,
+    // lama kunci dalam detik; counter juga direset bila tidak ada kegagalan selama ini
+    pub duration: u64,
+    // batas jumlah username dan IP di store in-memory, riwayat paling lama dibuang lebih dulu
+    pub max_keys: usize,
+}
+
+// CORS untuk frontend di origin lain; origins kosong berarti CORS tidak diaktifkan
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CorsConfig {
+    // "*", origin persis seperti "https://app.example.com", atau "https://*.example.com"
+    pub origins: Vec<String>,
+    pub methods: Vec<String>,
+    // header request yang boleh dikirim client
+    pub headers: Vec<String>,
+    // header response yang boleh dibaca JavaScript di browser
+    pub expose_headers: Vec<String>,
+    // izinkan cookie dan header Authorization, tidak bisa digabung dengan origin "*"
+    pub credentials: bool,
+    // lama browser menyimpan hasil preflight dalam detik
+    pub max_age: u64,
+}
+
+// Header keamanan untuk semua response; Content-Security-Policy diatur lewat security::ContentSecurityPolicy
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SecurityHeadersConfig {
+    pub enabled: bool,
+    // max-age Strict-Transport-Security dalam detik, 0 berarti header tidak dikirim
+    pub hsts_max_age: u64,
+    pub hsts_include_subdomains: bool,
+    // hanya aktifkan setelah domain siap didaftarkan ke hstspreload.org
+    pub hsts_preload: bool,
+    pub frame_options: FrameOptions,
+    // kosong berarti header tidak dikirim
+    pub referrer_policy: String,
+    // contoh: "camera=(), microphone=()"; kosong berarti header tidak dikirim
+    pub permissions_policy: String,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FrameOptions {
+    #[default]
+    Deny,
+    SameOrigin,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SameSitePolicy {
+    Strict,
+    #[default]
+    Lax,
+    None,
+}
+
+impl From<SameSitePolicy> for cookie::SameSite {
+    fn from(policy: SameSitePolicy) -> Self {
+        match policy {
+            SameSitePolicy::Strict => cookie::SameSite::Strict,
+            SameSitePolicy::Lax => cookie::SameSite::Lax,
+            SameSitePolicy::None => cookie::SameSite::None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LoggingConfig {
+    pub format: LogFormat,
+    pub level: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HealthConfig {
+    // batas waktu setiap health check dalam milidetik
+    pub timeout_ms: u64,
+    pub disk_path: PathBuf,
+    // 0 berarti cek disk dimatikan
+    pub min_free_disk_mb: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FeaturesConfig {
+    pub request_logging: bool,
+    pub metrics: bool,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        ServerConfig {
+            host: "127.0.0.1".to_string(),
+            port: 3000,
+            workers: None,
+            drain_timeout: 30,
+        }
+    }
+}
+
+impl Default for AuthConfig {
+    fn default() -> Self {
+        AuthConfig {
+            token_secret: None,
+            token_ttl: 3600,
+        }
+    }
+}
+
+impl Default for DatabaseConfig {
+    fn default() -> Self {
+        DatabaseConfig {
+            url: if cfg!(feature = "postgres") {
+                "postgres://localhost/rust_axum_web".to_string()
+            } else {
+                "sqlite::memory:".to_string()
+            },
+            max_connections: 5,
+            min_connections: 0,
+            acquire_timeout: 5,
+            auto_migrate: true,
+        }
+    }
+}
+
+impl Default for SessionConfig {
+    fn default() -> Self {
+        SessionConfig {
+            cookie_name: "session_id".to_string(),
+            ttl: 86400,
+            secure: true,
+            same_site: SameSitePolicy::Lax,
+            purge_interval: 600,
+        }
+    }
+}
+
+impl Default for UploadConfig {
+    fn default() -> Self {
+        UploadConfig {
+            dir: PathBuf::from("uploads"),
+            max_size: 5 * 1024 * 1024,
+            allowed_types: crate::files::SNIFFABLE_TYPES
+                .iter()
+                .map(|content_type| content_type.to_string())
+                .collect(),
+            thumbnail_sizes: vec![64, 128, 256],
+        }
+    }
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        RateLimitConfig {
+            enabled: true,
+            api_key_header: "x-api-key".to_string(),
+            api_keys: Vec::new(),
+            max_keys: 100_000,
+            default: None,
+            routes: BTreeMap::from([(
+                "/api/auth/login".to_string(),
+                RateLimitPolicy {
+                    requests: 10,
+                    period: 60,
+                    key: RateLimitKey::Ip,
+                },
+            )]),
+        }
+    }
+}
+
+impl Default for LockoutConfig {
+    fn default() -> Self {
+        LockoutConfig {
+            enabled: true,
+            free_attempts: 3,
+            base_delay: 1,
+            max_delay: 60,
+            username_threshold: 10,
+            ip_threshold: 50,
+            duration: 900,
+            max_keys: 100_000,
+        }
+    }
+}
+
+impl Default for CorsConfig {
+    fn default() -> Self {
+        CorsConfig {
+            origins: Vec::new(),
+            methods: ["GET", "POST", "PUT", "PATCH", "DELETE"]
+                .map(str::to_string)
+                .to_vec(),
+            headers: ["authorization", "content-type", "x-request-id"]
+                .map(str::to_string)
+                .to_vec(),
+            expose_headers: ["x-request-id", "retry-after"].map(str::to_string).to_vec(),
+            credentials: false,
+            max_age: 600,
+        }
+    }
+}
+
+impl Default for SecurityHeadersConfig {
+    fn default() -> Self {
+        SecurityHeadersConfig {
+            enabled: true,
+            hsts_max_age: 31_536_000,
+            hsts_include_subdomains: true,
+            hsts_preload: false,
+            frame_options: FrameOptions::Deny,
+            referrer_policy: "strict-origin-when-cross-origin".to_string(),
+            permissions_policy: "camera=(), microphone=(), geolocation=(), payment=()".to_string(),
+        }
+    }
+}
+
+impl Default for LoggingConfig {
+    fn default() -> Self {
+        LoggingConfig {
+            format: LogFormat::Pretty,
+            level: "info".to_string(),
+        }
+    }
+}
+
+impl Default for HealthConfig {
+    fn default() -> Self {
+        HealthConfig {
+            timeout_ms: 2000,
+            disk_path: PathBuf::from("."),
+            min_free_disk_mb: 100,
+        }
+    }
+}
+
+impl Default for FeaturesConfig {
+    fn default() -> Self {
+        FeaturesConfig {
+            request_logging: true,
+            metrics: true,
+        }
+    }
+}
+
+impl Config {
+    /// Loads the configuration from the process environment.
+    ///
+    /// Precedence, highest first: CLI flags, `APP_*` environment variables,
+    /// the TOML file (`--config` or `APP_CONFIG`), built-in defaults.
+    pub fn load(cli: &Cli) -> Result<Config, ConfigError> {
+        Config::from_sources(cli, std::env::vars())
+    }
+
+    pub fn from_sources<I>(cli: &Cli, env: I) -> Result<Config, ConfigError>
+    where
+        I: IntoIterator<Item = (String, String)>,
+    {
+        let env: HashMap<String, String> = env
+            .into_iter()
+            .filter_map(|(key, value)| {
+                key.strip_prefix(ENV_PREFIX)
+                    .map(|key| (key.to_ascii_uppercase(), value))
+            })
+            .collect();
+
+        let file = cli
+            .config
+            .clone()
+            .or_else(|| env.get("CONFIG").map(PathBuf::from));
+
+        let mut config = match file {
+            Some(path) => Config::from_file(&path)?,
+            None => Config::default(),
+        };
+
+        config.apply_env(&env)?;
+        config.apply_cli(cli);
+        config.validate()?;
+
+        Ok(config)
+    }
+
+    pub fn from_file(path: &Path) -> Result<Config, ConfigError> {
+        let content = fs::read_to_string(path).map_err(|source| ConfigError::Read {
+            path: path.to_path_buf(),
+            source,
+        })?;
+
+        toml::from_str(&content).map_err(|source| ConfigError::Parse {
+            path: path.to_path_buf(),
+            source,
+        })
+    }
+
+    fn apply_env(&mut self, env: &HashMap<String, String>) -> Result<(), ConfigError> {
+        if let Some(host) = env.get("HOST") {
+            self.server.host = host.clone();
+        }
+        if let Some(port) = env.get("PORT") {
+            self.server.port = parse_env("PORT", port)?;
+        }
+        if let Some(workers) = env.get("WORKERS") {
+            self.server.workers = Some(parse_env("WORKERS", workers)?);
+        }
+        if let Some(drain_timeout) = env.get("DRAIN_TIMEOUT") {
+            self.server.drain_timeout = parse_env("DRAIN_TIMEOUT", drain_timeout)?;
+        }
+        if let Some(token_secret) = env.get("TOKEN_SECRET") {
+            self.auth.token_secret = Some(token_secret.clone());
+        }
+        if let Some(token_ttl) = env.get("TOKEN_TTL") {
+            self.auth.token_ttl = parse_env("TOKEN_TTL", token_ttl)?;
+        }
+        if let Some(url) = env.get("DATABASE_URL") {
+            self.database.url = url.clone();
+        }
+        if let Some(max_connections) = env.get("DATABASE_MAX_CONNECTIONS") {
+            self.database.max_connections = parse_env("DATABASE_MAX_CONNECTIONS", max_connections)?;
+        }
+        if let Some(auto_migrate) = env.get("AUTO_MIGRATE") {
+            self.database.auto_migrate = parse_env("AUTO_MIGRATE", auto_migrate)?;
+        }
+        if let Some(ttl) = env.get("SESSION_TTL") {
+            self.session.ttl = parse_env("SESSION_TTL", ttl)?;
+        }
+        if let Some(secure) = env.get("SESSION_SECURE") {
+            self.session.secure = parse_env("SESSION_SECURE", secure)?;
+        }
+        if let Some(interval) = env.get("SESSION_PURGE_INTERVAL") {
+            self.session.purge_interval = parse_env("SESSION_PURGE_INTERVAL", interval)?;
+        }
+        if let Some(key) = env.get("COOKIE_KEY") {
+            self.cookie.key = Some(key.clone());
+        }
+        if let Some(keys) = env.get("COOKIE_PREVIOUS_KEYS") {
+            // dipisah koma, contoh: APP_COOKIE_PREVIOUS_KEYS=key-lama-1,key-lama-2
+            self.cookie.previous_keys = keys
+                .split(',')
+                .map(str::trim)
+                .filter(|key| !key.is_empty())
+                .map(str::to_string)
+                .collect();
+        }
+        if let Some(dir) = env.get("UPLOAD_DIR") {
+            self.upload.dir = PathBuf::from(dir);
+        }
+        if let Some(max_size) = env.get("UPLOAD_MAX_SIZE") {
+            self.upload.max_size = parse_env("UPLOAD_MAX_SIZE", max_size)?;
+        }
+        if let Some(admins) = env.get("RBAC_ADMINS") {
+            // dipisah koma, contoh: APP_RBAC_ADMINS=aqil,budi
+            self.rbac.admins = admins
+                .split(',')
+                .map(str::trim)
+                .filter(|admin| !admin.is_empty())
+                .map(str::to_string)
+                .collect();
+        }
+        if let Some(enabled) = env.get("RATE_LIMIT_ENABLED") {
+            self.rate_limit.enabled = parse_env("RATE_LIMIT_ENABLED", enabled)?;
+        }
+        if let Some(api_keys) = env.get("RATE_LIMIT_API_KEYS") {
+            // dipisah koma, contoh: APP_RATE_LIMIT_API_KEYS=kunci-a,kunci-b
+            self.rate_limit.api_keys = api_keys
+                .split(',')
+                .map(str::trim)
+                .filter(|api_key| !api_key.is_empty())
+                .map(str::to_string)
+                .collect();
+        }
+        if let Some(enabled) = env.get("LOCKOUT_ENABLED") {
+            self.lockout.enabled = parse_env("LOCKOUT_ENABLED", enabled)?;
+        }
+        if let Some(origins) = env.get("CORS_ORIGINS") {
+            // dipisah koma, contoh: APP_CORS_ORIGINS=https://app.example.com,https://*.example.com
+            self.cors.origins = origins
+                .split(',')
+                .map(str::trim)
+                .filter(|origin| !origin.is_empty())
+                .map(str::to_string)
+                .collect();
+        }
+        if let Some(credentials) = env.get("CORS_CREDENTIALS") {
+            self.cors.credentials = parse_env("CORS_CREDENTIALS", credentials)?;
+        }
+        if let Some(enabled) = env.get("SECURITY_HEADERS_ENABLED") {
+            self.security_headers.enabled = parse_env("SECURITY_HEADERS_ENABLED", enabled)?;
+        }
+        if let Some(format) = env.get("LOG_FORMAT") {
+            self.logging.format = parse_env("LOG_FORMAT", format)?;
+        }
+        if let Some(level) = env.get("LOG_LEVEL") {
+            self.logging.level = level.clone();
+        }
+        if let Some(timeout_ms) = env.get("HEALTH_TIMEOUT_MS") {
+            self.health.timeout_ms = parse_env("HEALTH_TIMEOUT_MS", timeout_ms)?;
+        }
+        if let Some(min_free_disk_mb) = env.get("MIN_FREE_DISK_MB") {
+            self.health.min_free_disk_mb = parse_env("MIN_FREE_DISK_MB", min_free_disk_mb)?;
+        }
+        if let Some(request_logging) = env.get("REQUEST_LOGGING") {
+            self.features.request_logging = parse_env("REQUEST_LOGGING", request_logging)?;
+        }
+        if let Some(metrics) = env.get("METRICS") {
+            self.features.metrics = parse_env("METRICS", metrics)?;
+        }
+
+        Ok(())
+    }
+
+    fn apply_cli(&mut self, cli: &Cli) {
+        if let Some(host) = &cli.host {
+            self.server.host = host.clone();
+        }
+        if let Some(port) = cli.port {
+            self.server.port = port;
+        }
+        if let Some(workers) = cli.workers {
+            self.server.workers = Some(workers);
+        }
+        if let Some(drain_timeout) = cli.drain_timeout {
+            self.server.drain_timeout = drain_timeout;
+        }
+        if let Some(url) = &cli.database_url {
+            self.database.url = url.clone();
+        }
+        if let Some(auto_migrate) = cli.auto_migrate {
+            self.database.auto_migrate = auto_migrate;
+        }
+        if let Some(format) = cli.log_format {
+            self.logging.format = format;
+        }
+        if let Some(level) = &cli.log_level {
+            self.logging.level = level.clone();
+        }
+        if let Some(request_logging) = cli.request_logging {
+            self.features.request_logging = request_logging;
+        }
+        if let Some(metrics) = cli.metrics {
+            self.features.metrics = metrics;
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        self.server.socket_addr()?;
+
+        if self.server.workers == Some(0) {
+            return Err(ConfigError::Invalid {
+                field: "server.workers",
+                reason: "must be at least 1".to_string(),
+            });
+        }
+
+        crate::db::connect_options(&self.database.url).map_err(|error| ConfigError::Invalid {
+            field: "database.url",
+            reason: error.to_string(),
+        })?;
+
+        if self.database.max_connections == 0
+            || self.database.min_connections > self.database.max_connections
+        {
+            return Err(ConfigError::Invalid {
+                field: "database.max_connections",
+                reason: "must be at least 1 and not less than database.min_connections".to_string(),
+            });
+        }
+
+        let cookie_name = &self.session.cookie_name;
+        if cookie_name.is_empty()
+            || !cookie_name
+                .bytes()
+                .all(|byte| byte.is_ascii_alphanumeric() || b"_-.".contains(&byte))
+        {
+            return Err(ConfigError::Invalid {
+                field: "session.cookie_name",
+                reason: "must be a non-empty token of [A-Za-z0-9_.-]".to_string(),
+            });
+        }
+
+        if self.session.ttl == 0 || self.session.ttl > MAX_DURATION {
+            return Err(ConfigError::Invalid {
+                field: "session.ttl",
+                reason: format!("must be between 1 and {} seconds", MAX_DURATION),
+            });
+        }
+
+        if self.session.purge_interval == 0 {
+            return Err(ConfigError::Invalid {
+                field: "session.purge_interval",
+                reason: "must be at least 1 second".to_string(),
+            });
+        }
+
+        // browser menolak SameSite=None tanpa Secure
+        if self.session.same_site == SameSitePolicy::None && !self.session.secure {
+            return Err(ConfigError::Invalid {
+                field: "session.same_site",
+                reason: "none requires session.secure = true".to_string(),
+            });
+        }
+
+        // Key::derive_from membutuhkan minimal 32 byte
+        let cookie_keys = self.cookie.key.iter().chain(&self.cookie.previous_keys);
+        for key in cookie_keys {
+            if key.len() < 32 {
+                return Err(ConfigError::Invalid {
+                    field: "cookie.key",
+                    reason: "every cookie key must be at least 32 bytes".to_string(),
+                });
+            }
+        }
+
+        if self.cookie.key.is_none() && !self.cookie.previous_keys.is_empty() {
+            return Err(ConfigError::Invalid {
+                field: "cookie.previous_keys",
+                reason: "requires cookie.key to be set".to_string(),
+            });
+        }
+
+        if self.upload.max_size == 0 {
+            return Err(ConfigError::Invalid {
+                field: "upload.max_size",
+                reason: "must be at least 1 byte".to_string(),
+            });
+        }
+
+        if let Some(content_type) =
+            self.upload.allowed_types.iter().find(|content_type| {
+                !crate::files::SNIFFABLE_TYPES.contains(&content_type.as_str())
+            })
+        {
+            return Err(ConfigError::Invalid {
+                field: "upload.allowed_types",
+                reason: format!(
+                    "{} cannot be detected, supported types are {}",
+                    content_type,
+                    crate::files::SNIFFABLE_TYPES.join(", ")
+                ),
+            });
+        }
+
+        if self
+            .upload
+            .thumbnail_sizes
+            .iter()
+            .any(|size| !(1..=1024).contains(size))
+        {
+            return Err(ConfigError::Invalid {
+                field: "upload.thumbnail_sizes",
+                reason: "every size must be between 1 and 1024 pixels".to_string(),
+            });
+        }
+
+        if let Some(role) = self
+            .rbac
+            .roles
+            .keys()
+            .find(|role| !crate::rbac::valid_role(role) || *role == crate::rbac::ADMIN_ROLE)
+        {
+            return Err(ConfigError::Invalid {
+                field: "rbac.roles",
+                reason: format!(
+                    "invalid role {:?}, roles must match [a-z0-9_-] and admin is built in",
+                    role
+                ),
+            });
+        }
+
+        if http::HeaderName::try_from(&self.rate_limit.api_key_header).is_err() {
+            return Err(ConfigError::Invalid {
+                field: "rate_limit.api_key_header",
+                reason: "must be a valid header name".to_string(),
+            });
+        }
+
+        if self.rate_limit.max_keys == 0 {
+            return Err(ConfigError::Invalid {
+                field: "rate_limit.max_keys",
+                reason: "must be at least 1".to_string(),
+            });
+        }
+
+        if let Some(policy) = &self.rate_limit.default {
+            validate_policy("rate_limit.default", policy)?;
+        }
+        for policy in self.rate_limit.routes.values() {
+            validate_policy("rate_limit.routes", policy)?;
+        }
+
+        if let Some(route) = self
+            .rate_limit
+            .routes
+            .keys()
+            .find(|route| !route.starts_with('/'))
+        {
+            return Err(ConfigError::Invalid {
+                field: "rate_limit.routes",
+                reason: format!("route {:?} must start with /", route),
+            });
+        }
+
+        let lockout = &self.lockout;
+        if lockout.username_threshold <= lockout.free_attempts
+            || lockout.ip_threshold <= lockout.free_attempts
+        {
+            return Err(ConfigError::Invalid {
+                field: "lockout.username_threshold",
+                reason: "thresholds must be greater than lockout.free_attempts".to_string(),
+            });
+        }
+
+        if lockout.max_keys == 0 {
+            return Err(ConfigError::Invalid {
+                field: "lockout.max_keys",
+                reason: "must be at least 1".to_string(),
+            });
+        }
+
+        let durations = [
+            ("lockout.base_delay", lockout.base_delay),
+            ("lockout.max_delay", lockout.max_delay),
+            ("lockout.duration", lockout.duration),
+        ];
+        for (field, seconds) in durations {
+            if seconds == 0 || seconds > MAX_DURATION {
+                return Err(ConfigError::Invalid {
+                    field,
+                    reason: format!("must be between 1 and {} seconds", MAX_DURATION),
+                });
+            }
+        }
+
+        if lockout.max_delay < lockout.base_delay {
+            return Err(ConfigError::Invalid {
+                field: "lockout.max_delay",
+                reason: "must not be less than lockout.base_delay".to_string(),
+            });
+        }
+
+        for origin in &self.cors.origins {
+            origin
+                .parse::<crate::cors::OriginPattern>()
+                .map_err(|reason| ConfigError::Invalid {
+                    field: "cors.origins",
+                    reason,
+                })?;
+        }
+
+        if self.cors.credentials && self.cors.origins.iter().any(|origin| origin == "*") {
+            return Err(ConfigError::Invalid {
+                field: "cors.credentials",
+                reason: "cannot be combined with origin \"*\", list the origins instead"
+                    .to_string(),
+            });
+        }
+
+        if let Some(method) = self
+            .cors
+            .methods
+            .iter()
+            .find(|method| http::Method::from_bytes(method.as_bytes()).is_err())
+        {
+            return Err(ConfigError::Invalid {
+                field: "cors.methods",
+                reason: format!("{:?} is not a valid HTTP method", method),
+            });
+        }
+
+        if let Some(header) = self
+            .cors
+            .headers
+            .iter()
+            .chain(&self.cors.expose_headers)
+            .find(|header| http::HeaderName::from_bytes(header.as_bytes()).is_err())
+        {
+            return Err(ConfigError::Invalid {
+                field: "cors.headers",
+                reason: format!("{:?} is not a valid header name", header),
+            });
+        }
+
+        let security = &self.security_headers;
+        if !REFERRER_POLICIES.contains(&security.referrer_policy.as_str()) {
+            return Err(ConfigError::Invalid {
+                field: "security_headers.referrer_policy",
+                reason: format!(
+                    "must be empty or one of {}",
+                    REFERRER_POLICIES[1..].join(", ")
+                ),
+            });
+        }
+
+        if http::HeaderValue::from_str(&security.permissions_policy).is_err() {
+            return Err(ConfigError::Invalid {
+                field: "security_headers.permissions_policy",
+                reason: "must be a valid header value".to_string(),
+            });
+        }
+
+        // syarat daftar preload browser
+        if security.hsts_preload
+            && (!security.hsts_include_subdomains || security.hsts_max_age < 31_536_000)
+        {
+            return Err(ConfigError::Invalid {
+                field: "security_headers.hsts_preload",
+                reason: "requires hsts_include_subdomains and hsts_max_age of at least 31536000"
+                    .to_string(),
+            });
+        }
+
+        tracing_subscriber::EnvFilter::try_new(&self.logging.level).map_err(|error| {
+            ConfigError::Invalid {
+                field: "logging.level",
+                reason: error.to_string(),
+            }
+        })?;
+
+        if self.auth.token_ttl == 0 || self.auth.token_ttl > MAX_DURATION {
+            return Err(ConfigError::Invalid {
+                field: "auth.token_ttl",
+                reason: format!("must be between 1 and {} seconds", MAX_DURATION),
+            });
+        }
+
+        if matches!(&self.auth.token_secret, Some(secret) if secret.len() < 32) {
+            return Err(ConfigError::Invalid {
+                field: "auth.token_secret",
+                reason: "must be at least 32 bytes".to_string(),
+            });
+        }
+
+        Ok(())
+    }
+}
+
+impl HealthConfig {
+    pub fn timeout(&self) -> Duration {
+        Duration::from_millis(self.timeout_ms)
+    }
+}
+
+impl DatabaseConfig {
+    pub fn acquire_timeout(&self) -> Duration {
+        Duration::from_secs(self.acquire_timeout)
+    }
+}
+
+impl SessionConfig {
+    pub fn ttl(&self) -> Duration {
+        Duration::from_secs(self.ttl)
+    }
+
+    pub fn purge_interval(&self) -> Duration {
+        Duration::from_secs(self.purge_interval)
+    }
+}
+
+impl RateLimitPolicy {
+    pub fn period(&self) -> Duration {
+        Duration::from_secs(self.period)
+    }
+}
+
+// nilai Referrer-Policy yang dikenal browser, "" berarti header tidak dikirim
+const REFERRER_POLICIES: &[&str] = &[
+    "",
+    "no-referrer",
+    "no-referrer-when-downgrade",
+    "origin",
+    "origin-when-cross-origin",
+    "same-origin",
+    "strict-origin",
+    "strict-origin-when-cross-origin",
+    "unsafe-url",
+];
+
+impl LockoutConfig {
+    pub fn duration(&self) -> Duration {
+        Duration::from_secs(self.duration)
+    }
+
+    /// Backoff after the given number of consecutive failures, zero while within `free_attempts`.
+    pub fn delay(&self, failures: u32) -> Duration {
+        let Some(doublings) = failures.checked_sub(self.free_attempts + 1) else {
+            return Duration::ZERO;
+        };
+        let delay = self
+            .base_delay
+            .saturating_mul(1u64.checked_shl(doublings).unwrap_or(u64::MAX));
+        Duration::from_secs(delay.min(self.max_delay))
+    }
+}
+
+impl AuthConfig {
+    pub fn token_ttl(&self) -> Duration {
+        Duration::from_secs(self.token_ttl)
+    }
+}
+
+impl ServerConfig {
+    pub fn socket_addr(&self) -> Result<SocketAddr, ConfigError> {
+        let ip: IpAddr = self
+            .host
+            .parse()
+            .map_err(|error: std::net::AddrParseError| ConfigError::Invalid {
+                field: "server.host",
+                reason: error.to_string(),
+            })?;
+
+        Ok(SocketAddr::new(ip, self.port))
+    }
+
+    pub fn drain_timeout(&self) -> Duration {
+        Duration::from_secs(self.drain_timeout)
+    }
+}
+
+fn validate_policy(field: &'static str, policy: &RateLimitPolicy) -> Result<(), ConfigError> {
+    if policy.requests == 0 || policy.period == 0 {
+        return Err(ConfigError::Invalid {
+            field,
+            reason: "requests and period must be at least 1".to_string(),
+        });
+    }
+    if policy.period > MAX_DURATION {
+        return Err(ConfigError::Invalid {
+            field,
+            reason: format!("period must be at most {} seconds", MAX_DURATION),
+        });
+    }
+    Ok(())
+}
+
+fn parse_env<T>(key: &str, value: &str) -> Result<T, ConfigError>
+where
+    T: std::str::FromStr,
+    T::Err: std::fmt::Display,
+{
+    value
+        .trim()
+        .parse()
+        .map_err(|error: T::Err| ConfigError::Env {
+            key: format!("{}{}", ENV_PREFIX, key),
+            value: value.to_string(),
+            reason: error.to_string(),
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use super::*;
+
+    fn env(vars: &[(&str, &str)]) -> Vec<(String, String)> {
+        vars.iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect()
+    }
+
+    fn config_file(name: &str, content: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!(
+            "rust-axum-web-{}-{}.toml",
+            std::process::id(),
+            name
+        ));
+        let mut file = fs::File::create(&path).unwrap();
+        file.write_all(content.as_bytes()).unwrap();
+        path
+    }
+
+    // Default
+    #[test]
+    fn test_default_config() {
+        let config = Config::from_sources(&Cli::default(), env(&[])).unwrap();
+
+        assert_eq!(config, Config::default());
+        assert_eq!(
+            config.server.socket_addr().unwrap(),
+            "127.0.0.1:3000".parse().unwrap()
+        );
+    }
+
+    // Precedence: cli > env > file > default
+    #[test]
+    fn test_config_precedence() {
+        let path = config_file(
+            "precedence",
+            "[server]\nhost = \"0.0.0.0\"\nport = 8000\nworkers = 2\ndrain_timeout = 5\n\n[features]\nrequest_logging = false\n",
+        );
+
+        let cli = Cli {
+            config: Some(path.clone()),
+            port: Some(9000),
+            ..Cli::default()
+        };
+        let config =
+            Config::from_sources(&cli, env(&[("APP_PORT", "8500"), ("APP_WORKERS", "4")])).unwrap();
+        fs::remove_file(path).unwrap();
+
+        assert_eq!(config.server.host, "0.0.0.0");
+        assert_eq!(config.server.port, 9000);
+        assert_eq!(config.server.workers, Some(4));
+        assert_eq!(config.server.drain_timeout(), Duration::from_secs(5));
+        assert!(!config.features.request_logging);
+    }
+
+    // Cli flags
+    #[test]
+    fn test_cli_parse() {
+        let cli = Cli::try_parse_from([
+            "rust-axum-web",
+            "--host",
+            "0.0.0.0",
+            "-p",
+            "8080",
+            "--request-logging",
+            "false",
+            "--log-format",
+            "json",
+        ])
+        .unwrap();
+
+        let config = Config::from_sources(&cli, env(&[])).unwrap();
+        assert_eq!(config.server.host, "0.0.0.0");
+        assert_eq!(config.server.port, 8080);
+        assert_eq!(config.logging.format, LogFormat::Json);
+        assert!(!config.features.request_logging);
+    }
+
+    // Subcommand migrate
+    #[test]
+    fn test_cli_migrate() {
+        let cli = Cli::try_parse_from([
+            "rust-axum-web",
+            "--auto-migrate",
+            "false",
+            "migrate",
+            "down",
+        ])
+        .unwrap();
+        assert_eq!(
+            cli.command,
+            Some(Command::Migrate {
+                action: MigrateAction::Down
+            })
+        );
+
+        let config = Config::from_sources(&cli, env(&[("APP_AUTO_MIGRATE", "true")])).unwrap();
+        assert!(!config.database.auto_migrate);
+
+        assert!(Cli::try_parse_from(["rust-axum-web", "migrate", "sideways"]).is_err());
+    }
+
+    // Field pertama yang ditolak validate untuk config dari TOML
+    fn invalid_field(content: &str) -> &'static str {
+        let config: Config = toml::from_str(content).unwrap();
+        match config.validate().unwrap_err() {
+            ConfigError::Invalid { field, .. } => field,
+            error => panic!("unexpected error: {}", error),
+        }
+    }
+
+    // Nilai env dan file yang tidak bisa di-parse
+    #[test]
+    fn test_config_parse_error() {
+        let error = Config::from_sources(&Cli::default(), env(&[("APP_PORT", "abc")])).unwrap_err();
+        assert!(matches!(error, ConfigError::Env { ref key, .. } if key == "APP_PORT"));
+
+        let path = config_file("invalid", "[server]\nport = \"not a number\"\n");
+        let error = Config::from_sources(
+            &Cli::default(),
+            env(&[("APP_CONFIG", path.to_str().unwrap())]),
+        )
+        .unwrap_err();
+        fs::remove_file(path).unwrap();
+        assert!(matches!(error, ConfigError::Parse { .. }));
+
+        let error = toml::from_str::<Config>("[rbac.roles]\neditor = [\"products\"]\n");
+        assert!(error.is_err());
+    }
+
+    #[test]
+    fn test_server_validation() {
+        let error = Config::from_sources(&Cli::default(), env(&[("APP_HOST", "localhost:80")]))
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            ConfigError::Invalid {
+                field: "server.host",
+                ..
+            }
+        ));
+
+        let cli = Cli {
+            workers: Some(0),
+            ..Cli::default()
+        };
+        let error = Config::from_sources(&cli, env(&[])).unwrap_err();
+        assert!(matches!(
+            error,
+            ConfigError::Invalid {
+                field: "server.workers",
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_auth_validation() {
+        assert_eq!(invalid_field("[auth]\ntoken_ttl = 0\n"), "auth.token_ttl");
+        assert_eq!(
+            invalid_field("[auth]\ntoken_ttl = 31536001\n"),
+            "auth.token_ttl"
+        );
+        assert_eq!(
+            invalid_field("[auth]\ntoken_secret = \"pendek\"\n"),
+            "auth.token_secret"
+        );
+    }
+
+    #[test]
+    fn test_database_validation() {
+        assert_eq!(
+            invalid_field("[database]\nurl = \"mysql://localhost/app\"\n"),
+            "database.url"
+        );
+    }
+
+    #[test]
+    fn test_session_validation() {
+        assert_eq!(invalid_field("[session]\nttl = 0\n"), "session.ttl");
+        assert_eq!(invalid_field("[session]\nttl = 31536001\n"), "session.ttl");
+        assert_eq!(
+            invalid_field("[session]\npurge_interval = 0\n"),
+            "session.purge_interval"
+        );
+    }
+
+    #[test]
+    fn test_cookie_validation() {
+        let error = Config::from_sources(&Cli::default(), env(&[("APP_COOKIE_KEY", "pendek")]))
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            ConfigError::Invalid {
+                field: "cookie.key",
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_upload_validation() {
+        assert_eq!(
+            invalid_field("[upload]\nallowed_types = [\"image/svg+xml\"]\n"),
+            "upload.allowed_types"
+        );
+    }
+
+    #[test]
+    fn test_rbac_validation() {
+        assert_eq!(
+            invalid_field("[rbac.roles]\nadmin = [\"products:write\"]\n"),
+            "rbac.roles"
+        );
+    }
+
+    #[test]
+    fn test_rate_limit_validation() {
+        assert_eq!(
+            invalid_field("[rate_limit.routes.\"/api/auth/login\"]\nrequests = 0\nperiod = 60\n"),
+            "rate_limit.routes"
+        );
+        assert_eq!(
+            invalid_field("[rate_limit.default]\nrequests = 1\nperiod = 31536001\n"),
+            "rate_limit.default"
+        );
+        assert_eq!(
+            invalid_field("[rate_limit.default]\nrequests = 0\nperiod = 60\n"),
+            "rate_limit.default"
+        );
+    }
+
+    #[test]
+    fn test_lockout_validation() {
+        assert_eq!(
+            invalid_field("[lockout]\nfree_attempts = 5\nusername_threshold = 5\n"),
+            "lockout.username_threshold"
+        );
+        assert_eq!(
+            invalid_field("[lockout]\nmax_keys = 0\n"),
+            "lockout.max_keys"
+        );
+        assert_eq!(
+            invalid_field("[lockout]\nbase_delay = 0\n"),
+            "lockout.base_delay"
+        );
+        assert_eq!(
+            invalid_field("[lockout]\nmax_delay = 31536001\n"),
+            "lockout.max_delay"
+        );
+        assert_eq!(
+            invalid_field("[lockout]\nbase_delay = 10\nmax_delay = 5\n"),
+            "lockout.max_delay"
+        );
+        assert_eq!(
+            invalid_field("[lockout]\nduration = 31536001\n"),
+            "lockout.duration"
+        );
+    }
+
+    #[test]
+    fn test_cors_validation() {
+        assert_eq!(
+            invalid_field("[cors]\norigins = [\"https://*.example.com/app\"]\n"),
+            "cors.origins"
+        );
+
+        let error = Config::from_sources(
+            &Cli::default(),
+            env(&[("APP_CORS_ORIGINS", "*"), ("APP_CORS_CREDENTIALS", "true")]),
+        )
+        .unwrap_err();
+        assert!(matches!(
+            error,
+            ConfigError::Invalid {
+                field: "cors.credentials",
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_security_headers_validation() {
+        assert_eq!(
+            invalid_field("[security_headers]\nreferrer_policy = \"always\"\n"),
+            "security_headers.referrer_policy"
+        );
+    }
+}
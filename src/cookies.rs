@@ -0,0 +1,379 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{FromRef, FromRequestParts},
+    response::{IntoResponse, IntoResponseParts, Response, ResponseParts},
+};
+use axum_extra::extract::{
+    cookie::{Cookie, Key},
+    CookieJar, PrivateCookieJar, SignedCookieJar,
+};
+use http::request::Parts;
+
+use crate::{config::CookieConfig, error::AppError, state::AppState};
+
+// Key untuk cookie signed/private; cookie baru memakai current,
+// cookie lama masih diterima bila cocok dengan salah satu previous
+#[derive(Clone)]
+pub struct CookieKeys {
+    current: Key,
+    previous: Arc<[Key]>,
+}
+
+impl CookieKeys {
+    pub fn new(current: Key, previous: Vec<Key>) -> CookieKeys {
+        CookieKeys {
+            current,
+            previous: previous.into(),
+        }
+    }
+
+    /// Derives the keys from the configured secrets, or generates a random key if none is set.
+    ///
+    /// Fails if a secret is shorter than 32 bytes, e.g. for a `CookieConfig` that skipped `validate`.
+    pub fn from_config(config: &CookieConfig) -> anyhow::Result<CookieKeys> {
+        Ok(match &config.key {
+            Some(key) => CookieKeys::new(
+                derive_key(key)?,
+                config
+                    .previous_keys
+                    .iter()
+                    .map(|key| derive_key(key))
+                    .collect::<anyhow::Result<_>>()?,
+            ),
+            None => CookieKeys::new(Key::generate(), Vec::new()),
+        })
+    }
+
+    pub fn current(&self) -> &Key {
+        &self.current
+    }
+
+    /// Signs the cookie with the current key; the value is prefixed with its HMAC.
+    pub fn sign(&self, cookie: Cookie<'static>) -> Cookie<'static> {
+        let name = cookie.name().to_string();
+        let mut jar = cookie::CookieJar::new();
+        jar.signed_mut(&self.current).add(cookie);
+        jar.get(&name)
+            .cloned()
+            .expect("signed cookie was just added")
+    }
+
+    /// Returns the cookie with its original value if the signature matches the current or a previous key.
+    pub fn verify(&self, cookie: Cookie<'static>) -> Option<Cookie<'static>> {
+        std::iter::once(&self.current)
+            .chain(self.previous.iter())
+            .find_map(|key| SignedCookieJar::new(key.clone()).verify(cookie.clone()))
+    }
+}
+
+// Key::derive_from panic untuk secret yang lebih pendek dari 32 byte
+fn derive_key(secret: &str) -> anyhow::Result<Key> {
+    if secret.len() < 32 {
+        anyhow::bail!("cookie keys must be at least 32 bytes");
+    }
+    Ok(Key::derive_from(secret.as_bytes()))
+}
+
+// Agar SignedCookieJar dan PrivateCookieJar dari axum-extra juga bisa dipakai langsung
+impl FromRef<AppState> for Key {
+    fn from_ref(state: &AppState) -> Key {
+        state.cookie_keys.current.clone()
+    }
+}
+
+fn invalid_cookie(name: &str) -> AppError {
+    AppError::validation(
+        "invalid_cookie",
+        format!(
+            "Cookie {} has been tampered with or uses an unknown key",
+            name
+        ),
+    )
+}
+
+// Cookie yang ditandatangani (HMAC): isi terbaca client, tapi tidak bisa diubah
+#[derive(Debug, Clone)]
+pub struct SignedCookies {
+    jar: SignedCookieJar,
+    raw: CookieJar,
+    previous: Vec<SignedCookieJar>,
+}
+
+impl SignedCookies {
+    /// Returns `None` if the cookie is missing and an error if its signature does not verify.
+    pub fn get(&self, name: &str) -> Result<Option<Cookie<'static>>, AppError> {
+        let Some(cookie) = self.raw.get(name) else {
+            return Ok(None);
+        };
+        if let Some(cookie) = self.jar.get(name) {
+            return Ok(Some(cookie));
+        }
+
+        self.previous
+            .iter()
+            .find_map(|jar| jar.verify(cookie.clone()))
+            .map(Some)
+            .ok_or_else(|| invalid_cookie(name))
+    }
+
+    /// Signs the cookie with the current key.
+    #[allow(clippy::should_implement_trait)]
+    pub fn add<C: Into<Cookie<'static>>>(mut self, cookie: C) -> SignedCookies {
+        self.jar = self.jar.add(cookie);
+        self
+    }
+
+    pub fn remove<C: Into<Cookie<'static>>>(mut self, cookie: C) -> SignedCookies {
+        self.jar = self.jar.remove(cookie);
+        self
+    }
+}
+
+impl<S> FromRequestParts<S> for SignedCookies
+where
+    AppState: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let keys = AppState::from_ref(state).cookie_keys;
+        Ok(SignedCookies {
+            jar: SignedCookieJar::from_headers(&parts.headers, keys.current.clone()),
+            raw: CookieJar::from_headers(&parts.headers),
+            previous: keys
+                .previous
+                .iter()
+                .map(|key| SignedCookieJar::new(key.clone()))
+                .collect(),
+        })
+    }
+}
+
+impl IntoResponseParts for SignedCookies {
+    type Error = <SignedCookieJar as IntoResponseParts>::Error;
+
+    fn into_response_parts(self, res: ResponseParts) -> Result<ResponseParts, Self::Error> {
+        self.jar.into_response_parts(res)
+    }
+}
+
+impl IntoResponse for SignedCookies {
+    fn into_response(self) -> Response {
+        self.jar.into_response()
+    }
+}
+
+// Cookie terenkripsi (AES-GCM): isi tidak terbaca dan tidak bisa diubah client
+#[derive(Debug, Clone)]
+pub struct PrivateCookies {
+    jar: PrivateCookieJar,
+    raw: CookieJar,
+    previous: Vec<PrivateCookieJar>,
+}
+
+impl PrivateCookies {
+    /// Returns `None` if the cookie is missing and an error if it cannot be decrypted.
+    pub fn get(&self, name: &str) -> Result<Option<Cookie<'static>>, AppError> {
+        let Some(cookie) = self.raw.get(name) else {
+            return Ok(None);
+        };
+        if let Some(cookie) = self.jar.get(name) {
+            return Ok(Some(cookie));
+        }
+
+        self.previous
+            .iter()
+            .find_map(|jar| jar.decrypt(cookie.clone()))
+            .map(Some)
+            .ok_or_else(|| invalid_cookie(name))
+    }
+
+    /// Encrypts the cookie with the current key.
+    #[allow(clippy::should_implement_trait)]
+    pub fn add<C: Into<Cookie<'static>>>(mut self, cookie: C) -> PrivateCookies {
+        self.jar = self.jar.add(cookie);
+        self
+    }
+
+    pub fn remove<C: Into<Cookie<'static>>>(mut self, cookie: C) -> PrivateCookies {
+        self.jar = self.jar.remove(cookie);
+        self
+    }
+}
+
+impl<S> FromRequestParts<S> for PrivateCookies
+where
+    AppState: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let keys = AppState::from_ref(state).cookie_keys;
+        Ok(PrivateCookies {
+            jar: PrivateCookieJar::from_headers(&parts.headers, keys.current.clone()),
+            raw: CookieJar::from_headers(&parts.headers),
+            previous: keys
+                .previous
+                .iter()
+                .map(|key| PrivateCookieJar::new(key.clone()))
+                .collect(),
+        })
+    }
+}
+
+impl IntoResponseParts for PrivateCookies {
+    type Error = <PrivateCookieJar as IntoResponseParts>::Error;
+
+    fn into_response_parts(self, res: ResponseParts) -> Result<ResponseParts, Self::Error> {
+        self.jar.into_response_parts(res)
+    }
+}
+
+impl IntoResponse for PrivateCookies {
+    fn into_response(self) -> Response {
+        self.jar.into_response()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{extract::Query, routing::get, Router};
+    use axum_test::TestServer;
+    use http::StatusCode;
+    use serde::Deserialize;
+
+    use super::*;
+    use crate::Config;
+
+    const OLD_KEY: &str = "key-lama-yang-panjangnya-minimal-32-byte";
+    const NEW_KEY: &str = "key-baru-yang-panjangnya-minimal-32-byte";
+
+    #[derive(Deserialize)]
+    struct Name {
+        name: String,
+    }
+
+    fn server(key: &str, previous_keys: &[&str]) -> TestServer {
+        async fn set_signed(jar: SignedCookies, Query(query): Query<Name>) -> SignedCookies {
+            jar.add(Cookie::new("name", query.name))
+        }
+
+        async fn get_signed(jar: SignedCookies) -> Result<String, AppError> {
+            let name = jar.get("name")?.map(|cookie| cookie.value().to_string());
+            Ok(format!("Hello {}", name.unwrap_or_default()))
+        }
+
+        async fn set_private(jar: PrivateCookies, Query(query): Query<Name>) -> PrivateCookies {
+            jar.add(Cookie::new("name", query.name))
+        }
+
+        async fn get_private(jar: PrivateCookies) -> Result<String, AppError> {
+            let name = jar.get("name")?.map(|cookie| cookie.value().to_string());
+            Ok(format!("Hello {}", name.unwrap_or_default()))
+        }
+
+        let mut config = Config::default();
+        config.cookie.key = Some(key.to_string());
+        config.cookie.previous_keys = previous_keys.iter().map(|key| key.to_string()).collect();
+
+        let app = Router::new()
+            .route("/signed/set", get(set_signed))
+            .route("/signed", get(get_signed))
+            .route("/private/set", get(set_private))
+            .route("/private", get(get_private))
+            .with_state(AppState::new(config).unwrap());
+        TestServer::new(app).unwrap()
+    }
+
+    // nilai cookie "name=..." dari header Set-Cookie
+    async fn issue(server: &TestServer, path: &str) -> String {
+        let response = server.get(path).add_query_param("name", "Aqil").await;
+        let cookie = response.header("Set-Cookie");
+        let cookie = Cookie::parse(cookie.to_str().unwrap().to_string()).unwrap();
+        format!("name={}", cookie.value())
+    }
+
+    #[tokio::test]
+    async fn test_signed_cookie() {
+        let server = server(NEW_KEY, &[]);
+        let cookie = issue(&server, "/signed/set").await;
+        assert_ne!(cookie, "name=Aqil");
+        assert!(cookie.ends_with("Aqil"));
+
+        let response = server.get("/signed").add_header("Cookie", cookie).await;
+        response.assert_status_ok();
+        response.assert_text("Hello Aqil");
+
+        // tanpa cookie bukan error
+        server.get("/signed").await.assert_text("Hello ");
+    }
+
+    #[tokio::test]
+    async fn test_tampered_cookie() {
+        let server = server(NEW_KEY, &[]);
+        let cookie = issue(&server, "/signed/set").await;
+
+        let tampered = cookie.replace("Aqil", "Budi");
+        let response = server.get("/signed").add_header("Cookie", tampered).await;
+        response.assert_status(StatusCode::BAD_REQUEST);
+        response.assert_text("Cookie name has been tampered with or uses an unknown key");
+
+        // cookie polos tanpa tanda tangan juga ditolak
+        let response = server
+            .get("/signed")
+            .add_header("Cookie", "name=Aqil")
+            .await;
+        response.assert_status(StatusCode::BAD_REQUEST);
+
+        let cookie = issue(&server, "/private/set").await;
+        assert!(!cookie.contains("Aqil"));
+        let response = server
+            .get("/private")
+            .add_header("Cookie", cookie.clone())
+            .await;
+        response.assert_text("Hello Aqil");
+
+        let mut tampered = cookie.into_bytes();
+        let last = tampered.len() - 1;
+        tampered[last] = if tampered[last] == b'A' { b'B' } else { b'A' };
+        let response = server
+            .get("/private")
+            .add_header("Cookie", String::from_utf8(tampered).unwrap())
+            .await;
+        response.assert_status(StatusCode::BAD_REQUEST);
+    }
+
+    // Rotasi key: cookie lama tetap berlaku, cookie baru memakai key baru
+    #[tokio::test]
+    async fn test_rotated_keys() {
+        let old = server(OLD_KEY, &[]);
+        let signed = issue(&old, "/signed/set").await;
+        let private = issue(&old, "/private/set").await;
+
+        let rotated = server(NEW_KEY, &[OLD_KEY]);
+        for (path, cookie) in [("/signed", &signed), ("/private", &private)] {
+            let response = rotated.get(path).add_header("Cookie", cookie.clone()).await;
+            response.assert_status_ok();
+            response.assert_text("Hello Aqil");
+        }
+
+        // cookie yang ditandatangani ulang tidak lagi bergantung pada key lama
+        let resigned = issue(&rotated, "/signed/set").await;
+        assert_ne!(resigned, signed);
+        let current = server(NEW_KEY, &[]);
+        current
+            .get("/signed")
+            .add_header("Cookie", resigned)
+            .await
+            .assert_text("Hello Aqil");
+
+        // setelah key lama dibuang, cookie lama ditolak
+        for (path, cookie) in [("/signed", signed), ("/private", private)] {
+            let response = current.get(path).add_header("Cookie", cookie).await;
+            response.assert_status(StatusCode::BAD_REQUEST);
+        }
+    }
+}
@@ -0,0 +1,534 @@
+use std::{str::FromStr, sync::Arc};
+
+use axum::{
+    extract::{Request, State},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use http::{header, HeaderMap, HeaderName, HeaderValue, Method, StatusCode};
+
+use crate::{config::CorsConfig, error::AppError};
+
+// Pola origin dari config: "*", origin persis, atau "https://*.example.com" untuk semua subdomain
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OriginPattern {
+    Any,
+    Exact(String),
+    // origin harus diawali scheme dan diakhiri ".domain[:port]", contoh: "https://" dan ".example.com"
+    Subdomain { scheme: String, suffix: String },
+}
+
+// host berupa label [a-z0-9-] yang dipisah titik, sehingga tidak bisa menyelipkan port, path atau userinfo
+fn valid_host(host: &str) -> bool {
+    !host.is_empty()
+        && host.split('.').all(|label| {
+            !label.is_empty()
+                && label
+                    .bytes()
+                    .all(|byte| byte.is_ascii_lowercase() || byte.is_ascii_digit() || byte == b'-')
+        })
+}
+
+impl FromStr for OriginPattern {
+    type Err = String;
+
+    fn from_str(pattern: &str) -> Result<Self, Self::Err> {
+        if pattern == "*" {
+            return Ok(OriginPattern::Any);
+        }
+
+        let pattern = pattern.to_ascii_lowercase();
+        let Some((scheme, authority)) = pattern.split_once("://") else {
+            return Err(format!(
+                "{:?} is not an origin, expected scheme://host[:port]",
+                pattern
+            ));
+        };
+        if scheme != "http" && scheme != "https" {
+            return Err(format!("{:?} must use http or https", pattern));
+        }
+
+        let host = match authority.rsplit_once(':') {
+            Some((host, port)) if port.parse::<u16>().is_ok() => host,
+            Some(_) => return Err(format!("{:?} has an invalid port", pattern)),
+            None => authority,
+        };
+        let wildcard = host.strip_prefix("*.");
+        if !valid_host(wildcard.unwrap_or(host)) {
+            return Err(format!(
+                "{:?} has an invalid host, only a leading *. wildcard is supported",
+                pattern
+            ));
+        }
+
+        Ok(match wildcard {
+            Some(_) => OriginPattern::Subdomain {
+                scheme: format!("{}://", scheme),
+                suffix: authority[1..].to_string(),
+            },
+            None => OriginPattern::Exact(pattern),
+        })
+    }
+}
+
+impl OriginPattern {
+    pub fn matches(&self, origin: &str) -> bool {
+        match self {
+            OriginPattern::Any => true,
+            OriginPattern::Exact(exact) => exact.eq_ignore_ascii_case(origin),
+            OriginPattern::Subdomain { scheme, suffix } => {
+                let origin = origin.to_ascii_lowercase();
+                origin
+                    .strip_prefix(scheme.as_str())
+                    .and_then(|rest| rest.strip_suffix(suffix.as_str()))
+                    .is_some_and(valid_host)
+            }
+        }
+    }
+}
+
+fn header_list<T: AsRef<str>>(values: &[T]) -> Option<HeaderValue> {
+    let list = values
+        .iter()
+        .map(AsRef::as_ref)
+        .collect::<Vec<_>>()
+        .join(", ");
+    HeaderValue::try_from(list)
+        .ok()
+        .filter(|list| !list.is_empty())
+}
+
+/// CORS policy built once from [`CorsConfig`] and shared by [`cors_middleware`].
+#[derive(Debug, Clone)]
+pub struct Cors {
+    origins: Vec<OriginPattern>,
+    methods: Vec<Method>,
+    headers: Vec<HeaderName>,
+    allow_methods: Option<HeaderValue>,
+    allow_headers: Option<HeaderValue>,
+    expose_headers: Option<HeaderValue>,
+    credentials: bool,
+    max_age: HeaderValue,
+}
+
+impl Cors {
+    /// Returns `None` when no origin is configured, leaving CORS disabled.
+    pub fn from_config(config: &CorsConfig) -> Option<Cors> {
+        if config.origins.is_empty() {
+            return None;
+        }
+
+        // semua nilai sudah divalidasi oleh Config::validate
+        let origins = config
+            .origins
+            .iter()
+            .map(|origin| origin.parse().expect("invalid cors origin"))
+            .collect();
+        let methods: Vec<Method> = config
+            .methods
+            .iter()
+            .map(|method| Method::from_bytes(method.as_bytes()).expect("invalid cors method"))
+            .collect();
+        let headers: Vec<HeaderName> = config
+            .headers
+            .iter()
+            .map(|name| HeaderName::from_bytes(name.as_bytes()).expect("invalid cors header"))
+            .collect();
+
+        Some(Cors {
+            origins,
+            allow_methods: header_list(&methods),
+            allow_headers: header_list(&headers),
+            methods,
+            headers,
+            expose_headers: header_list(&config.expose_headers),
+            credentials: config.credentials,
+            max_age: HeaderValue::from(config.max_age),
+        })
+    }
+
+    // nilai Access-Control-Allow-Origin, None bila origin tidak diizinkan;
+    // dengan credentials origin selalu dipantulkan karena browser menolak "*"
+    fn allow_origin(&self, origin: &HeaderValue) -> Option<HeaderValue> {
+        let origin_str = origin.to_str().ok()?;
+        let pattern = self
+            .origins
+            .iter()
+            .find(|pattern| pattern.matches(origin_str))?;
+        if *pattern == OriginPattern::Any && !self.credentials {
+            Some(HeaderValue::from_static("*"))
+        } else {
+            Some(origin.clone())
+        }
+    }
+
+    fn check_preflight(
+        &self,
+        origin: &HeaderValue,
+        headers: &HeaderMap,
+    ) -> Result<HeaderValue, AppError> {
+        let allow_origin = self.allow_origin(origin).ok_or_else(|| {
+            AppError::forbidden(
+                "cors_origin_not_allowed",
+                format!(
+                    "Origin {} is not allowed",
+                    String::from_utf8_lossy(origin.as_bytes())
+                ),
+            )
+        })?;
+
+        let method = headers
+            .get(header::ACCESS_CONTROL_REQUEST_METHOD)
+            .and_then(|method| Method::from_bytes(method.as_bytes()).ok());
+        match method {
+            Some(method) if self.methods.contains(&method) => {}
+            method => {
+                return Err(AppError::forbidden(
+                    "cors_method_not_allowed",
+                    format!(
+                        "Method {} is not allowed for cross-origin requests",
+                        method.as_ref().map_or("?", Method::as_str)
+                    ),
+                ))
+            }
+        }
+
+        let requested = headers
+            .get_all(header::ACCESS_CONTROL_REQUEST_HEADERS)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .map(str::trim)
+            .filter(|name| !name.is_empty());
+        for name in requested {
+            let allowed = HeaderName::from_bytes(name.as_bytes())
+                .is_ok_and(|name| self.headers.contains(&name));
+            if !allowed {
+                return Err(AppError::forbidden(
+                    "cors_header_not_allowed",
+                    format!("Header {} is not allowed for cross-origin requests", name),
+                ));
+            }
+        }
+
+        Ok(allow_origin)
+    }
+
+    fn preflight(&self, origin: &HeaderValue, headers: &HeaderMap) -> Response {
+        let allow_origin = match self.check_preflight(origin, headers) {
+            Ok(allow_origin) => allow_origin,
+            Err(error) => return error.into_response(),
+        };
+
+        let mut response = StatusCode::NO_CONTENT.into_response();
+        let headers = response.headers_mut();
+        self.insert_origin(headers, allow_origin);
+        if let Some(methods) = &self.allow_methods {
+            headers.insert(header::ACCESS_CONTROL_ALLOW_METHODS, methods.clone());
+        }
+        if let Some(allow_headers) = &self.allow_headers {
+            headers.insert(header::ACCESS_CONTROL_ALLOW_HEADERS, allow_headers.clone());
+        }
+        headers.insert(header::ACCESS_CONTROL_MAX_AGE, self.max_age.clone());
+        response
+    }
+
+    fn insert_origin(&self, headers: &mut HeaderMap, allow_origin: HeaderValue) {
+        headers.insert(header::ACCESS_CONTROL_ALLOW_ORIGIN, allow_origin);
+        if self.credentials {
+            headers.insert(
+                header::ACCESS_CONTROL_ALLOW_CREDENTIALS,
+                HeaderValue::from_static("true"),
+            );
+        }
+    }
+}
+
+// Middleware: dipasang di luar router agar preflight dijawab sebelum routing,
+// sehingga OPTIONS ke route yang hanya punya GET tidak berakhir di method_not_allowed_fallback.
+// OPTIONS tanpa Access-Control-Request-Method bukan preflight dan tetap diteruskan ke router.
+pub async fn cors_middleware(
+    State(cors): State<Arc<Cors>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let origin = request.headers().get(header::ORIGIN).cloned();
+    let preflight = origin.is_some()
+        && request.method() == Method::OPTIONS
+        && request
+            .headers()
+            .contains_key(header::ACCESS_CONTROL_REQUEST_METHOD);
+
+    let mut response = match &origin {
+        Some(origin) if preflight => cors.preflight(origin, request.headers()),
+        _ => next.run(request).await,
+    };
+
+    let headers = response.headers_mut();
+    // response berbeda per origin, cache tidak boleh memakai ulang untuk origin lain
+    headers.append(header::VARY, HeaderValue::from_static("origin"));
+    if preflight {
+        headers.append(
+            header::VARY,
+            HeaderValue::from_static(
+                "access-control-request-method, access-control-request-headers",
+            ),
+        );
+    } else if let Some(allow_origin) = origin.and_then(|origin| cors.allow_origin(&origin)) {
+        cors.insert_origin(headers, allow_origin);
+        if let Some(expose_headers) = &cors.expose_headers {
+            headers.insert(
+                header::ACCESS_CONTROL_EXPOSE_HEADERS,
+                expose_headers.clone(),
+            );
+        }
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{middleware::from_fn_with_state, routing::get, Router};
+    use axum_test::TestServer;
+
+    use super::*;
+    use crate::{error::assert_error_text, test_support::TestApp};
+
+    fn config(origins: &[&str]) -> CorsConfig {
+        CorsConfig {
+            origins: origins.iter().map(|origin| origin.to_string()).collect(),
+            ..CorsConfig::default()
+        }
+    }
+
+    #[test]
+    fn test_origin_pattern() {
+        let exact: OriginPattern = "https://App.example.com".parse().unwrap();
+        assert!(exact.matches("https://app.example.com"));
+        assert!(!exact.matches("http://app.example.com"));
+        assert!(!exact.matches("https://app.example.com:8443"));
+
+        let wildcard: OriginPattern = "https://*.example.com".parse().unwrap();
+        assert!(wildcard.matches("https://app.example.com"));
+        assert!(wildcard.matches("https://a.b.example.com"));
+        assert!(!wildcard.matches("https://example.com"));
+        assert!(!wildcard.matches("https://evilexample.com"));
+        assert!(!wildcard.matches("https://evil.com:1.example.com"));
+        assert!(!wildcard.matches("https://evil.com/.example.com"));
+        assert!(!wildcard.matches("http://app.example.com"));
+        assert!(!wildcard.matches("https://app.example.com:8443"));
+
+        let port: OriginPattern = "http://*.localhost:5173".parse().unwrap();
+        assert!(port.matches("http://app.localhost:5173"));
+        assert!(!port.matches("http://app.localhost"));
+
+        for invalid in [
+            "example.com",
+            "ftp://example.com",
+            "https://example.com/",
+            "https://example.com:port",
+            "https://app.*.example.com",
+            "https://*",
+        ] {
+            assert!(invalid.parse::<OriginPattern>().is_err(), "{}", invalid);
+        }
+    }
+
+    // Router seperti di test_fallback: route hanya GET dengan method_not_allowed_fallback
+    fn server(config: CorsConfig) -> TestServer {
+        async fn not_allowed(request: Request) -> (StatusCode, String) {
+            (
+                StatusCode::METHOD_NOT_ALLOWED,
+                format!("Method {} is not allowed", request.method()),
+            )
+        }
+
+        let cors = Cors::from_config(&config).unwrap();
+        let app = Router::new()
+            .route("/first", get(|| async { "Hello" }))
+            .method_not_allowed_fallback(not_allowed)
+            .layer(from_fn_with_state(Arc::new(cors), cors_middleware));
+        TestServer::new(app).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_preflight() {
+        let server = server(config(&["https://*.example.com"]));
+
+        let response = server
+            .method(Method::OPTIONS, "/first")
+            .add_header("Origin", "https://app.example.com")
+            .add_header("Access-Control-Request-Method", "DELETE")
+            .add_header(
+                "Access-Control-Request-Headers",
+                "Content-Type, Authorization",
+            )
+            .await;
+        response.assert_status(StatusCode::NO_CONTENT);
+        response.assert_header("Access-Control-Allow-Origin", "https://app.example.com");
+        response.assert_header(
+            "Access-Control-Allow-Methods",
+            "GET, POST, PUT, PATCH, DELETE",
+        );
+        response.assert_header(
+            "Access-Control-Allow-Headers",
+            "authorization, content-type, x-request-id",
+        );
+        response.assert_header("Access-Control-Max-Age", "600");
+        assert!(response
+            .maybe_header("Access-Control-Allow-Credentials")
+            .is_none());
+
+        // OPTIONS biasa tetap sampai ke method_not_allowed_fallback
+        let response = server
+            .method(Method::OPTIONS, "/first")
+            .add_header("Origin", "https://app.example.com")
+            .await;
+        response.assert_status(StatusCode::METHOD_NOT_ALLOWED);
+        response.assert_text("Method OPTIONS is not allowed");
+        response.assert_header("Access-Control-Allow-Origin", "https://app.example.com");
+
+        let response = server
+            .method(Method::OPTIONS, "/first")
+            .add_header("Origin", "https://example.org")
+            .add_header("Access-Control-Request-Method", "GET")
+            .await;
+        response.assert_status(StatusCode::FORBIDDEN);
+        response.assert_text("Origin https://example.org is not allowed");
+        assert!(response
+            .maybe_header("Access-Control-Allow-Origin")
+            .is_none());
+
+        let response = server
+            .method(Method::OPTIONS, "/first")
+            .add_header("Origin", "https://app.example.com")
+            .add_header("Access-Control-Request-Method", "TRACE")
+            .await;
+        response.assert_status(StatusCode::FORBIDDEN);
+        response.assert_text("Method TRACE is not allowed for cross-origin requests");
+
+        let response = server
+            .method(Method::OPTIONS, "/first")
+            .add_header("Origin", "https://app.example.com")
+            .add_header("Access-Control-Request-Method", "GET")
+            .add_header("Access-Control-Request-Headers", "x-secret")
+            .await;
+        response.assert_status(StatusCode::FORBIDDEN);
+        response.assert_text("Header x-secret is not allowed for cross-origin requests");
+    }
+
+    #[tokio::test]
+    async fn test_actual_request() {
+        let server = server(CorsConfig {
+            credentials: true,
+            ..config(&["https://app.example.com"])
+        });
+
+        let response = server
+            .get("/first")
+            .add_header("Origin", "https://app.example.com")
+            .await;
+        response.assert_status_ok();
+        response.assert_header("Access-Control-Allow-Origin", "https://app.example.com");
+        response.assert_header("Access-Control-Allow-Credentials", "true");
+        response.assert_header("Access-Control-Expose-Headers", "x-request-id, retry-after");
+        response.assert_header("Vary", "origin");
+
+        // method yang tidak ada tetap 405, dengan header CORS agar browser bisa membaca errornya
+        let response = server
+            .delete("/first")
+            .add_header("Origin", "https://app.example.com")
+            .await;
+        response.assert_status(StatusCode::METHOD_NOT_ALLOWED);
+        response.assert_header("Access-Control-Allow-Origin", "https://app.example.com");
+
+        let response = server
+            .get("/first")
+            .add_header("Origin", "https://example.org")
+            .await;
+        response.assert_status_ok();
+        assert!(response
+            .maybe_header("Access-Control-Allow-Origin")
+            .is_none());
+
+        let response = server.get("/first").await;
+        assert!(response
+            .maybe_header("Access-Control-Allow-Origin")
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn test_any_origin() {
+        let server = server(config(&["*"]));
+        let response = server
+            .get("/first")
+            .add_header("Origin", "https://example.org")
+            .await;
+        response.assert_header("Access-Control-Allow-Origin", "*");
+    }
+
+    // Preflight lewat build_app tidak butuh login dan tidak dihitung rate limit
+    #[tokio::test]
+    async fn test_build_app() {
+        let (server, _) = TestApp::new()
+            .config(|config| {
+                config.cors = CorsConfig {
+                    credentials: true,
+                    ..self::config(&["https://app.example.com"])
+                }
+            })
+            .server();
+
+        for _ in 0..12 {
+            let response = server
+                .method(Method::OPTIONS, "/api/auth/login")
+                .add_header("Origin", "https://app.example.com")
+                .add_header("Access-Control-Request-Method", "POST")
+                .add_header("Access-Control-Request-Headers", "content-type")
+                .await;
+            response.assert_status(StatusCode::NO_CONTENT);
+            response.assert_header("Access-Control-Allow-Credentials", "true");
+            assert!(response.maybe_header("Set-Cookie").is_none());
+        }
+
+        // preflight yang ditolak dirender oleh negotiate_error seperti error lainnya
+        let response = server
+            .method(Method::OPTIONS, "/api/auth/login")
+            .add_header("Origin", "https://example.org")
+            .add_header("Access-Control-Request-Method", "POST")
+            .await;
+        response.assert_status(StatusCode::FORBIDDEN);
+        assert_error_text(&response, "Origin https://example.org is not allowed");
+
+        let response = server
+            .method(Method::OPTIONS, "/api/auth/login")
+            .add_header("Origin", "https://app.example.com")
+            .add_header("Access-Control-Request-Method", "TRACE")
+            .add_header("Accept", "application/json")
+            .await;
+        response.assert_status(StatusCode::FORBIDDEN);
+        response.assert_header("Content-Type", "application/problem+json");
+        let problem: serde_json::Value = response.json();
+        assert_eq!(problem["code"], "cors_method_not_allowed");
+        assert!(problem["request_id"].is_string());
+
+        let response = server
+            .post("/api/products")
+            .add_header("Origin", "https://app.example.com")
+            .add_header("Accept", "application/json")
+            .json(&serde_json::json!({}))
+            .await;
+        response.assert_status(StatusCode::UNAUTHORIZED);
+        response.assert_header("Access-Control-Allow-Origin", "https://app.example.com");
+
+        // tanpa origin di config tidak ada header CORS sama sekali
+        let (server, _) = TestApp::new().server();
+        let response = server
+            .get("/api/products")
+            .add_header("Origin", "https://app.example.com")
+            .await;
+        assert!(response
+            .maybe_header("Access-Control-Allow-Origin")
+            .is_none());
+    }
+}
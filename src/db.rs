@@ -0,0 +1,258 @@
+use std::{
+    ops::{Deref, DerefMut},
+    str::FromStr,
+};
+
+use async_trait::async_trait;
+use axum::extract::{FromRef, FromRequestParts};
+use http::request::Parts;
+use prometheus::{
+    core::{Collector, Desc},
+    proto::MetricFamily,
+    IntGauge, Opts,
+};
+use sqlx::{
+    pool::{PoolConnection, PoolOptions},
+    Connection, Transaction,
+};
+
+use crate::{config::DatabaseConfig, error::AppError, health::HealthCheck, state::AppState};
+
+// Backend dipilih saat compile: SQLite secara default, Postgres dengan feature "postgres"
+#[cfg(not(feature = "postgres"))]
+pub type Database = sqlx::Sqlite;
+#[cfg(feature = "postgres")]
+pub type Database = sqlx::Postgres;
+
+pub type DbPool = sqlx::Pool<Database>;
+pub type DbConnection = <Database as sqlx::Database>::Connection;
+pub type ConnectOptions = <DbConnection as Connection>::Options;
+
+#[cfg(not(feature = "postgres"))]
+const URL_SCHEMES: &[&str] = &["sqlite:"];
+#[cfg(feature = "postgres")]
+const URL_SCHEMES: &[&str] = &["postgres:", "postgresql:"];
+
+pub fn connect_options(url: &str) -> Result<ConnectOptions, sqlx::Error> {
+    if !URL_SCHEMES.iter().any(|scheme| url.starts_with(scheme)) {
+        return Err(sqlx::Error::Configuration(
+            format!("database url must start with one of {:?}", URL_SCHEMES).into(),
+        ));
+    }
+    ConnectOptions::from_str(url)
+}
+
+/// Builds the pool without connecting; connections are opened on first use.
+pub fn connect_lazy(config: &DatabaseConfig) -> Result<DbPool, sqlx::Error> {
+    let mut options = PoolOptions::new()
+        .max_connections(config.max_connections)
+        .min_connections(config.min_connections)
+        .acquire_timeout(config.acquire_timeout());
+
+    // database in-memory hilang begitu koneksi terakhir ditutup
+    if config.url.contains(":memory:") {
+        options = options.idle_timeout(None).max_lifetime(None);
+    }
+
+    Ok(options.connect_lazy_with(connect_options(&config.url)?))
+}
+
+impl From<sqlx::Error> for AppError {
+    fn from(error: sqlx::Error) -> Self {
+        AppError::Internal(error.into())
+    }
+}
+
+// Satu koneksi dari pool per request, dikembalikan ke pool saat request selesai
+pub struct Db(PoolConnection<Database>);
+
+impl Db {
+    /// Starts a transaction that rolls back unless committed.
+    pub async fn begin(&mut self) -> Result<Transaction<'_, Database>, AppError> {
+        Ok(self.0.begin().await?)
+    }
+}
+
+impl Deref for Db {
+    type Target = DbConnection;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl DerefMut for Db {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl<S> FromRequestParts<S> for Db
+where
+    AppState: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request_parts(_parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let state = AppState::from_ref(state);
+        Ok(Db(state.db.acquire().await?))
+    }
+}
+
+// Readiness check: ambil koneksi dan ping database
+pub struct DatabaseCheck {
+    pub pool: DbPool,
+}
+
+#[async_trait]
+impl HealthCheck for DatabaseCheck {
+    async fn check(&self) -> Result<(), String> {
+        let mut connection = self
+            .pool
+            .acquire()
+            .await
+            .map_err(|error| error.to_string())?;
+        connection.ping().await.map_err(|error| error.to_string())
+    }
+}
+
+// Ukuran pool dibaca saat /metrics di-scrape
+pub struct PoolCollector {
+    pool: DbPool,
+    connections: IntGauge,
+    idle: IntGauge,
+    max: IntGauge,
+}
+
+impl PoolCollector {
+    pub fn new(pool: DbPool) -> PoolCollector {
+        let gauge = |name: &str, help: &str| IntGauge::with_opts(Opts::new(name, help)).unwrap();
+
+        PoolCollector {
+            connections: gauge("db_pool_connections", "Open database connections"),
+            idle: gauge("db_pool_idle_connections", "Idle database connections"),
+            max: gauge("db_pool_max_connections", "Maximum database connections"),
+            pool,
+        }
+    }
+}
+
+impl Collector for PoolCollector {
+    fn desc(&self) -> Vec<&Desc> {
+        [&self.connections, &self.idle, &self.max]
+            .into_iter()
+            .flat_map(|gauge| gauge.desc())
+            .collect()
+    }
+
+    fn collect(&self) -> Vec<MetricFamily> {
+        self.connections.set(self.pool.size() as i64);
+        self.idle.set(self.pool.num_idle() as i64);
+        self.max
+            .set(self.pool.options().get_max_connections() as i64);
+
+        [&self.connections, &self.idle, &self.max]
+            .into_iter()
+            .flat_map(|gauge| gauge.collect())
+            .collect()
+    }
+}
+
+/// In-memory SQLite database with the migrations applied, shared by the store tests.
+#[cfg(test)]
+pub(crate) async fn sqlite_memory() -> sqlx::SqlitePool {
+    // satu koneksi agar database in-memory tidak hilang di antara query
+    let pool = sqlx::sqlite::SqlitePoolOptions::new()
+        .max_connections(1)
+        .connect("sqlite::memory:")
+        .await
+        .unwrap();
+    sqlx::migrate!("./migrations/sqlite")
+        .run(&pool)
+        .await
+        .unwrap();
+    pool
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{routing::get, Router};
+    use axum_test::TestServer;
+
+    use super::*;
+    use crate::{test_support::TestApp, Config};
+
+    // Koneksi dan transaksi per request
+    #[tokio::test]
+    async fn test_db_extractor() {
+        async fn create(mut db: Db) -> Result<String, AppError> {
+            sqlx::query("CREATE TABLE IF NOT EXISTS counters (value INTEGER NOT NULL)")
+                .execute(&mut *db)
+                .await?;
+
+            // transaksi yang tidak di-commit dibatalkan
+            let mut tx = db.begin().await?;
+            sqlx::query("INSERT INTO counters (value) VALUES (1)")
+                .execute(&mut *tx)
+                .await?;
+            drop(tx);
+
+            let mut tx = db.begin().await?;
+            sqlx::query("INSERT INTO counters (value) VALUES (2)")
+                .execute(&mut *tx)
+                .await?;
+            tx.commit().await?;
+
+            let (total,): (i64,) = sqlx::query_as("SELECT SUM(value) FROM counters")
+                .fetch_one(&mut *db)
+                .await?;
+            Ok(format!("Total {}", total))
+        }
+
+        let state = AppState::new(Config::default()).unwrap();
+        let app = Router::new()
+            .route("/create", get(create))
+            .with_state(state);
+        let server = TestServer::new(app).unwrap();
+
+        server.get("/create").await.assert_text("Total 2");
+        server.get("/create").await.assert_text("Total 4");
+    }
+
+    // Config yang tidak lewat validate tidak membuat AppState::new panic
+    #[tokio::test]
+    async fn test_invalid_database_url() {
+        let mut config = Config::default();
+        config.database.url = "mysql://localhost/app".to_string();
+        assert!(AppState::new(config).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_short_cookie_key() {
+        let mut config = Config::default();
+        config.cookie.key = Some("terlalu pendek".to_string());
+        assert!(AppState::new(config).is_err());
+
+        let mut config = Config::default();
+        config.cookie.key = Some("k".repeat(32));
+        config.cookie.previous_keys = vec!["terlalu pendek".to_string()];
+        assert!(AppState::new(config).is_err());
+    }
+
+    // Metrics pool
+    #[tokio::test]
+    async fn test_pool_metrics() {
+        let (server, state) = TestApp::new()
+            .config(|config| config.database.max_connections = 3)
+            .server();
+
+        drop(state.db.acquire().await.unwrap());
+
+        let response = server.get("/metrics").await;
+        response.assert_status_ok();
+        response.assert_text_contains("db_pool_connections 1");
+        response.assert_text_contains("# TYPE db_pool_idle_connections gauge");
+        response.assert_text_contains("db_pool_max_connections 3");
+    }
+}
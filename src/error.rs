@@ -0,0 +1,418 @@
+use axum::{
+    body::Body,
+    extract::Request,
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use http::{header, HeaderMap, HeaderValue, StatusCode};
+use serde::Serialize;
+
+use crate::middleware::RequestId;
+
+pub const PROBLEM_JSON: &str = "application/problem+json";
+
+// Error Handling
+#[derive(Debug, thiserror::Error)]
+pub enum AppError {
+    #[error("{message}")]
+    Validation { code: &'static str, message: String },
+
+    #[error("{message}")]
+    Unauthorized { code: &'static str, message: String },
+
+    #[error("{message}")]
+    Forbidden { code: &'static str, message: String },
+
+    #[error("{message}")]
+    NotFound { code: &'static str, message: String },
+
+    #[error("{message}")]
+    Conflict { code: &'static str, message: String },
+
+    #[error("{message}")]
+    Locked { code: &'static str, message: String },
+
+    #[error("{message}")]
+    PayloadTooLarge { code: &'static str, message: String },
+
+    #[error("{message}")]
+    UnsupportedMediaType { code: &'static str, message: String },
+
+    #[error("{message}")]
+    TooManyRequests { code: &'static str, message: String },
+
+    #[error("{message}")]
+    Unprocessable {
+        code: &'static str,
+        message: String,
+        errors: Vec<FieldError>,
+    },
+
+    #[error(transparent)]
+    Internal(#[from] anyhow::Error),
+}
+
+impl AppError {
+    pub fn validation(code: &'static str, message: impl Into<String>) -> AppError {
+        AppError::Validation {
+            code,
+            message: message.into(),
+        }
+    }
+
+    pub fn unauthorized(code: &'static str, message: impl Into<String>) -> AppError {
+        AppError::Unauthorized {
+            code,
+            message: message.into(),
+        }
+    }
+
+    pub fn forbidden(code: &'static str, message: impl Into<String>) -> AppError {
+        AppError::Forbidden {
+            code,
+            message: message.into(),
+        }
+    }
+
+    pub fn not_found(code: &'static str, message: impl Into<String>) -> AppError {
+        AppError::NotFound {
+            code,
+            message: message.into(),
+        }
+    }
+
+    pub fn conflict(code: &'static str, message: impl Into<String>) -> AppError {
+        AppError::Conflict {
+            code,
+            message: message.into(),
+        }
+    }
+
+    pub fn locked(code: &'static str, message: impl Into<String>) -> AppError {
+        AppError::Locked {
+            code,
+            message: message.into(),
+        }
+    }
+
+    pub fn payload_too_large(code: &'static str, message: impl Into<String>) -> AppError {
+        AppError::PayloadTooLarge {
+            code,
+            message: message.into(),
+        }
+    }
+
+    pub fn unsupported_media_type(code: &'static str, message: impl Into<String>) -> AppError {
+        AppError::UnsupportedMediaType {
+            code,
+            message: message.into(),
+        }
+    }
+
+    pub fn too_many_requests(code: &'static str, message: impl Into<String>) -> AppError {
+        AppError::TooManyRequests {
+            code,
+            message: message.into(),
+        }
+    }
+
+    pub fn unprocessable(
+        code: &'static str,
+        message: impl Into<String>,
+        errors: Vec<FieldError>,
+    ) -> AppError {
+        AppError::Unprocessable {
+            code,
+            message: message.into(),
+            errors,
+        }
+    }
+
+    pub fn status(&self) -> StatusCode {
+        match self {
+            AppError::Validation { .. } => StatusCode::BAD_REQUEST,
+            AppError::Unauthorized { .. } => StatusCode::UNAUTHORIZED,
+            AppError::Forbidden { .. } => StatusCode::FORBIDDEN,
+            AppError::NotFound { .. } => StatusCode::NOT_FOUND,
+            AppError::Conflict { .. } => StatusCode::CONFLICT,
+            AppError::Locked { .. } => StatusCode::LOCKED,
+            AppError::PayloadTooLarge { .. } => StatusCode::PAYLOAD_TOO_LARGE,
+            AppError::UnsupportedMediaType { .. } => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            AppError::Unprocessable { .. } => StatusCode::UNPROCESSABLE_ENTITY,
+            AppError::TooManyRequests { .. } => StatusCode::TOO_MANY_REQUESTS,
+            AppError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    // kode yang bisa dibaca mesin, contoh: "token_expired"
+    pub fn code(&self) -> &'static str {
+        match self {
+            AppError::Validation { code, .. }
+            | AppError::Unauthorized { code, .. }
+            | AppError::Forbidden { code, .. }
+            | AppError::NotFound { code, .. }
+            | AppError::Conflict { code, .. }
+            | AppError::Locked { code, .. }
+            | AppError::PayloadTooLarge { code, .. }
+            | AppError::UnsupportedMediaType { code, .. }
+            | AppError::TooManyRequests { code, .. }
+            | AppError::Unprocessable { code, .. } => code,
+            AppError::Internal(_) => "internal_error",
+        }
+    }
+
+    pub fn problem(&self) -> Problem {
+        let status = self.status();
+        let detail = match self {
+            // detail error internal tidak dikirim ke client
+            AppError::Internal(_) => "Internal Server Error".to_string(),
+            error => error.to_string(),
+        };
+        let errors = match self {
+            AppError::Unprocessable { errors, .. } => errors.clone(),
+            _ => Vec::new(),
+        };
+
+        Problem {
+            kind: "about:blank".to_string(),
+            title: status.canonical_reason().unwrap_or("Error").to_string(),
+            status: status.as_u16(),
+            detail,
+            code: self.code().to_string(),
+            errors,
+            request_id: None,
+        }
+    }
+}
+
+/// RFC 7807 problem details body.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Problem {
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub title: String,
+    pub status: u16,
+    pub detail: String,
+    pub code: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<FieldError>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+}
+
+// Error untuk satu field, field berupa path seperti "items[0].name"
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
+}
+
+impl FieldError {
+    pub fn new(field: impl Into<String>, message: impl Into<String>) -> FieldError {
+        FieldError {
+            field: field.into(),
+            message: message.into(),
+        }
+    }
+}
+
+impl Problem {
+    // "detail" diikuti satu baris per field error, request id di baris terakhir
+    pub fn to_text(&self) -> String {
+        let mut text = self.detail.clone();
+        for error in &self.errors {
+            text.push_str(&format!("\n{}: {}", error.field, error.message));
+        }
+        if let Some(request_id) = &self.request_id {
+            text.push_str(&format!("\nRequest ID: {}", request_id));
+        }
+        text
+    }
+}
+
+impl IntoResponse for AppError {
+    fn into_response(self) -> Response {
+        if let AppError::Internal(error) = &self {
+            tracing::error!(error = ?error, "internal error");
+        }
+
+        // default plain text, negotiate_error mengubahnya menjadi JSON bila diminta
+        let problem = self.problem();
+        let mut response = (self.status(), problem.to_text()).into_response();
+        response.extensions_mut().insert(problem);
+        response
+    }
+}
+
+// Middleware: render error sebagai problem+json bila client meminta JSON
+pub async fn negotiate_error(request: Request, next: Next) -> Response {
+    let wants_json = prefers_json(request.headers());
+    let request_id = request.extensions().get::<RequestId>().cloned();
+    let response = next.run(request).await;
+
+    let Some(mut problem) = response.extensions().get::<Problem>().cloned() else {
+        return response;
+    };
+    problem.request_id = request_id.map(|id| id.to_string());
+
+    let (mut parts, _) = response.into_parts();
+    parts.headers.remove(header::CONTENT_LENGTH);
+    let body = if wants_json {
+        parts
+            .headers
+            .insert(header::CONTENT_TYPE, HeaderValue::from_static(PROBLEM_JSON));
+        serde_json::to_vec(&problem).unwrap_or_default()
+    } else {
+        problem.to_text().into_bytes()
+    };
+    Response::from_parts(parts, Body::from(body))
+}
+
+// Bandingkan q-value JSON dengan text/plain pada header Accept
+fn prefers_json(headers: &HeaderMap) -> bool {
+    let Some(accept) = headers
+        .get(header::ACCEPT)
+        .and_then(|value| value.to_str().ok())
+    else {
+        return false;
+    };
+
+    let mut json = 0.0_f32;
+    let mut text = 0.0_f32;
+
+    for item in accept.split(',') {
+        let mut params = item.split(';').map(str::trim);
+        let media = params.next().unwrap_or("").to_ascii_lowercase();
+        let q = params
+            .find_map(|param| param.strip_prefix("q="))
+            .and_then(|q| q.parse().ok())
+            .unwrap_or(1.0);
+
+        if media == "application/json" || media == PROBLEM_JSON || media.ends_with("+json") {
+            json = json.max(q);
+        } else if media == "text/plain" || media == "text/*" || media == "*/*" {
+            text = text.max(q);
+        }
+    }
+
+    json > 0.0 && json > text
+}
+
+// Body error plain text diakhiri request id yang sama dengan header X-Request-Id
+#[cfg(test)]
+pub(crate) fn assert_error_text(response: &axum_test::TestResponse, detail: impl AsRef<str>) {
+    let request_id = response.header(&crate::middleware::X_REQUEST_ID);
+    response.assert_text(format!(
+        "{}\nRequest ID: {}",
+        detail.as_ref(),
+        request_id.to_str().unwrap()
+    ));
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{middleware::from_fn, routing::get, Router};
+    use axum_test::TestServer;
+
+    use super::*;
+    use crate::middleware::request_id_middleware;
+
+    fn server() -> TestServer {
+        async fn not_found() -> Result<String, AppError> {
+            Err(AppError::not_found(
+                "product_not_found",
+                "Product 1 not found",
+            ))
+        }
+
+        async fn internal() -> Result<String, AppError> {
+            Err(anyhow::anyhow!("database is down").into())
+        }
+
+        let app = Router::new()
+            .route("/not-found", get(not_found))
+            .route("/internal", get(internal))
+            .layer(from_fn(negotiate_error))
+            .layer(from_fn(request_id_middleware));
+
+        TestServer::new(app).unwrap()
+    }
+
+    // Plain text
+    #[tokio::test]
+    async fn test_error_plain_text() {
+        let server = server();
+
+        let response = server
+            .get("/not-found")
+            .add_header("X-Request-Id", "abc-123")
+            .await;
+        response.assert_status_not_found();
+        response.assert_text("Product 1 not found\nRequest ID: abc-123");
+
+        let response = server
+            .get("/internal")
+            .add_header("Accept", "text/plain, application/json;q=0.5")
+            .await;
+        response.assert_status(StatusCode::INTERNAL_SERVER_ERROR);
+        assert_error_text(&response, "Internal Server Error");
+    }
+
+    // application/problem+json
+    #[tokio::test]
+    async fn test_error_problem_json() {
+        let server = server();
+
+        let response = server
+            .get("/not-found")
+            .add_header("Accept", "application/json")
+            .add_header("X-Request-Id", "abc-123")
+            .await;
+        response.assert_status_not_found();
+        response.assert_header("Content-Type", PROBLEM_JSON);
+        response.assert_json(&serde_json::json!({
+            "type": "about:blank",
+            "title": "Not Found",
+            "status": 404,
+            "detail": "Product 1 not found",
+            "code": "product_not_found",
+            "request_id": "abc-123",
+        }));
+
+        let response = server
+            .get("/internal")
+            .add_header("Accept", "application/problem+json")
+            .await;
+        let problem: serde_json::Value = response.json();
+        assert_eq!(problem["code"], "internal_error");
+        assert_eq!(problem["detail"], "Internal Server Error");
+    }
+
+    #[test]
+    fn test_error_status() {
+        assert_eq!(
+            AppError::validation("bad_request", "Bad").status(),
+            StatusCode::BAD_REQUEST
+        );
+        assert_eq!(
+            AppError::unauthorized("missing_token", "Missing").status(),
+            StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(
+            AppError::forbidden("missing_permission", "Forbidden").status(),
+            StatusCode::FORBIDDEN
+        );
+        assert_eq!(
+            AppError::conflict("duplicate", "Duplicate").status(),
+            StatusCode::CONFLICT
+        );
+        assert_eq!(
+            AppError::locked("account_locked", "Locked").status(),
+            StatusCode::LOCKED
+        );
+        assert_eq!(
+            AppError::too_many_requests("rate_limited", "Slow down").status(),
+            StatusCode::TOO_MANY_REQUESTS
+        );
+    }
+}
@@ -0,0 +1,375 @@
+use std::{
+    convert::Infallible,
+    net::{IpAddr, SocketAddr},
+};
+
+use axum::{
+    extract::{
+        connect_info::MockConnectInfo,
+        multipart::{MultipartError, MultipartRejection},
+        rejection::{FormRejection, JsonRejection, PathRejection, QueryRejection},
+        ConnectInfo, FromRequest, FromRequestParts, Path, Query, Request,
+    },
+    response::{IntoResponse, Response},
+    Form, Json,
+};
+use http::{request::Parts, StatusCode};
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::{
+    error::{AppError, FieldError},
+    validation::{validate, Validate},
+};
+
+// Seperti Json<T>, tetapi setiap rejection diubah menjadi AppError
+// dan aturan Validate dijalankan sebelum handler
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ValidatedJson<T>(pub T);
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ValidatedForm<T>(pub T);
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ValidatedQuery<T>(pub T);
+
+// Seperti Path<T>, dipakai untuk ID bertipe; parameter yang tidak valid menjadi AppError
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PathParams<T>(pub T);
+
+// Alamat IP client dari ConnectInfo (atau MockConnectInfo di test);
+// None bila server tidak dijalankan dengan connect info
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClientIp(pub Option<IpAddr>);
+
+impl ClientIp {
+    pub fn from_parts(parts: &Parts) -> ClientIp {
+        let extensions = &parts.extensions;
+        let addr = match extensions.get::<ConnectInfo<SocketAddr>>() {
+            Some(ConnectInfo(addr)) => Some(addr),
+            None => extensions
+                .get::<MockConnectInfo<SocketAddr>>()
+                .map(|MockConnectInfo(addr)| addr),
+        };
+        ClientIp(addr.map(SocketAddr::ip))
+    }
+}
+
+impl<S> FromRequestParts<S> for ClientIp
+where
+    S: Send + Sync,
+{
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        Ok(ClientIp::from_parts(parts))
+    }
+}
+
+impl<T, S> FromRequest<S> for ValidatedJson<T>
+where
+    T: DeserializeOwned + Validate,
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request(request: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let Json(value) = Json::<T>::from_request(request, state)
+            .await
+            .map_err(json_rejection)?;
+        validate(&value)?;
+        Ok(ValidatedJson(value))
+    }
+}
+
+impl<T, S> FromRequest<S> for ValidatedForm<T>
+where
+    T: DeserializeOwned + Validate,
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request(request: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let Form(value) = Form::<T>::from_request(request, state)
+            .await
+            .map_err(form_rejection)?;
+        validate(&value)?;
+        Ok(ValidatedForm(value))
+    }
+}
+
+impl<T, S> FromRequestParts<S> for ValidatedQuery<T>
+where
+    T: DeserializeOwned + Validate,
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let Query(value) = Query::<T>::from_request_parts(parts, state)
+            .await
+            .map_err(query_rejection)?;
+        validate(&value)?;
+        Ok(ValidatedQuery(value))
+    }
+}
+
+impl<T, S> FromRequestParts<S> for PathParams<T>
+where
+    T: DeserializeOwned + Send,
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let Path(value) = Path::<T>::from_request_parts(parts, state)
+            .await
+            .map_err(path_rejection)?;
+        Ok(PathParams(value))
+    }
+}
+
+impl<T> IntoResponse for ValidatedJson<T>
+where
+    T: Serialize,
+{
+    fn into_response(self) -> Response {
+        Json(self.0).into_response()
+    }
+}
+
+pub fn json_rejection(rejection: JsonRejection) -> AppError {
+    match rejection {
+        JsonRejection::JsonDataError(error) => {
+            let field_error =
+                path_error(&error).unwrap_or_else(|| FieldError::new("", error.body_text()));
+            AppError::unprocessable("invalid_json_data", "Invalid JSON body", vec![field_error])
+        }
+        JsonRejection::JsonSyntaxError(error) => {
+            let message = match path_error(&error) {
+                Some(FieldError { field, message }) if field != "." => {
+                    format!("{} at {}", message, field)
+                }
+                Some(FieldError { message, .. }) => message,
+                None => error.body_text(),
+            };
+            AppError::validation(
+                "invalid_json_syntax",
+                format!("Malformed JSON body: {}", message),
+            )
+        }
+        JsonRejection::MissingJsonContentType(_) => AppError::unsupported_media_type(
+            "missing_json_content_type",
+            "Expected request with `Content-Type: application/json`",
+        ),
+        JsonRejection::BytesRejection(error) if error.status() == StatusCode::PAYLOAD_TOO_LARGE => {
+            AppError::payload_too_large("payload_too_large", "Request body is too large")
+        }
+        rejection => AppError::validation("invalid_body", rejection.body_text()),
+    }
+}
+
+pub fn form_rejection(rejection: FormRejection) -> AppError {
+    match rejection {
+        FormRejection::InvalidFormContentType(_) => AppError::unsupported_media_type(
+            "invalid_form_content_type",
+            "Expected request with `Content-Type: application/x-www-form-urlencoded`",
+        ),
+        FormRejection::FailedToDeserializeFormBody(error) => AppError::unprocessable(
+            "invalid_form_data",
+            "Invalid form body",
+            vec![FieldError::new("", error.body_text())],
+        ),
+        FormRejection::BytesRejection(error) if error.status() == StatusCode::PAYLOAD_TOO_LARGE => {
+            AppError::payload_too_large("payload_too_large", "Request body is too large")
+        }
+        rejection => AppError::validation("invalid_form", rejection.body_text()),
+    }
+}
+
+impl From<MultipartRejection> for AppError {
+    fn from(rejection: MultipartRejection) -> Self {
+        AppError::unsupported_media_type("invalid_multipart_content_type", rejection.body_text())
+    }
+}
+
+// Setiap error saat membaca multipart menjadi AppError, handler cukup memakai `?`
+impl From<MultipartError> for AppError {
+    fn from(error: MultipartError) -> Self {
+        match error.status() {
+            StatusCode::PAYLOAD_TOO_LARGE => {
+                AppError::payload_too_large("payload_too_large", "Request body is too large")
+            }
+            StatusCode::BAD_REQUEST => AppError::validation("invalid_multipart", error.body_text()),
+            // contoh: koneksi client terputus saat body masih dibaca
+            _ => anyhow::Error::from(error).into(),
+        }
+    }
+}
+
+pub fn query_rejection(rejection: QueryRejection) -> AppError {
+    AppError::validation("invalid_query", rejection.body_text())
+}
+
+pub fn path_rejection(rejection: PathRejection) -> AppError {
+    match rejection {
+        PathRejection::FailedToDeserializePathParams(error) => {
+            AppError::validation("invalid_path", error.body_text())
+        }
+        // route tanpa parameter yang diminta adalah bug di aplikasi
+        rejection => anyhow::anyhow!(rejection.body_text()).into(),
+    }
+}
+
+// axum memakai serde_path_to_error, ambil path field dari rantai source error
+fn path_error(error: &(dyn std::error::Error + 'static)) -> Option<FieldError> {
+    let mut source = error.source();
+    while let Some(error) = source {
+        if let Some(error) = error.downcast_ref::<serde_path_to_error::Error<serde_json::Error>>() {
+            return Some(FieldError::new(
+                error.path().to_string(),
+                error.inner().to_string(),
+            ));
+        }
+        source = error.source();
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{
+        extract::DefaultBodyLimit,
+        routing::{get, post},
+        Router,
+    };
+    use axum_test::TestServer;
+    use serde::Deserialize;
+    use serde_json::json;
+
+    use super::*;
+
+    #[derive(Debug, Deserialize, Validate)]
+    struct Order {
+        #[validate(nested)]
+        items: Vec<Item>,
+    }
+
+    #[derive(Debug, Deserialize, Validate)]
+    struct Item {
+        #[validate(length(min = 1))]
+        name: String,
+        #[validate(range(min = 1, max = 10))]
+        quantity: u32,
+    }
+
+    #[derive(Debug, Deserialize, Validate)]
+    struct Search {
+        #[validate(length(min = 2))]
+        q: String,
+        #[validate(range(max = 50))]
+        limit: Option<u32>,
+    }
+
+    fn server() -> TestServer {
+        async fn order(ValidatedJson(order): ValidatedJson<Order>) -> String {
+            let item = &order.items[0];
+            format!("{} x{}", item.name, item.quantity)
+        }
+
+        async fn order_form(ValidatedForm(item): ValidatedForm<Item>) -> String {
+            format!("{} x{}", item.name, item.quantity)
+        }
+
+        async fn search(ValidatedQuery(search): ValidatedQuery<Search>) -> String {
+            format!("Search {} {}", search.q, search.limit.unwrap_or(10))
+        }
+
+        let app = Router::new()
+            .route("/orders", post(order))
+            .route("/orders/form", post(order_form))
+            .route("/search", get(search))
+            .layer(DefaultBodyLimit::max(64));
+
+        TestServer::new(app).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_validated_json() {
+        let response = server()
+            .post("/orders")
+            .json(&json!({"items": [{"name": "Buku", "quantity": 2}]}))
+            .await;
+        response.assert_status_ok();
+        response.assert_text("Buku x2");
+    }
+
+    #[tokio::test]
+    async fn test_validated_json_rejection() {
+        let server = server();
+
+        let response = server.post("/orders").text("tidak valid").await;
+        response.assert_status(StatusCode::UNSUPPORTED_MEDIA_TYPE);
+
+        let response = server
+            .post("/orders")
+            .text("{\"items\": [")
+            .content_type("application/json")
+            .await;
+        response.assert_status_bad_request();
+        response.assert_text_contains("Malformed JSON body");
+
+        let response = server
+            .post("/orders")
+            .json(&json!({"items": [{"name": "Buku", "quantity": -1}]}))
+            .await;
+        response.assert_status(StatusCode::UNPROCESSABLE_ENTITY);
+        response.assert_text_contains("\nitems[0].quantity: invalid value");
+
+        let response = server
+            .post("/orders")
+            .json(&json!({"items": [{"name": "x".repeat(100), "quantity": 1}]}))
+            .await;
+        response.assert_status(StatusCode::PAYLOAD_TOO_LARGE);
+    }
+
+    // Validasi
+    #[tokio::test]
+    async fn test_validation_rejection() {
+        let server = server();
+
+        let response = server
+            .post("/orders")
+            .json(&json!({"items": [{"name": "", "quantity": 20}]}))
+            .await;
+        response.assert_status(StatusCode::UNPROCESSABLE_ENTITY);
+        response.assert_text(
+            "Request validation failed\n\
+             items[0].name: length must be at least 1\n\
+             items[0].quantity: must be between 1 and 10",
+        );
+
+        let response = server
+            .post("/orders/form")
+            .form(&json!({"name": "Buku", "quantity": 0}))
+            .await;
+        response.assert_status(StatusCode::UNPROCESSABLE_ENTITY);
+        response.assert_text("Request validation failed\nquantity: must be between 1 and 10");
+
+        let response = server.get("/search").add_query_param("q", "tv").await;
+        response.assert_status_ok();
+        response.assert_text("Search tv 10");
+
+        let response = server
+            .get("/search")
+            .add_query_param("q", "t")
+            .add_query_param("limit", 100)
+            .await;
+        response.assert_status(StatusCode::UNPROCESSABLE_ENTITY);
+        response.assert_text(
+            "Request validation failed\nlimit: must be at most 50\nq: length must be at least 2",
+        );
+
+        let response = server.get("/search").await;
+        response.assert_status_bad_request();
+    }
+}
@@ -0,0 +1,535 @@
+pub mod storage;
+pub mod thumbnail;
+
+use std::fmt;
+
+use axum::{
+    extract::{multipart::Field, Multipart, State},
+    response::{IntoResponse, Response},
+    routing::get,
+    Router,
+};
+use http::{header, HeaderMap, HeaderValue, StatusCode};
+use rand::{rngs::OsRng, RngCore};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use validator::Validate;
+
+use crate::{
+    config::UploadConfig,
+    error::AppError,
+    extract::{PathParams, ValidatedQuery},
+    state::AppState,
+};
+
+pub use self::storage::{
+    FileStorage, FileWriter, InMemoryFileStorage, LocalFileStorage, StoredFile,
+};
+
+// Tipe yang bisa dikenali dari magic bytes, hanya ini yang boleh ada di upload.allowed_types
+pub const SNIFFABLE_TYPES: &[&str] = &["image/png", "image/jpeg", "image/gif", "image/webp"];
+
+// Magic bytes terpanjang yang dicek oleh sniff (RIFF....WEBP)
+const SNIFF_LEN: usize = 12;
+
+// ID acak 128 bit dalam hex, aman dipakai sebagai nama file dan di URL
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct FileId(String);
+
+#[derive(Debug, thiserror::Error)]
+#[error("file id must be 32 lowercase hex characters")]
+pub struct InvalidFileId;
+
+impl FileId {
+    pub fn generate() -> FileId {
+        let mut bytes = [0u8; 16];
+        OsRng.fill_bytes(&mut bytes);
+        FileId(bytes.iter().map(|byte| format!("{:02x}", byte)).collect())
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl TryFrom<String> for FileId {
+    type Error = InvalidFileId;
+
+    fn try_from(id: String) -> Result<Self, Self::Error> {
+        if id.len() == 32
+            && id
+                .bytes()
+                .all(|byte| matches!(byte, b'0'..=b'9' | b'a'..=b'f'))
+        {
+            Ok(FileId(id))
+        } else {
+            Err(InvalidFileId)
+        }
+    }
+}
+
+impl From<FileId> for String {
+    fn from(id: FileId) -> Self {
+        id.0
+    }
+}
+
+impl fmt::Display for FileId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UploadedFile {
+    pub id: FileId,
+    pub content_type: String,
+    pub size: u64,
+    // hex, dihitung saat file ditulis
+    pub sha256: String,
+}
+
+/// Detects the file type from its magic bytes; the client's Content-Type is never trusted.
+pub fn sniff(data: &[u8]) -> Option<&'static str> {
+    if data.starts_with(b"\x89PNG\r\n\x1a\n") {
+        Some("image/png")
+    } else if data.starts_with(b"\xff\xd8\xff") {
+        Some("image/jpeg")
+    } else if data.starts_with(b"GIF87a") || data.starts_with(b"GIF89a") {
+        Some("image/gif")
+    } else if data.len() >= 12 && data.starts_with(b"RIFF") && &data[8..12] == b"WEBP" {
+        Some("image/webp")
+    } else {
+        None
+    }
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct DownloadQuery {
+    // salah satu dari upload.thumbnail_sizes, kosong berarti file asli
+    pub size: Option<u32>,
+}
+
+// Route /api/files
+pub fn router() -> Router<AppState> {
+    Router::new().route("/{id}", get(download))
+}
+
+/// Streams the multipart field `field_name` into storage, enforcing `upload.max_size` and
+/// `upload.allowed_types` while the chunks arrive.
+///
+/// The file must be the only field, so the request body stays bounded by the file limit.
+pub async fn upload(
+    state: &AppState,
+    mut multipart: Multipart,
+    field_name: &str,
+) -> Result<UploadedFile, AppError> {
+    let mut field = match multipart.next_field().await? {
+        Some(field) if field.name() == Some(field_name) => field,
+        Some(field) => {
+            return Err(AppError::validation(
+                "unexpected_field",
+                format!(
+                    "Unexpected multipart field {}",
+                    field.name().unwrap_or_default()
+                ),
+            ))
+        }
+        None => {
+            return Err(AppError::validation(
+                "missing_file",
+                format!("Multipart field {} is required", field_name),
+            ))
+        }
+    };
+
+    let id = FileId::generate();
+    let mut writer = state.files.create(&id).await?;
+    match receive(&mut field, writer.as_mut(), &state.config.upload).await {
+        Ok(received) => {
+            writer.commit(received.content_type).await?;
+            Ok(UploadedFile {
+                id,
+                content_type: received.content_type.to_string(),
+                size: received.size,
+                sha256: received.sha256,
+            })
+        }
+        Err(error) => {
+            // error dari upload lebih penting untuk client daripada error saat membersihkan
+            if let Err(abort_error) = writer.abort().await {
+                tracing::warn!(file = %id, error = %abort_error, "failed to discard aborted upload");
+            }
+            Err(error)
+        }
+    }
+}
+
+struct Received {
+    content_type: &'static str,
+    size: u64,
+    sha256: String,
+}
+
+// Chunk langsung diteruskan ke storage, memori yang dipakai tidak bergantung pada ukuran file
+async fn receive(
+    field: &mut Field<'_>,
+    writer: &mut dyn FileWriter,
+    config: &UploadConfig,
+) -> Result<Received, AppError> {
+    let mut hasher = Sha256::new();
+    let mut size = 0u64;
+    let mut head = Vec::with_capacity(SNIFF_LEN);
+    let mut content_type = None;
+
+    while let Some(chunk) = field.chunk().await? {
+        size += chunk.len() as u64;
+        if size > config.max_size {
+            return Err(AppError::payload_too_large(
+                "file_too_large",
+                format!("File must not exceed {} bytes", config.max_size),
+            ));
+        }
+
+        // tipe dicek begitu magic bytes lengkap, sebelum apa pun ditulis ke storage
+        if content_type.is_none() {
+            let missing = SNIFF_LEN - head.len();
+            head.extend_from_slice(&chunk[..missing.min(chunk.len())]);
+            if head.len() == SNIFF_LEN {
+                content_type = Some(allowed_type(&head, config)?);
+            }
+        }
+
+        hasher.update(&chunk);
+        writer.write(&chunk).await?;
+    }
+
+    let content_type = match content_type {
+        Some(content_type) => content_type,
+        // file lebih pendek dari SNIFF_LEN
+        None => allowed_type(&head, config)?,
+    };
+
+    Ok(Received {
+        content_type,
+        size,
+        sha256: format!("{:x}", hasher.finalize()),
+    })
+}
+
+fn allowed_type(head: &[u8], config: &UploadConfig) -> Result<&'static str, AppError> {
+    sniff(head)
+        .filter(|content_type| {
+            config
+                .allowed_types
+                .iter()
+                .any(|allowed| allowed == content_type)
+        })
+        .ok_or_else(|| {
+            AppError::unsupported_media_type(
+                "unsupported_file_type",
+                format!(
+                    "File type must be one of {}",
+                    config.allowed_types.join(", ")
+                ),
+            )
+        })
+}
+
+/// Deletes the file together with its thumbnails.
+pub async fn delete(state: &AppState, id: &FileId) -> anyhow::Result<bool> {
+    // file asli dihapus dulu, lihat thumbnail::store
+    let existed = state.files.delete(id).await?;
+    for size in &state.config.upload.thumbnail_sizes {
+        state
+            .thumbnails
+            .delete(&thumbnail::thumbnail_id(id, *size))
+            .await?;
+    }
+    Ok(existed)
+}
+
+fn not_found(id: &FileId) -> AppError {
+    AppError::not_found("file_not_found", format!("File {} not found", id))
+}
+
+async fn find(state: &AppState, id: &FileId) -> Result<StoredFile, AppError> {
+    state.files.get(id).await?.ok_or_else(|| not_found(id))
+}
+
+async fn download(
+    State(state): State<AppState>,
+    PathParams(id): PathParams<FileId>,
+    ValidatedQuery(query): ValidatedQuery<DownloadQuery>,
+    headers: HeaderMap,
+) -> Result<Response, AppError> {
+    let (file, etag) = match query.size {
+        None => (find(&state, &id).await?, format!("\"{}\"", id)),
+        Some(size) => {
+            let sizes = &state.config.upload.thumbnail_sizes;
+            if !sizes.contains(&size) {
+                let sizes: Vec<String> = sizes.iter().map(u32::to_string).collect();
+                return Err(AppError::validation(
+                    "invalid_thumbnail_size",
+                    format!("Thumbnail size must be one of {}", sizes.join(", ")),
+                ));
+            }
+
+            // thumbnail yang belum dibuat oleh background task dibuat sekarang;
+            // ID thumbnail tidak ada di state.files, jadi thumbnail dari thumbnail selalu 404
+            let thumbnail_id = thumbnail::thumbnail_id(&id, size);
+            let file = match state.thumbnails.get(&thumbnail_id).await? {
+                Some(file) => file,
+                None => {
+                    let original = find(&state, &id).await?;
+                    let file =
+                        tokio::task::spawn_blocking(move || thumbnail::resize(&original, size))
+                            .await
+                            .map_err(anyhow::Error::from)?
+                            .map_err(|error| {
+                                AppError::unprocessable(
+                                    "invalid_image",
+                                    format!("File {} is not a readable image: {}", id, error),
+                                    Vec::new(),
+                                )
+                            })?;
+                    if !thumbnail::store(
+                        state.files.as_ref(),
+                        state.thumbnails.as_ref(),
+                        &id,
+                        size,
+                        &file,
+                    )
+                    .await?
+                    {
+                        return Err(not_found(&id));
+                    }
+                    file
+                }
+            };
+            (file, format!("\"{}-{}\"", id, size))
+        }
+    };
+
+    // isi file dengan id yang sama tidak pernah berubah, jadi boleh di-cache selamanya
+    let etag = HeaderValue::from_str(&etag).unwrap();
+    let cache_headers = [
+        (
+            header::CACHE_CONTROL,
+            HeaderValue::from_static("public, max-age=31536000, immutable"),
+        ),
+        (header::ETAG, etag.clone()),
+    ];
+
+    if headers.get(header::IF_NONE_MATCH) == Some(&etag) {
+        return Ok((StatusCode::NOT_MODIFIED, cache_headers).into_response());
+    }
+
+    let content_type = HeaderValue::from_str(&file.content_type).map_err(anyhow::Error::from)?;
+    Ok((
+        cache_headers,
+        [
+            (header::CONTENT_TYPE, content_type),
+            (
+                header::X_CONTENT_TYPE_OPTIONS,
+                HeaderValue::from_static("nosniff"),
+            ),
+        ],
+        file.data,
+    )
+        .into_response())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use axum::{body::Bytes, extract::DefaultBodyLimit, routing::post, Json};
+    use axum_test::{
+        multipart::{MultipartForm, Part},
+        TestServer,
+    };
+
+    use super::*;
+    use crate::{error::assert_error_text, Config};
+
+    fn server(dir: &std::path::Path, max_size: u64) -> TestServer {
+        async fn handler(
+            State(state): State<AppState>,
+            multipart: Multipart,
+        ) -> Result<Json<UploadedFile>, AppError> {
+            Ok(Json(upload(&state, multipart, "file").await?))
+        }
+
+        let mut config = Config::default();
+        config.upload.max_size = max_size;
+        let state = AppState::new(config)
+            .unwrap()
+            .with_files(Arc::new(LocalFileStorage::new(dir)));
+        let app = Router::new()
+            .route("/upload", post(handler).layer(DefaultBodyLimit::disable()))
+            .with_state(state);
+        TestServer::new(app).unwrap()
+    }
+
+    fn file(data: Vec<u8>) -> MultipartForm {
+        MultipartForm::new().add_part("file", Part::bytes(data).file_name("foto.png"))
+    }
+
+    // File besar diterima per chunk dan langsung ditulis ke storage
+    #[tokio::test]
+    async fn test_streaming_upload() {
+        let dir = std::env::temp_dir().join(format!("rust-axum-web-{}-upload", std::process::id()));
+        let server = server(&dir, 8 * 1024 * 1024);
+
+        let mut data = b"\x89PNG\r\n\x1a\n".to_vec();
+        data.resize(4 * 1024 * 1024, 7);
+        let response = server.post("/upload").multipart(file(data.clone())).await;
+        response.assert_status_ok();
+
+        let uploaded: UploadedFile = response.json();
+        assert_eq!(uploaded.content_type, "image/png");
+        assert_eq!(uploaded.size, data.len() as u64);
+        assert_eq!(uploaded.sha256, format!("{:x}", Sha256::digest(&data)));
+        assert_eq!(std::fs::read(dir.join(uploaded.id.as_str())).unwrap(), data);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    // Upload yang dibatalkan tidak meninggalkan file di storage
+    #[tokio::test]
+    async fn test_aborted_upload() {
+        let dir =
+            std::env::temp_dir().join(format!("rust-axum-web-{}-aborted", std::process::id()));
+        let server = server(&dir, 1024);
+
+        let mut data = b"GIF89a".to_vec();
+        data.resize(64 * 1024, 0);
+        let response = server.post("/upload").multipart(file(data)).await;
+        response.assert_status(StatusCode::PAYLOAD_TOO_LARGE);
+        response.assert_text("File must not exceed 1024 bytes");
+
+        let response = server
+            .post("/upload")
+            .multipart(file(b"%PDF-1.7 bukan gambar".to_vec()))
+            .await;
+        response.assert_status(StatusCode::UNSUPPORTED_MEDIA_TYPE);
+
+        // file kecil, lebih pendek dari magic bytes WebP
+        let response = server
+            .post("/upload")
+            .multipart(file(b"GIF87a".to_vec()))
+            .await;
+        response.assert_status_ok();
+
+        let response = server
+            .post("/upload")
+            .content_type("multipart/form-data; boundary=batas")
+            .text(
+                "--batas\r\nContent-Disposition: form-data; name=\"file\"\r\n\r\nGIF89a terpotong",
+            )
+            .await;
+        response.assert_status(StatusCode::BAD_REQUEST);
+        response.assert_text_contains("multipart");
+
+        // hanya file GIF87a yang tersimpan (isi + metadata)
+        let mut names: Vec<String> = std::fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect();
+        names.sort();
+        assert_eq!(names.len(), 2);
+        assert!(names.iter().all(|name| !name.ends_with(".tmp")));
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    // Thumbnail yang belum ada dibuat saat diminta
+    #[tokio::test]
+    async fn test_thumbnail_on_demand() {
+        let state = AppState::new(Config::default()).unwrap();
+        let server = TestServer::new(crate::build_app(state.clone())).unwrap();
+
+        let mut data = Vec::new();
+        image::DynamicImage::new_rgb8(100, 200)
+            .write_to(
+                &mut std::io::Cursor::new(&mut data),
+                image::ImageFormat::Jpeg,
+            )
+            .unwrap();
+        let id = FileId::generate();
+        let original = StoredFile {
+            content_type: "image/jpeg".to_string(),
+            data: data.into(),
+        };
+        state.files.put(&id, &original).await.unwrap();
+
+        let response = server
+            .get(&format!("/api/files/{}", id))
+            .add_query_param("size", 128)
+            .await;
+        response.assert_status_ok();
+        response.assert_header("Content-Type", "image/jpeg");
+        let image = image::load_from_memory(response.as_bytes()).unwrap();
+        assert_eq!((image.width(), image.height()), (64, 128));
+
+        let thumbnail_id = thumbnail::thumbnail_id(&id, 128);
+        let stored = state.thumbnails.get(&thumbnail_id).await.unwrap();
+        assert_eq!(stored.unwrap().data, response.as_bytes());
+
+        // thumbnail bukan file asli, tidak bisa diunduh langsung atau dibuatkan thumbnail lagi
+        let url = format!("/api/files/{}", thumbnail_id);
+        server.get(&url).await.assert_status_not_found();
+        let response = server.get(&url).add_query_param("size", 64).await;
+        response.assert_status_not_found();
+        assert_error_text(&response, format!("File {} not found", thumbnail_id));
+        assert!(state
+            .thumbnails
+            .get(&thumbnail::thumbnail_id(&thumbnail_id, 64))
+            .await
+            .unwrap()
+            .is_none());
+
+        // file yang bukan gambar valid
+        let broken = FileId::generate();
+        let file = StoredFile {
+            content_type: "image/png".to_string(),
+            data: Bytes::from_static(b"\x89PNG\r\n\x1a\nrusak"),
+        };
+        state.files.put(&broken, &file).await.unwrap();
+        let response = server
+            .get(&format!("/api/files/{}", broken))
+            .add_query_param("size", 64)
+            .await;
+        response.assert_status(StatusCode::UNPROCESSABLE_ENTITY);
+
+        server
+            .get(&format!("/api/files/{}", FileId::generate()))
+            .add_query_param("size", 64)
+            .await
+            .assert_status_not_found();
+    }
+
+    #[test]
+    fn test_sniff() {
+        assert_eq!(sniff(b"\x89PNG\r\n\x1a\n...."), Some("image/png"));
+        assert_eq!(sniff(b"\xff\xd8\xff\xe0...."), Some("image/jpeg"));
+        assert_eq!(sniff(b"GIF89a...."), Some("image/gif"));
+        assert_eq!(sniff(b"RIFF\x10\x00\x00\x00WEBPVP8 "), Some("image/webp"));
+        assert_eq!(sniff(b"RIFF\x10\x00\x00\x00WAVEfmt "), None);
+        assert_eq!(sniff(b"<svg xmlns=\"http://www.w3.org/2000/svg\">"), None);
+        assert_eq!(sniff(b""), None);
+    }
+
+    #[test]
+    fn test_file_id() {
+        let id = FileId::generate();
+        assert_eq!(FileId::try_from(id.to_string()).unwrap(), id);
+        assert_ne!(FileId::generate(), id);
+
+        for invalid in ["", "../../etc/passwd", "0123456789ABCDEF0123456789ABCDEF"] {
+            assert!(FileId::try_from(invalid.to_string()).is_err());
+        }
+    }
+}
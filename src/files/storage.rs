@@ -0,0 +1,343 @@
+use std::{
+    collections::HashMap,
+    ffi::OsString,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+    sync::{Arc, RwLock},
+};
+
+use async_trait::async_trait;
+use axum::body::Bytes;
+use serde::{Deserialize, Serialize};
+use tokio::{fs::File, io::AsyncWriteExt};
+
+use super::FileId;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StoredFile {
+    pub content_type: String,
+    pub data: Bytes,
+}
+
+// Penyimpanan isi file, bisa diganti dengan object storage (S3 dan sejenisnya)
+#[async_trait]
+pub trait FileStorage: Send + Sync {
+    /// Starts writing a file; nothing is visible through `get` until [`FileWriter::commit`].
+    async fn create(&self, id: &FileId) -> anyhow::Result<Box<dyn FileWriter>>;
+    async fn get(&self, id: &FileId) -> anyhow::Result<Option<StoredFile>>;
+    async fn delete(&self, id: &FileId) -> anyhow::Result<bool>;
+
+    async fn exists(&self, id: &FileId) -> anyhow::Result<bool> {
+        Ok(self.get(id).await?.is_some())
+    }
+
+    async fn put(&self, id: &FileId, file: &StoredFile) -> anyhow::Result<()> {
+        let mut writer = self.create(id).await?;
+        writer.write(&file.data).await?;
+        writer.commit(&file.content_type).await
+    }
+}
+
+// File yang sedang ditulis per chunk; writer yang di-drop tanpa commit tidak pernah terlihat
+#[async_trait]
+pub trait FileWriter: Send {
+    async fn write(&mut self, chunk: &[u8]) -> anyhow::Result<()>;
+    async fn commit(self: Box<Self>, content_type: &str) -> anyhow::Result<()>;
+    /// Discards everything written so far.
+    async fn abort(self: Box<Self>) -> anyhow::Result<()>;
+}
+
+// Implementasi in-memory; writer berbagi map dengan storage dan baru mengisinya saat commit
+#[derive(Debug, Default)]
+pub struct InMemoryFileStorage {
+    files: Arc<RwLock<HashMap<FileId, StoredFile>>>,
+}
+
+impl InMemoryFileStorage {
+    pub fn new() -> InMemoryFileStorage {
+        InMemoryFileStorage::default()
+    }
+}
+
+struct InMemoryFileWriter {
+    files: Arc<RwLock<HashMap<FileId, StoredFile>>>,
+    id: FileId,
+    data: Vec<u8>,
+}
+
+#[async_trait]
+impl FileStorage for InMemoryFileStorage {
+    async fn create(&self, id: &FileId) -> anyhow::Result<Box<dyn FileWriter>> {
+        Ok(Box::new(InMemoryFileWriter {
+            files: self.files.clone(),
+            id: id.clone(),
+            data: Vec::new(),
+        }))
+    }
+
+    async fn get(&self, id: &FileId) -> anyhow::Result<Option<StoredFile>> {
+        Ok(self.files.read().unwrap().get(id).cloned())
+    }
+
+    async fn delete(&self, id: &FileId) -> anyhow::Result<bool> {
+        Ok(self.files.write().unwrap().remove(id).is_some())
+    }
+}
+
+#[async_trait]
+impl FileWriter for InMemoryFileWriter {
+    async fn write(&mut self, chunk: &[u8]) -> anyhow::Result<()> {
+        self.data.extend_from_slice(chunk);
+        Ok(())
+    }
+
+    async fn commit(self: Box<Self>, content_type: &str) -> anyhow::Result<()> {
+        let file = StoredFile {
+            content_type: content_type.to_string(),
+            data: self.data.into(),
+        };
+        self.files.write().unwrap().insert(self.id, file);
+        Ok(())
+    }
+
+    async fn abort(self: Box<Self>) -> anyhow::Result<()> {
+        Ok(())
+    }
+}
+
+// Implementasi filesystem lokal: isi file di <dir>/<id>, metadata di <dir>/<id>.json
+#[derive(Debug, Clone)]
+pub struct LocalFileStorage {
+    dir: PathBuf,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Metadata {
+    content_type: String,
+}
+
+impl LocalFileStorage {
+    pub fn new(dir: impl Into<PathBuf>) -> LocalFileStorage {
+        LocalFileStorage { dir: dir.into() }
+    }
+
+    fn data_path(&self, id: &FileId) -> PathBuf {
+        self.dir.join(id.as_str())
+    }
+
+    fn metadata_path(&self, id: &FileId) -> PathBuf {
+        self.dir.join(format!("{}.json", id))
+    }
+}
+
+// Chunk ditulis ke <id>.<acak>.tmp, baru di-rename ke <id> saat commit
+struct LocalFileWriter {
+    file: File,
+    // None setelah commit atau abort; selain itu dihapus saat writer di-drop
+    temporary: Option<PathBuf>,
+    data_path: PathBuf,
+    metadata_path: PathBuf,
+}
+
+// nama unik per writer, dua writer untuk id yang sama tidak saling menimpa file sementara
+fn temporary_path(path: &Path) -> PathBuf {
+    let mut temporary = OsString::from(path);
+    temporary.push(format!(".{:016x}.tmp", rand::random::<u64>()));
+    temporary.into()
+}
+
+// tulis ke file sementara lalu rename, pembaca tidak pernah melihat file setengah jadi
+async fn write_atomic(path: &Path, contents: &[u8]) -> anyhow::Result<()> {
+    let temporary = temporary_path(path);
+    tokio::fs::write(&temporary, contents).await?;
+    tokio::fs::rename(&temporary, path).await?;
+    Ok(())
+}
+
+async fn remove_if_exists(path: &Path) -> anyhow::Result<bool> {
+    match tokio::fs::remove_file(path).await {
+        Ok(()) => Ok(true),
+        Err(error) if error.kind() == ErrorKind::NotFound => Ok(false),
+        Err(error) => Err(error.into()),
+    }
+}
+
+#[async_trait]
+impl FileStorage for LocalFileStorage {
+    async fn create(&self, id: &FileId) -> anyhow::Result<Box<dyn FileWriter>> {
+        tokio::fs::create_dir_all(&self.dir).await?;
+        let data_path = self.data_path(id);
+        let temporary = temporary_path(&data_path);
+
+        Ok(Box::new(LocalFileWriter {
+            file: File::create(&temporary).await?,
+            temporary: Some(temporary),
+            data_path,
+            metadata_path: self.metadata_path(id),
+        }))
+    }
+
+    async fn get(&self, id: &FileId) -> anyhow::Result<Option<StoredFile>> {
+        let metadata = match tokio::fs::read(self.metadata_path(id)).await {
+            Ok(metadata) => metadata,
+            Err(error) if error.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(error) => return Err(error.into()),
+        };
+        let metadata: Metadata = serde_json::from_slice(&metadata)?;
+        let data = tokio::fs::read(self.data_path(id)).await?;
+
+        Ok(Some(StoredFile {
+            content_type: metadata.content_type,
+            data: data.into(),
+        }))
+    }
+
+    // cukup cek metadata, isi file tidak perlu dibaca
+    async fn exists(&self, id: &FileId) -> anyhow::Result<bool> {
+        Ok(tokio::fs::try_exists(self.metadata_path(id)).await?)
+    }
+
+    async fn delete(&self, id: &FileId) -> anyhow::Result<bool> {
+        let existed = remove_if_exists(&self.metadata_path(id)).await?;
+        remove_if_exists(&self.data_path(id)).await?;
+        Ok(existed)
+    }
+}
+
+#[async_trait]
+impl FileWriter for LocalFileWriter {
+    async fn write(&mut self, chunk: &[u8]) -> anyhow::Result<()> {
+        self.file.write_all(chunk).await?;
+        Ok(())
+    }
+
+    async fn commit(mut self: Box<Self>, content_type: &str) -> anyhow::Result<()> {
+        self.file.sync_all().await?;
+        if let Some(temporary) = &self.temporary {
+            tokio::fs::rename(temporary, &self.data_path).await?;
+            self.temporary = None;
+        }
+
+        // metadata ditulis terakhir, file tanpa metadata dianggap tidak ada
+        let metadata = serde_json::to_vec(&Metadata {
+            content_type: content_type.to_string(),
+        })?;
+        write_atomic(&self.metadata_path, &metadata).await
+    }
+
+    async fn abort(mut self: Box<Self>) -> anyhow::Result<()> {
+        if let Some(temporary) = self.temporary.take() {
+            remove_if_exists(&temporary).await?;
+        }
+        Ok(())
+    }
+}
+
+// Writer yang di-drop tanpa commit atau abort, misalnya karena client memutus koneksi
+// di tengah upload dan future handler ikut di-drop, tidak boleh meninggalkan file sementara
+impl Drop for LocalFileWriter {
+    fn drop(&mut self) {
+        if let Some(temporary) = self.temporary.take() {
+            if let Err(error) = std::fs::remove_file(&temporary) {
+                if error.kind() != ErrorKind::NotFound {
+                    tracing::warn!(
+                        %error,
+                        path = %temporary.display(),
+                        "failed to remove temporary file"
+                    );
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::check_store_contract;
+
+    fn png(data: &'static [u8]) -> StoredFile {
+        StoredFile {
+            content_type: "image/png".to_string(),
+            data: Bytes::from_static(data),
+        }
+    }
+
+    // Kontrak FileStorage: isi baru terlihat setelah commit, abort tidak meninggalkan apa pun
+    async fn exercise(storage: &dyn FileStorage) {
+        let id = FileId::generate();
+        assert_eq!(storage.get(&id).await.unwrap(), None);
+
+        assert!(!storage.exists(&id).await.unwrap());
+
+        storage.put(&id, &png(b"pertama")).await.unwrap();
+        assert_eq!(storage.get(&id).await.unwrap(), Some(png(b"pertama")));
+        assert!(storage.exists(&id).await.unwrap());
+
+        // isi lama tetap terbaca sampai commit
+        let mut writer = storage.create(&id).await.unwrap();
+        writer.write(b"ked").await.unwrap();
+        writer.write(b"ua").await.unwrap();
+        assert_eq!(storage.get(&id).await.unwrap(), Some(png(b"pertama")));
+        writer.commit("image/png").await.unwrap();
+        assert_eq!(storage.get(&id).await.unwrap(), Some(png(b"kedua")));
+
+        // dua writer bersamaan untuk id yang sama, commit terakhir yang menang
+        let mut first = storage.create(&id).await.unwrap();
+        let mut second = storage.create(&id).await.unwrap();
+        first.write(b"ketiga").await.unwrap();
+        second.write(b"keempat").await.unwrap();
+        first.write(b"!").await.unwrap();
+        first.commit("image/png").await.unwrap();
+        assert_eq!(storage.get(&id).await.unwrap(), Some(png(b"ketiga!")));
+        second.commit("image/png").await.unwrap();
+        assert_eq!(storage.get(&id).await.unwrap(), Some(png(b"keempat")));
+
+        let other = FileId::generate();
+        let mut writer = storage.create(&other).await.unwrap();
+        writer.write(b"batal").await.unwrap();
+        writer.abort().await.unwrap();
+        assert_eq!(storage.get(&other).await.unwrap(), None);
+
+        assert!(storage.delete(&id).await.unwrap());
+        assert!(!storage.delete(&id).await.unwrap());
+        assert_eq!(storage.get(&id).await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_storage_contract() {
+        let dir = std::env::temp_dir().join(format!("rust-axum-web-{}-files", std::process::id()));
+        check_store_contract::<dyn FileStorage>(
+            vec![
+                ("in-memory", Box::new(InMemoryFileStorage::new())),
+                ("local", Box::new(LocalFileStorage::new(&dir))),
+            ],
+            async |store| exercise(store).await,
+        )
+        .await;
+
+        // tidak ada file sementara yang tertinggal
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 0);
+        std::fs::remove_dir(dir).unwrap();
+    }
+
+    // writer yang di-drop di tengah upload menghapus file sementaranya sendiri
+    #[tokio::test]
+    async fn test_local_writer_dropped() {
+        let dir = std::env::temp_dir().join(format!(
+            "rust-axum-web-{}-files-dropped",
+            std::process::id()
+        ));
+        let storage = LocalFileStorage::new(&dir);
+        let id = FileId::generate();
+
+        let mut writer = storage.create(&id).await.unwrap();
+        writer.write(b"terpo").await.unwrap();
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+        drop(writer);
+
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 0);
+        assert_eq!(storage.get(&id).await.unwrap(), None);
+        std::fs::remove_dir(dir).unwrap();
+    }
+}
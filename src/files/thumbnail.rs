@@ -0,0 +1,221 @@
+use std::{io::Cursor, sync::Arc};
+
+use axum::body::Bytes;
+use image::{DynamicImage, ImageFormat, ImageReader, Limits};
+use sha2::{Digest, Sha256};
+use tokio::task::JoinHandle;
+
+use super::{FileId, FileStorage, StoredFile};
+
+// Batas dimensi gambar sumber, mencegah decompression bomb
+const MAX_DIMENSION: u32 = 8192;
+
+/// ID of the thumbnail variant inside the thumbnail storage, which is kept apart from the originals.
+pub fn thumbnail_id(id: &FileId, size: u32) -> FileId {
+    let digest = Sha256::new()
+        .chain_update(id.as_str())
+        .chain_update(b":")
+        .chain_update(size.to_string())
+        .finalize();
+    let hex: String = digest[..16]
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect();
+    FileId::try_from(hex).expect("hex digest is a valid file id")
+}
+
+/// Scales the image down to fit within `size` x `size`, keeping the aspect ratio.
+///
+/// JPEG stays JPEG, every other format becomes PNG. CPU heavy, call from a blocking thread.
+pub fn resize(original: &StoredFile, size: u32) -> anyhow::Result<StoredFile> {
+    let mut reader = ImageReader::new(Cursor::new(&original.data)).with_guessed_format()?;
+    let mut limits = Limits::default();
+    limits.max_image_width = Some(MAX_DIMENSION);
+    limits.max_image_height = Some(MAX_DIMENSION);
+    reader.limits(limits);
+    let image = reader.decode()?;
+
+    // gambar kecil tidak diperbesar
+    let image = if image.width() > size || image.height() > size {
+        image.thumbnail(size, size)
+    } else {
+        image
+    };
+
+    let (format, content_type) = match original.content_type.as_str() {
+        "image/jpeg" => (ImageFormat::Jpeg, "image/jpeg"),
+        _ => (ImageFormat::Png, "image/png"),
+    };
+    let image = match format {
+        // JPEG tidak mendukung alpha channel
+        ImageFormat::Jpeg => DynamicImage::ImageRgb8(image.into_rgb8()),
+        _ => image,
+    };
+
+    let mut data = Vec::new();
+    image.write_to(&mut Cursor::new(&mut data), format)?;
+    Ok(StoredFile {
+        content_type: content_type.to_string(),
+        data: Bytes::from(data),
+    })
+}
+
+/// Stores the thumbnail of `id`, or returns false without keeping it if the original is gone.
+pub async fn store(
+    files: &dyn FileStorage,
+    thumbnails: &dyn FileStorage,
+    id: &FileId,
+    size: u32,
+    thumbnail: &StoredFile,
+) -> anyhow::Result<bool> {
+    let thumbnail_id = thumbnail_id(id, size);
+    thumbnails.put(&thumbnail_id, thumbnail).await?;
+
+    // files::delete menghapus file asli sebelum thumbnail-nya, jadi bila file asli
+    // masih ada di sini, thumbnail yang baru ditulis pasti ikut terhapus nanti
+    if files.exists(id).await? {
+        return Ok(true);
+    }
+    thumbnails.delete(&thumbnail_id).await?;
+    Ok(false)
+}
+
+// Dijalankan di background setelah upload, kegagalan hanya dicatat karena
+// thumbnail yang belum ada akan dibuat saat diminta. Task berhenti begitu
+// file asli terhapus (foto diganti atau akun dihapus).
+pub fn spawn_generate_all(
+    files: Arc<dyn FileStorage>,
+    thumbnails: Arc<dyn FileStorage>,
+    id: FileId,
+    sizes: Vec<u32>,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let original = match files.get(&id).await {
+            Ok(Some(original)) => original,
+            Ok(None) => return,
+            Err(error) => {
+                tracing::warn!(file = %id, %error, "failed to load file for thumbnails");
+                return;
+            }
+        };
+
+        for size in sizes {
+            let original = original.clone();
+            let stored = match tokio::task::spawn_blocking(move || resize(&original, size)).await {
+                Ok(Ok(thumbnail)) => {
+                    store(files.as_ref(), thumbnails.as_ref(), &id, size, &thumbnail).await
+                }
+                Ok(Err(error)) => Err(error),
+                Err(error) => Err(error.into()),
+            };
+            match stored {
+                Ok(true) => {}
+                Ok(false) => return,
+                Err(error) => {
+                    tracing::warn!(file = %id, size, %error, "failed to generate thumbnail")
+                }
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use image::{GenericImageView, RgbaImage};
+
+    use super::*;
+    use crate::files::InMemoryFileStorage;
+
+    fn png(width: u32, height: u32) -> StoredFile {
+        let image = DynamicImage::ImageRgba8(RgbaImage::new(width, height));
+        let mut data = Vec::new();
+        image
+            .write_to(&mut Cursor::new(&mut data), ImageFormat::Png)
+            .unwrap();
+        StoredFile {
+            content_type: "image/png".to_string(),
+            data: Bytes::from(data),
+        }
+    }
+
+    fn dimensions(file: &StoredFile) -> (u32, u32) {
+        image::load_from_memory(&file.data).unwrap().dimensions()
+    }
+
+    #[test]
+    fn test_resize() {
+        let thumbnail = resize(&png(300, 150), 64).unwrap();
+        assert_eq!(thumbnail.content_type, "image/png");
+        assert_eq!(dimensions(&thumbnail), (64, 32));
+
+        // tidak diperbesar
+        assert_eq!(dimensions(&resize(&png(40, 20), 64).unwrap()), (40, 20));
+
+        let jpeg = StoredFile {
+            content_type: "image/jpeg".to_string(),
+            ..png(1, 1)
+        };
+        let mut data = Vec::new();
+        image::load_from_memory(&jpeg.data)
+            .unwrap()
+            .into_rgb8()
+            .write_to(&mut Cursor::new(&mut data), ImageFormat::Jpeg)
+            .unwrap();
+        let jpeg = StoredFile {
+            data: Bytes::from(data),
+            ..jpeg
+        };
+        assert_eq!(resize(&jpeg, 64).unwrap().content_type, "image/jpeg");
+
+        let broken = StoredFile {
+            data: Bytes::from_static(b"\x89PNG\r\n\x1a\nrusak"),
+            ..png(1, 1)
+        };
+        assert!(resize(&broken, 64).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_spawn_generate_all() {
+        let files: Arc<dyn FileStorage> = Arc::new(InMemoryFileStorage::new());
+        let thumbnails: Arc<dyn FileStorage> = Arc::new(InMemoryFileStorage::new());
+        let id = FileId::generate();
+        files.put(&id, &png(300, 150)).await.unwrap();
+
+        spawn_generate_all(files.clone(), thumbnails.clone(), id.clone(), vec![64, 128])
+            .await
+            .unwrap();
+        let thumbnail = thumbnails.get(&thumbnail_id(&id, 64)).await.unwrap();
+        assert_eq!(dimensions(&thumbnail.unwrap()), (64, 32));
+        assert!(thumbnails.exists(&thumbnail_id(&id, 128)).await.unwrap());
+        // thumbnail tidak tercampur dengan file asli
+        assert!(!files.exists(&thumbnail_id(&id, 64)).await.unwrap());
+    }
+
+    // File asli terhapus selagi thumbnail dibuat, thumbnail tidak boleh tertinggal
+    #[tokio::test]
+    async fn test_store_after_delete() {
+        let files = InMemoryFileStorage::new();
+        let thumbnails = InMemoryFileStorage::new();
+        let id = FileId::generate();
+
+        let thumbnail = resize(&png(300, 150), 64).unwrap();
+        assert!(!store(&files, &thumbnails, &id, 64, &thumbnail)
+            .await
+            .unwrap());
+        assert!(!thumbnails.exists(&thumbnail_id(&id, 64)).await.unwrap());
+
+        files.put(&id, &png(300, 150)).await.unwrap();
+        assert!(store(&files, &thumbnails, &id, 64, &thumbnail)
+            .await
+            .unwrap());
+        assert!(thumbnails.exists(&thumbnail_id(&id, 64)).await.unwrap());
+    }
+
+    #[test]
+    fn test_thumbnail_id() {
+        let id = FileId::generate();
+        assert_eq!(thumbnail_id(&id, 64), thumbnail_id(&id, 64));
+        assert_ne!(thumbnail_id(&id, 64), thumbnail_id(&id, 128));
+        assert_ne!(thumbnail_id(&id, 64), id);
+    }
+}
@@ -0,0 +1,400 @@
+use std::{
+    collections::BTreeMap,
+    future::Future,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, RwLock,
+    },
+    time::{Duration, Instant},
+};
+
+use async_trait::async_trait;
+use axum::{extract::State, routing::get, Json, Router};
+use futures::future::join_all;
+use http::StatusCode;
+use serde::Serialize;
+
+use crate::state::AppState;
+
+// Flag readiness: false saat warm-up dan begitu shutdown dimulai
+#[derive(Debug, Clone, Default)]
+pub struct Readiness(Arc<AtomicBool>);
+
+impl Readiness {
+    pub fn new() -> Readiness {
+        Readiness::default()
+    }
+
+    pub fn is_ready(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+
+    pub fn set_ready(&self, ready: bool) {
+        self.0.store(ready, Ordering::SeqCst);
+    }
+}
+
+#[async_trait]
+pub trait HealthCheck: Send + Sync {
+    async fn check(&self) -> Result<(), String>;
+}
+
+// Closure async juga bisa dipakai sebagai health check
+#[async_trait]
+impl<F, Fut> HealthCheck for F
+where
+    F: Fn() -> Fut + Send + Sync,
+    Fut: Future<Output = Result<(), String>> + Send,
+{
+    async fn check(&self) -> Result<(), String> {
+        self().await
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Probe {
+    Liveness,
+    Readiness,
+}
+
+struct RegisteredCheck {
+    name: String,
+    probe: Probe,
+    timeout: Duration,
+    check: Arc<dyn HealthCheck>,
+}
+
+#[derive(Clone, Default)]
+pub struct HealthRegistry {
+    checks: Arc<RwLock<Vec<RegisteredCheck>>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CheckResult {
+    pub status: Status,
+    pub duration_ms: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Status {
+    Ok,
+    Fail,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct HealthReport {
+    pub status: Status,
+    pub checks: BTreeMap<String, CheckResult>,
+}
+
+impl HealthRegistry {
+    pub fn new() -> HealthRegistry {
+        HealthRegistry::default()
+    }
+
+    pub fn register<C>(&self, name: &str, probe: Probe, timeout: Duration, check: C)
+    where
+        C: HealthCheck + 'static,
+    {
+        self.checks.write().unwrap().push(RegisteredCheck {
+            name: name.to_string(),
+            probe,
+            timeout,
+            check: Arc::new(check),
+        });
+    }
+
+    /// Runs every check registered for `probe` concurrently, each bounded by its own timeout.
+    pub async fn run(&self, probe: Probe) -> HealthReport {
+        let checks: Vec<(String, Duration, Arc<dyn HealthCheck>)> = self
+            .checks
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|check| check.probe == probe)
+            .map(|check| (check.name.clone(), check.timeout, check.check.clone()))
+            .collect();
+
+        let results = join_all(checks.into_iter().map(|(name, timeout, check)| async move {
+            let started = Instant::now();
+            let result = match tokio::time::timeout(timeout, check.check()).await {
+                Ok(result) => result,
+                Err(_) => Err(format!("timed out after {:?}", timeout)),
+            };
+
+            let result = CheckResult {
+                status: if result.is_ok() {
+                    Status::Ok
+                } else {
+                    Status::Fail
+                },
+                duration_ms: started.elapsed().as_millis() as u64,
+                error: result.err(),
+            };
+            (name, result)
+        }))
+        .await;
+
+        HealthReport::new(results.into_iter().collect())
+    }
+}
+
+impl HealthReport {
+    fn new(checks: BTreeMap<String, CheckResult>) -> HealthReport {
+        let status = if checks.values().all(|check| check.status == Status::Ok) {
+            Status::Ok
+        } else {
+            Status::Fail
+        };
+
+        HealthReport { status, checks }
+    }
+
+    fn into_response(self) -> (StatusCode, Json<HealthReport>) {
+        let status = match self.status {
+            Status::Ok => StatusCode::OK,
+            Status::Fail => StatusCode::SERVICE_UNAVAILABLE,
+        };
+        (status, Json(self))
+    }
+}
+
+// Sumber informasi sisa ruang disk; test memakai nilai tetap agar tidak bergantung pada host
+pub trait DiskSpace: Send + Sync {
+    /// Bytes available to unprivileged users on the filesystem containing `path`.
+    fn free_space(&self, path: &Path) -> Result<u64, String>;
+}
+
+// Implementasi asli lewat statvfs
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Statvfs;
+
+impl DiskSpace for Statvfs {
+    fn free_space(&self, path: &Path) -> Result<u64, String> {
+        free_space(path)
+    }
+}
+
+// Cek sisa ruang disk pada path tertentu
+pub struct DiskSpaceCheck {
+    pub path: PathBuf,
+    pub min_free_bytes: u64,
+    pub disk: Arc<dyn DiskSpace>,
+}
+
+impl DiskSpaceCheck {
+    // batas dalam MiB, nilai yang terlalu besar dibulatkan ke u64::MAX byte
+    pub fn new(path: PathBuf, min_free_mb: u64) -> DiskSpaceCheck {
+        DiskSpaceCheck {
+            path,
+            min_free_bytes: min_free_mb.saturating_mul(1024 * 1024),
+            disk: Arc::new(Statvfs),
+        }
+    }
+
+    pub fn with_disk(mut self, disk: Arc<dyn DiskSpace>) -> DiskSpaceCheck {
+        self.disk = disk;
+        self
+    }
+}
+
+#[async_trait]
+impl HealthCheck for DiskSpaceCheck {
+    async fn check(&self) -> Result<(), String> {
+        let path = self.path.clone();
+        let disk = self.disk.clone();
+        let free = tokio::task::spawn_blocking(move || disk.free_space(&path))
+            .await
+            .map_err(|error| error.to_string())??;
+
+        if free < self.min_free_bytes {
+            return Err(format!(
+                "{} bytes free, need at least {}",
+                free, self.min_free_bytes
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(unix)]
+fn free_space(path: &Path) -> Result<u64, String> {
+    use std::{ffi::CString, os::unix::ffi::OsStrExt};
+
+    let path = CString::new(path.as_os_str().as_bytes()).map_err(|error| error.to_string())?;
+    let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+
+    // SAFETY: path adalah C string yang valid dan stat adalah buffer milik kita
+    if unsafe { libc::statvfs(path.as_ptr(), &mut stat) } != 0 {
+        return Err(std::io::Error::last_os_error().to_string());
+    }
+
+    Ok((stat.f_bavail as u64).saturating_mul(stat.f_frsize as u64))
+}
+
+#[cfg(not(unix))]
+fn free_space(_path: &Path) -> Result<u64, String> {
+    Ok(u64::MAX)
+}
+
+// Route /healthz dan /readyz
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/healthz", get(liveness))
+        .route("/readyz", get(readiness))
+}
+
+async fn liveness(State(state): State<AppState>) -> (StatusCode, Json<HealthReport>) {
+    state.health.run(Probe::Liveness).await.into_response()
+}
+
+async fn readiness(State(state): State<AppState>) -> (StatusCode, Json<HealthReport>) {
+    let mut report = state.health.run(Probe::Readiness).await;
+
+    if !state.readiness.is_ready() {
+        report.checks.insert(
+            "readiness".to_string(),
+            CheckResult {
+                status: Status::Fail,
+                duration_ms: 0,
+                error: Some("not ready".to_string()),
+            },
+        );
+        report.status = Status::Fail;
+    }
+
+    report.into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use axum_test::TestServer;
+    use serde_json::Value;
+
+    use super::*;
+    use crate::test_support::{self, TestApp};
+
+    // Disk palsu dengan sisa ruang tetap
+    struct FakeDisk(Result<u64, String>);
+
+    impl DiskSpace for FakeDisk {
+        fn free_space(&self, _path: &Path) -> Result<u64, String> {
+            self.0.clone()
+        }
+    }
+
+    fn disk_check(min_free_mb: u64, free: Result<u64, String>) -> DiskSpaceCheck {
+        DiskSpaceCheck::new(PathBuf::from("."), min_free_mb).with_disk(Arc::new(FakeDisk(free)))
+    }
+
+    fn server() -> (TestServer, AppState) {
+        let state = TestApp::new()
+            .config(|config| config.health.min_free_disk_mb = 0)
+            .state();
+        state.health.register(
+            "disk",
+            Probe::Readiness,
+            Duration::from_secs(1),
+            disk_check(100, Ok(200 * 1024 * 1024)),
+        );
+        state.health.register(
+            "always_ok",
+            Probe::Liveness,
+            Duration::from_secs(1),
+            || async { Ok(()) },
+        );
+        (test_support::server(&state), state)
+    }
+
+    // Liveness
+    #[tokio::test]
+    async fn test_healthz() {
+        let (server, _) = server();
+
+        let response = server.get("/healthz").await;
+        response.assert_status_ok();
+
+        let body: Value = response.json();
+        assert_eq!(body["status"], "ok");
+        assert_eq!(body["checks"]["always_ok"]["status"], "ok");
+        assert!(body["checks"].get("disk").is_none());
+    }
+
+    // Readiness
+    #[tokio::test]
+    async fn test_readyz() {
+        let (server, state) = server();
+
+        let response = server.get("/readyz").await;
+        response.assert_status(StatusCode::SERVICE_UNAVAILABLE);
+        let body: Value = response.json();
+        assert_eq!(body["checks"]["readiness"]["error"], "not ready");
+
+        state.readiness.set_ready(true);
+        let response = server.get("/readyz").await;
+        response.assert_status_ok();
+        let body: Value = response.json();
+        assert_eq!(body["status"], "ok");
+        assert_eq!(body["checks"]["disk"]["status"], "ok");
+    }
+
+    // Timeout dan check yang gagal
+    #[tokio::test]
+    async fn test_failing_checks() {
+        let (server, state) = server();
+        state.readiness.set_ready(true);
+        state.health.register(
+            "slow",
+            Probe::Readiness,
+            Duration::from_millis(20),
+            || async {
+                tokio::time::sleep(Duration::from_secs(5)).await;
+                Ok(())
+            },
+        );
+        state.health.register(
+            "broken",
+            Probe::Readiness,
+            Duration::from_secs(1),
+            || async { Err("connection refused".to_string()) },
+        );
+
+        let response = server.get("/readyz").await;
+        response.assert_status(StatusCode::SERVICE_UNAVAILABLE);
+
+        let body: Value = response.json();
+        assert_eq!(body["status"], "fail");
+        assert_eq!(body["checks"]["slow"]["error"], "timed out after 20ms");
+        assert_eq!(body["checks"]["broken"]["error"], "connection refused");
+        assert_eq!(body["checks"]["disk"]["status"], "ok");
+    }
+
+    #[tokio::test]
+    async fn test_disk_space_check() {
+        assert_eq!(disk_check(1, Ok(1024 * 1024)).check().await, Ok(()));
+        assert_eq!(
+            disk_check(2, Ok(1024 * 1024)).check().await,
+            Err("1048576 bytes free, need at least 2097152".to_string())
+        );
+        assert_eq!(
+            disk_check(0, Err("no such file".to_string())).check().await,
+            Err("no such file".to_string())
+        );
+
+        // batas yang sangat besar tidak overflow
+        let check = disk_check(u64::MAX, Ok(u64::MAX - 1));
+        assert_eq!(check.min_free_bytes, u64::MAX);
+        assert!(check.check().await.is_err());
+    }
+
+    // statvfs asli hanya dicek bisa dipanggil, bukan nilainya
+    #[test]
+    fn test_statvfs() {
+        assert!(Statvfs.free_space(Path::new(".")).is_ok());
+        assert!(Statvfs.free_space(Path::new("/tidak/ada")).is_err());
+    }
+}
@@ -0,0 +1,29 @@
+pub mod app;
+pub mod auth;
+pub mod config;
+pub mod cookies;
+pub mod cors;
+pub mod db;
+pub mod error;
+pub mod extract;
+pub mod files;
+pub mod health;
+pub mod logging;
+pub mod metrics;
+pub mod middleware;
+pub mod migrate;
+pub mod products;
+pub mod rate_limit;
+pub mod rbac;
+pub mod security;
+pub mod session;
+pub mod shutdown;
+pub mod state;
+#[cfg(test)]
+mod test_support;
+pub mod users;
+pub mod validation;
+
+pub use app::build_app;
+pub use config::Config;
+pub use state::AppState;
@@ -0,0 +1,161 @@
+use std::str::FromStr;
+
+use serde::Deserialize;
+use tracing_subscriber::{fmt, layer::SubscriberExt, util::SubscriberInitExt, EnvFilter};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    #[default]
+    Pretty,
+    Json,
+}
+
+impl FromStr for LogFormat {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_ascii_lowercase().as_str() {
+            "pretty" => Ok(LogFormat::Pretty),
+            "json" => Ok(LogFormat::Json),
+            other => Err(format!(
+                "unknown log format {:?}, expected pretty or json",
+                other
+            )),
+        }
+    }
+}
+
+// Dipanggil sekali di main() sebelum server berjalan, RUST_LOG menimpa level dari config
+pub fn init(format: LogFormat, level: &str) -> anyhow::Result<()> {
+    let filter = EnvFilter::try_from_default_env().or_else(|_| EnvFilter::try_new(level))?;
+    let registry = tracing_subscriber::registry().with(filter);
+
+    match format {
+        LogFormat::Pretty => registry.with(fmt::layer().pretty()).try_init()?,
+        LogFormat::Json => registry
+            .with(
+                fmt::layer()
+                    .json()
+                    .flatten_event(true)
+                    .with_current_span(true),
+            )
+            .try_init()?,
+    }
+
+    Ok(())
+}
+
+/// Captures tracing events in memory so tests can assert on them.
+#[cfg(test)]
+pub mod capture {
+    use std::{
+        collections::HashMap,
+        fmt::Debug,
+        sync::{Arc, Mutex},
+    };
+
+    use tracing::{
+        field::{Field, Visit},
+        span, Event, Level, Subscriber,
+    };
+    use tracing_subscriber::{
+        layer::{Context, SubscriberExt},
+        registry::LookupSpan,
+        Layer,
+    };
+
+    #[derive(Debug, Clone)]
+    pub struct CapturedEvent {
+        pub level: Level,
+        pub message: String,
+        // field dari event dan semua span induknya
+        pub fields: HashMap<String, String>,
+    }
+
+    impl CapturedEvent {
+        pub fn field(&self, name: &str) -> Option<&str> {
+            self.fields.get(name).map(String::as_str)
+        }
+    }
+
+    #[derive(Debug, Clone, Default)]
+    pub struct CapturedEvents(Arc<Mutex<Vec<CapturedEvent>>>);
+
+    impl CapturedEvents {
+        pub fn events(&self) -> Vec<CapturedEvent> {
+            self.0.lock().unwrap().clone()
+        }
+
+        pub fn find(&self, message: &str) -> Option<CapturedEvent> {
+            self.events()
+                .into_iter()
+                .find(|event| event.message == message)
+        }
+    }
+
+    /// Installs a capturing subscriber for the current thread until the guard is dropped.
+    pub fn capture() -> (CapturedEvents, tracing::subscriber::DefaultGuard) {
+        let events = CapturedEvents::default();
+        let subscriber = tracing_subscriber::registry().with(CaptureLayer(events.clone()));
+        (events, tracing::subscriber::set_default(subscriber))
+    }
+
+    struct CaptureLayer(CapturedEvents);
+
+    #[derive(Default)]
+    struct Fields(HashMap<String, String>);
+
+    impl Visit for Fields {
+        fn record_str(&mut self, field: &Field, value: &str) {
+            self.0.insert(field.name().to_string(), value.to_string());
+        }
+
+        fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+            self.0
+                .insert(field.name().to_string(), format!("{:?}", value));
+        }
+    }
+
+    impl<S> Layer<S> for CaptureLayer
+    where
+        S: Subscriber + for<'a> LookupSpan<'a>,
+    {
+        fn on_new_span(&self, attrs: &span::Attributes<'_>, id: &span::Id, ctx: Context<'_, S>) {
+            let mut fields = Fields::default();
+            attrs.record(&mut fields);
+            if let Some(span) = ctx.span(id) {
+                span.extensions_mut().insert(fields);
+            }
+        }
+
+        fn on_record(&self, id: &span::Id, values: &span::Record<'_>, ctx: Context<'_, S>) {
+            if let Some(span) = ctx.span(id) {
+                if let Some(fields) = span.extensions_mut().get_mut::<Fields>() {
+                    values.record(fields);
+                }
+            }
+        }
+
+        fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+            let mut fields = HashMap::new();
+            if let Some(scope) = ctx.event_scope(event) {
+                for span in scope.from_root() {
+                    if let Some(span_fields) = span.extensions().get::<Fields>() {
+                        fields.extend(span_fields.0.clone());
+                    }
+                }
+            }
+
+            let mut event_fields = Fields::default();
+            event.record(&mut event_fields);
+            fields.extend(event_fields.0);
+
+            self.0 .0.lock().unwrap().push(CapturedEvent {
+                level: *event.metadata().level(),
+                message: fields.remove("message").unwrap_or_default(),
+                fields,
+            });
+        }
+    }
+}
@@ -1,33 +1,136 @@
-use std::{collections::HashMap, sync::Arc};
+#[cfg(test)]
+use std::collections::HashMap;
 
+#[cfg(test)]
 use anyhow::anyhow;
-use axum::{body::{Body, Bytes}, error_handling::HandleError, extract::{rejection::JsonRejection, Multipart, Path, Query, Request, State}, middleware::{from_fn, map_request, Next}, response::{IntoResponse, Response}, routing::{get, post}, serve, Extension, Form, Json, Router};
-use axum_extra::{body, extract::{cookie::{self, Cookie}, CookieJar}, response};
+#[cfg(test)]
+use axum::{
+    body::{Body, Bytes},
+    error_handling::HandleError,
+    extract::{Multipart, Path, Query, Request, State},
+    middleware::from_fn,
+    response::Response,
+    routing::{get, post},
+    Extension, Form, Json, Router,
+};
+#[cfg(test)]
+use axum_extra::extract::{cookie::Cookie, CookieJar};
+#[cfg(test)]
 use axum_test::{multipart::{MultipartForm, Part}, TestServer};
-use http::{header, method, request, HeaderMap, HeaderValue, Method, StatusCode, Uri};
-use serde::{Deserialize, Serialize};
-use tokio::net::TcpListener;
+use clap::Parser;
+#[cfg(test)]
+use http::{HeaderMap, HeaderValue, Method, StatusCode, Uri};
+#[cfg(test)]
+use rust_axum_web::{
+    auth::{LoginRequest, LoginResponse},
+    cookies::SignedCookies,
+    db::DbPool,
+    error::AppError,
+    extract::ValidatedJson,
+    middleware::{log_middleware, request_id_middleware},
+};
+use rust_axum_web::{
+    build_app,
+    config::{Cli, Command, Config},
+    health::Probe,
+    logging, migrate,
+    shutdown::{serve_with_shutdown, shutdown_signal},
+    AppState,
+};
+use tokio::{net::TcpListener, runtime};
 
 // Setup
-#[tokio::main]
-async fn main() {
-    let app = Router::new()
-        .route("/", get(|| async {"Hello, World!"}));
-    
-    let listener = TcpListener::bind("127.0.0.1:3000")
-        .await
-        .unwrap();
+fn main() -> anyhow::Result<()> {
+    let cli = Cli::parse();
+    let config = Config::load(&cli)?;
+    logging::init(config.logging.format, &config.logging.level)?;
+
+    let mut builder = runtime::Builder::new_multi_thread();
+    if let Some(workers) = config.server.workers {
+        builder.worker_threads(workers);
+    }
+    let runtime = builder.enable_all().build()?;
+
+    match cli.command {
+        Some(Command::Migrate { action }) => {
+            runtime.block_on(migrate::run_command(&config, action))
+        }
+        None => runtime.block_on(run(config)),
+    }
+}
 
-    // menjalankan server
-    serve(listener, app).await.unwrap();
+async fn run(config: Config) -> anyhow::Result<()> {
+    let state = AppState::new(config)?;
+    if state.config.database.auto_migrate {
+        let applied = migrate::up(&state.db).await?;
+        tracing::info!(?applied, "database migrations applied");
+    }
+
+    // user, katalog produk, role dan session disimpan di SQLite, backend lain masih memakai store in-memory
+    #[cfg(not(feature = "postgres"))]
+    let state = {
+        let users = rust_axum_web::auth::store::SqliteUserStore::new(state.db.clone());
+        let products = rust_axum_web::products::SqliteProductStore::new(state.db.clone());
+        let sessions = rust_axum_web::session::SqliteSessionStore::new(state.db.clone());
+        let roles = rust_axum_web::rbac::SqliteRoleStore::new(state.db.clone());
+        state
+            .with_users(std::sync::Arc::new(users))
+            .with_products(std::sync::Arc::new(products))
+            .with_roles(std::sync::Arc::new(roles))
+            .with_sessions(std::sync::Arc::new(sessions))
+    };
+
+    let files = rust_axum_web::files::LocalFileStorage::new(&state.config.upload.dir);
+    let thumbnails =
+        rust_axum_web::files::LocalFileStorage::new(state.config.upload.dir.join("thumbnails"));
+    let state = state
+        .with_files(std::sync::Arc::new(files))
+        .with_thumbnails(std::sync::Arc::new(thumbnails));
+
+    let app = build_app(state.clone());
+
+    let listener = TcpListener::bind(state.config.server.socket_addr()?).await?;
+    tracing::info!(address = %listener.local_addr()?, "listening");
+
+    // warm-up: jalankan readiness check sekali sebelum menerima traffic
+    let report = state.health.run(Probe::Readiness).await;
+    for (name, check) in &report.checks {
+        if let Some(error) = &check.error {
+            tracing::warn!(check = %name, %error, "readiness check failed during warm-up");
+        }
+    }
+    state.readiness.set_ready(true);
+
+    // session kedaluwarsa dibersihkan berkala sampai server berhenti
+    let (stop_purge, purge_stopped) = tokio::sync::oneshot::channel::<()>();
+    let purge = rust_axum_web::session::spawn_purge(
+        state.sessions.clone(),
+        state.config.session.purge_interval(),
+        async move {
+            let _ = purge_stopped.await;
+        },
+    );
+
+    // menjalankan server sampai menerima SIGTERM / SIGINT
+    let served = serve_with_shutdown(
+        listener,
+        app,
+        state.readiness.clone(),
+        state.config.server.drain_timeout(),
+        shutdown_signal(),
+    )
+    .await;
+    let _ = stop_purge.send(());
+    purge.await?;
+    served?;
+    Ok(())
 }
 
 
 // Axum Test
 #[tokio::test]
 async fn test_axum() {
-    let app = Router::new()
-        .route("/", get(|| async {"Hello, World!"}));
+    let app = build_app(AppState::new(Config::default()).unwrap());
 
     let server = TestServer::new(app).unwrap();
     let response = server.get("/").await;
@@ -36,6 +139,21 @@ async fn test_axum() {
     response.assert_text("Hello, World!");
 }
 
+// Readiness
+#[tokio::test]
+async fn test_readiness() {
+    let state = AppState::new(Config::default()).unwrap();
+    let server = TestServer::new(build_app(state.clone())).unwrap();
+
+    let response = server.get("/readyz").await;
+    response.assert_status(StatusCode::SERVICE_UNAVAILABLE);
+
+    state.readiness.set_ready(true);
+    let response = server.get("/readyz").await;
+    response.assert_status_ok();
+    response.assert_text_contains("\"status\":\"ok\"");
+}
+
 
 // Router atau Routing
 #[tokio::test]
@@ -187,12 +305,6 @@ async fn test_body_string() {
 }
 
 // Json body extractor
-#[derive(Debug, Serialize, Deserialize)]
-struct LoginRequest {
-    username: String,
-    password: String,
-}
-
 #[tokio::test]
 async fn test_json_body() {
     async fn hello_world(Json(request) : Json<LoginRequest>) -> String {
@@ -219,15 +331,8 @@ async fn test_json_body() {
 // Json Error
 #[tokio::test]
 async fn test_json_error() {
-    async fn hello_world(payload: Result<Json<LoginRequest>, JsonRejection>) -> String {
-        match payload {
-            Ok(request) => {
-                format!("Hello {}", request.username)
-            }
-            Err(error) => {
-                format!("Error {:?}", error)
-            }
-        }
+    async fn hello_world(ValidatedJson(request): ValidatedJson<LoginRequest>) -> String {
+        format!("Hello {}", request.username)
     }
     
     let app = Router::new()
@@ -247,8 +352,16 @@ async fn test_json_error() {
 
     // menngunakan json yang tidak valid
     let response = server.post("/post").text("tidak valid").await;
-    response.assert_status_ok();
-    response.assert_text("Error MissingJsonContentType(MissingJsonContentType");
+    response.assert_status(StatusCode::UNSUPPORTED_MEDIA_TYPE);
+    response.assert_text("Expected request with `Content-Type: application/json`");
+
+    // field yang wajib tidak dikirim
+    let response = server
+        .post("/post")
+        .json(&serde_json::json!({"username": "Aqil"}))
+        .await;
+    response.assert_status(StatusCode::UNPROCESSABLE_ENTITY);
+    response.assert_text_contains("missing field `password`");
     
 }
 
@@ -277,11 +390,6 @@ async fn test_response() {
 
 
 // json response
-#[derive(Debug, Serialize, Deserialize)]
-struct LoginResponse {
-    token: String,
-}
-
 #[tokio::test]
 async fn test_response_json() {
     async fn hello_world() -> Json<LoginResponse> {
@@ -382,20 +490,23 @@ async fn test_form() {
 // Multiple Request
 #[tokio::test]
 async fn test_multipart() {
-    async fn hello_world(mut payload: Multipart) -> String {
-        let mut profile: Bytes = Bytes::new();
+    async fn hello_world(mut payload: Multipart) -> Result<String, AppError> {
+        let mut profile_size = 0;
         let mut username = "".to_string();
 
-        while let Some (field) = payload.next_field().await.unwrap() {
+        while let Some(mut field) = payload.next_field().await? {
             if field.name().unwrap_or("") == "profile" {
-                profile = field.bytes().await.unwrap()
+                // dibaca per chunk, file tidak pernah dimuat utuh ke memori
+                while let Some(chunk) = field.chunk().await? {
+                    profile_size += chunk.len();
+                }
             } else if field.name().unwrap_or("") == "username" {
-                username = field.text().await.unwrap()
+                username = field.text().await?
             }
         }
 
-        assert!(profile.len() > 0);
-        format!("Hello {}", username)
+        assert!(profile_size > 0);
+        Ok(format!("Hello {}", username))
     }
     
     let app = Router::new()
@@ -411,6 +522,14 @@ async fn test_multipart() {
     let response = server.post("/post").multipart(request).await;
     response.assert_status_ok();
     response.assert_text("Hello Aqil");
+
+    // body multipart yang rusak menjadi 400, bukan panic
+    let response = server
+        .post("/post")
+        .content_type("multipart/form-data; boundary=batas")
+        .text("--batas\r\nContent-Disposition: form-data; name=\"profile\"\r\n\r\nterpotong")
+        .await;
+    response.assert_status(StatusCode::BAD_REQUEST);
     
 }
 
@@ -440,43 +559,40 @@ async fn test_cookie_response() {
 }
 
 
-// cookie request
+// cookie request, ditandatangani dengan cookie.key agar tidak bisa diubah client
 #[tokio::test]
 async fn test_cookie_request() {
-    async fn hello_world(cookie: CookieJar) -> String {
-        let name = cookie.get("name").unwrap().value();
+    async fn hello_world(cookie: SignedCookies) -> Result<String, AppError> {
+        let name = cookie.get("name")?.unwrap();
 
-        format!("Hello {}", name)
+        Ok(format!("Hello {}", name.value()))
     }
     
+    let mut config = Config::default();
+    config.cookie.key = Some("key-yang-panjangnya-minimal-32-byte".to_string());
+    let state = AppState::new(config).unwrap();
     let app = Router::new()
-        .route("/get", get(hello_world));
-    
+        .route("/get", get(hello_world))
+        .with_state(state.clone());
+
     let server = TestServer::new(app).unwrap();
-    
-    let response = server.get("/get").add_header("Cookie", "name=Aqil").await;
+
+    let cookie = state.cookie_keys.sign(Cookie::new("name", "Aqil"));
+    let response = server
+        .get("/get")
+        .add_header("Cookie", cookie.to_string())
+        .await;
     response.assert_status_ok();
     response.assert_text("Hello Aqil");
-    
-}
-
 
-// Middleware
-async fn log_middleware(request: Request, next: Next) -> Response {
-    println!("Recieve request {} {}", request.method(), request.uri());
-    let response = next.run(request).await;
-    println!("Sen response {}", response.status());
-    response
+    let tampered = cookie.to_string().replace("Aqil", "Budi");
+    let response = server.get("/get").add_header("Cookie", tampered).await;
+    response.assert_status(StatusCode::BAD_REQUEST);
+    response.assert_text("Cookie name has been tampered with or uses an unknown key");
 }
 
-async fn request_id_middleware<T>(mut request: Request<T>) -> Request<T> {
-    let request_id = "12345";
-    request
-        .headers_mut()
-        .insert("X-Request-Id", request_id.parse().unwrap());
-    request
-}
 
+// Middleware
 #[tokio::test]
 async fn test_middleware() {
     async fn hello_world(method: Method, header_map: HeaderMap) -> String {
@@ -488,45 +604,27 @@ async fn test_middleware() {
     
     let app = Router::new()
         .route("/get", get(hello_world))
-        .layer(map_request(request_id_middleware))
-        .layer(from_fn(log_middleware));
+        .layer(from_fn(log_middleware))
+        .layer(from_fn(request_id_middleware));
     
     let server = TestServer::new(app).unwrap();
     
-    let response = server.get("/get").add_header("Cookie", "name=Aqil").await;
+    let response = server.get("/get").add_header("X-Request-Id", "12345").await;
     response.assert_status_ok();
     response.assert_text("Hello GET 12345");
+    response.assert_header("X-Request-Id", "12345");
     
 }
 
 
 // Error Handling
-struct AppError {
-    code: i32,
-    message: String,
-}
-
-impl IntoResponse for AppError {
-    fn into_response(self) -> Response {
-        (
-            StatusCode::from_u16(self.code as u16).unwrap(),
-            self.message,
-        )
-
-            .into_response()
-    }
-}
-
 #[tokio::test]
 async fn test_error_handling() {
     async fn hello_world(method: Method) -> Result<String, AppError> {
         if method == Method::POST {
             Ok("OK".to_string())
         } else {
-            Err(AppError { 
-                code: 400, 
-                message: "Bad Request".to_string(), 
-            })
+            Err(AppError::validation("bad_request", "Bad Request"))
         }
     }
     
@@ -576,22 +674,23 @@ async fn test_unexpected_eeror() {
 
 // State
 // state extractor
-struct DatabaseConfig {
-    total: i32
+#[cfg(test)]
+async fn total(pool: &DbPool) -> i64 {
+    let (total,): (i64,) = sqlx::query_as("SELECT 100").fetch_one(pool).await.unwrap();
+    total
 }
 
 #[tokio::test]
 async fn test_state_extractor() {
-    let database_state = Arc::new(DatabaseConfig{total: 100});
+    let state = AppState::new(Config::default()).unwrap();
 
-    async fn hello_world(State(database) : State<Arc<DatabaseConfig>>) -> String {
-        
-        format!("Total {}", database.total)
+    async fn hello_world(State(state): State<AppState>) -> String {
+        format!("Total {}", total(&state.db).await)
     }
     
     let app = Router::new()
         .route("/get", get(hello_world))
-        .with_state(database_state);
+        .with_state(state);
 
     let server = TestServer::new(app).unwrap();
     
@@ -604,16 +703,15 @@ async fn test_state_extractor() {
 // state extension
 #[tokio::test]
 async fn test_state_extension() {
-    let database_state = Arc::new(DatabaseConfig{total: 100});
+    let state = AppState::new(Config::default()).unwrap();
 
-    async fn hello_world(Extension(database) : Extension<Arc<DatabaseConfig>>) -> String {
-        
-        format!("Total {}", database.total)
+    async fn hello_world(Extension(pool): Extension<DbPool>) -> String {
+        format!("Total {}", total(&pool).await)
     }
     
     let app = Router::new()
         .route("/get", get(hello_world))
-        .layer(Extension(database_state));
+        .layer(Extension(state.db));
 
     let server = TestServer::new(app).unwrap();
     
@@ -626,19 +724,18 @@ async fn test_state_extension() {
 // closure capture
 #[tokio::test]
 async fn test_state_closure_capture() {
-    let database_state = Arc::new(DatabaseConfig{total: 100});
+    let state = AppState::new(Config::default()).unwrap();
 
-    async fn hello_world(database : Arc<DatabaseConfig>) -> String {
-        
-        format!("Total {}", database.total)
+    async fn hello_world(pool: DbPool) -> String {
+        format!("Total {}", total(&pool).await)
     }
     
     let app = Router::new()
         .route("/get", get({
-            let database_state = Arc::clone(&database_state);
-            move || hello_world(database_state)
-        }))
-        .layer(Extension(database_state));
+            let pool = state.db.clone();
+            move || hello_world(pool)
+        }),
+    );
 
     let server = TestServer::new(app).unwrap();
     
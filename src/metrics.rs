@@ -0,0 +1,228 @@
+use std::time::Instant;
+
+use axum::{
+    extract::{MatchedPath, Request, State},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use http::{header, StatusCode};
+use prometheus::{
+    Encoder, HistogramOpts, HistogramVec, IntCounterVec, IntGauge, IntGaugeVec, Opts, Registry,
+    TextEncoder,
+};
+
+use crate::state::AppState;
+
+// Registry per aplikasi (bukan global) agar test tidak saling mengganggu
+#[derive(Clone)]
+pub struct Metrics {
+    registry: Registry,
+    requests: IntCounterVec,
+    duration: HistogramVec,
+    in_flight: IntGaugeVec,
+}
+
+impl Metrics {
+    pub fn new() -> Metrics {
+        let registry = Registry::new();
+
+        let requests = IntCounterVec::new(
+            Opts::new("http_requests_total", "Total number of HTTP requests"),
+            &["method", "route", "status_class"],
+        )
+        .unwrap();
+        let duration = HistogramVec::new(
+            HistogramOpts::new(
+                "http_request_duration_seconds",
+                "HTTP request latency in seconds",
+            ),
+            &["method", "route", "status_class"],
+        )
+        .unwrap();
+        let in_flight = IntGaugeVec::new(
+            Opts::new(
+                "http_requests_in_flight",
+                "Number of HTTP requests being served",
+            ),
+            &["method", "route"],
+        )
+        .unwrap();
+
+        // nama metric unik, register tidak akan gagal
+        registry.register(Box::new(requests.clone())).unwrap();
+        registry.register(Box::new(duration.clone())).unwrap();
+        registry.register(Box::new(in_flight.clone())).unwrap();
+
+        Metrics {
+            registry,
+            requests,
+            duration,
+            in_flight,
+        }
+    }
+
+    /// Registry for other subsystems to register their own collectors.
+    pub fn registry(&self) -> &Registry {
+        &self.registry
+    }
+
+    pub fn render(&self) -> anyhow::Result<String> {
+        let mut buffer = Vec::new();
+        TextEncoder::new().encode(&self.registry.gather(), &mut buffer)?;
+        Ok(String::from_utf8(buffer)?)
+    }
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Metrics::new()
+    }
+}
+
+// Gauge in-flight diturunkan di Drop, sehingga tetap benar bila client memutus koneksi
+// dan future request di-drop sebelum selesai
+struct InFlightGuard(IntGauge);
+
+impl InFlightGuard {
+    fn new(gauge: IntGauge) -> InFlightGuard {
+        gauge.inc();
+        InFlightGuard(gauge)
+    }
+}
+
+impl Drop for InFlightGuard {
+    fn drop(&mut self) {
+        self.0.dec();
+    }
+}
+
+// Middleware: label route memakai template (MatchedPath), bukan path asli
+pub async fn metrics_middleware(
+    State(metrics): State<Metrics>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let started = Instant::now();
+    let method = request.method().to_string();
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_string())
+        .unwrap_or_else(|| "<unmatched>".to_string());
+
+    let in_flight = InFlightGuard::new(metrics.in_flight.with_label_values(&[&method, &route]));
+    let response = next.run(request).await;
+    drop(in_flight);
+
+    let status_class = format!("{}xx", response.status().as_u16() / 100);
+    let labels = [method.as_str(), route.as_str(), status_class.as_str()];
+    metrics.requests.with_label_values(&labels).inc();
+    metrics
+        .duration
+        .with_label_values(&labels)
+        .observe(started.elapsed().as_secs_f64());
+
+    response
+}
+
+pub async fn metrics_handler(State(state): State<AppState>) -> Response {
+    match state.metrics.render() {
+        Ok(body) => ([(header::CONTENT_TYPE, prometheus::TEXT_FORMAT)], body).into_response(),
+        Err(error) => {
+            tracing::error!(error = ?error, "failed to render metrics");
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{middleware::from_fn_with_state, routing::get, Router};
+    use axum_test::TestServer;
+
+    use super::*;
+    use crate::{build_app, Config};
+
+    // Route dengan path parameter
+    #[tokio::test]
+    async fn test_metrics_route_template() {
+        async fn hello_world() -> &'static str {
+            "Hello"
+        }
+
+        let metrics = Metrics::new();
+        let app = Router::new()
+            .route("/products/{id}/categories/{id_category}", get(hello_world))
+            .layer(from_fn_with_state(metrics.clone(), metrics_middleware));
+        let server = TestServer::new(app).unwrap();
+
+        server
+            .get("/products/1/categories/3")
+            .await
+            .assert_status_ok();
+        server
+            .get("/products/2/categories/4")
+            .await
+            .assert_status_ok();
+        server.get("/wrong").await.assert_status_not_found();
+
+        let body = metrics.render().unwrap();
+        assert!(body.contains(
+            "http_requests_total{method=\"GET\",route=\"/products/{id}/categories/{id_category}\",status_class=\"2xx\"} 2"
+        ));
+        assert!(body.contains(
+            "http_requests_total{method=\"GET\",route=\"<unmatched>\",status_class=\"4xx\"} 1"
+        ));
+        assert!(body.contains(
+            "http_request_duration_seconds_count{method=\"GET\",route=\"/products/{id}/categories/{id_category}\",status_class=\"2xx\"} 2"
+        ));
+        assert!(body.contains(
+            "http_requests_in_flight{method=\"GET\",route=\"/products/{id}/categories/{id_category}\"} 0"
+        ));
+    }
+
+    // Request yang dibatalkan tidak meninggalkan gauge in-flight
+    #[tokio::test]
+    async fn test_in_flight_cancelled() {
+        use tower::ServiceExt;
+
+        let metrics = Metrics::new();
+        let app = Router::new()
+            .route("/slow", get(std::future::pending::<()>))
+            .layer(from_fn_with_state(metrics.clone(), metrics_middleware));
+        let in_flight = metrics.in_flight.with_label_values(&["GET", "/slow"]);
+
+        let request = Request::get("/slow")
+            .body(axum::body::Body::empty())
+            .unwrap();
+        let mut call = Box::pin(app.oneshot(request));
+        assert!(futures::poll!(call.as_mut()).is_pending());
+        assert_eq!(in_flight.get(), 1);
+
+        drop(call);
+        assert_eq!(in_flight.get(), 0);
+    }
+
+    // Endpoint /metrics
+    #[tokio::test]
+    async fn test_metrics_endpoint() {
+        let server = TestServer::new(build_app(AppState::new(Config::default()).unwrap())).unwrap();
+
+        server.get("/").await.assert_status_ok();
+        server
+            .post("/api/auth/login")
+            .text("tidak valid")
+            .await
+            .assert_status(StatusCode::UNSUPPORTED_MEDIA_TYPE);
+
+        let response = server.get("/metrics").await;
+        response.assert_status_ok();
+        response.assert_header("Content-Type", prometheus::TEXT_FORMAT);
+        response.assert_text_contains(
+            "http_requests_total{method=\"GET\",route=\"/\",status_class=\"2xx\"} 1",
+        );
+        response.assert_text_contains(
+            "http_requests_total{method=\"POST\",route=\"/api/auth/login\",status_class=\"4xx\"} 1",
+        );
+    }
+}
@@ -0,0 +1,260 @@
+use std::{fmt, time::Instant};
+
+use axum::{
+    body::HttpBody,
+    extract::{FromRequestParts, MatchedPath, Request},
+    middleware::Next,
+    response::Response,
+};
+use http::{header, request::Parts, HeaderMap, HeaderName, HeaderValue};
+use http_body::SizeHint;
+use tracing::Instrument;
+use uuid::Uuid;
+
+use crate::error::AppError;
+
+pub static X_REQUEST_ID: HeaderName = HeaderName::from_static("x-request-id");
+
+// Middleware: satu span per request, semua log di dalam handler ikut membawa field span
+pub async fn log_middleware(request: Request, next: Next) -> Response {
+    let started = Instant::now();
+    let request_id = request
+        .extensions()
+        .get::<RequestId>()
+        .map(|id| id.to_string())
+        .unwrap_or_default();
+    // template route, bukan path asli, agar kardinalitas log tetap kecil
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_string())
+        .unwrap_or_else(|| "<unmatched>".to_string());
+
+    let span = tracing::info_span!(
+        "request",
+        method = %request.method(),
+        route = %route,
+        request_id = %request_id,
+        bytes_in = body_size(request.headers(), request.body().size_hint()),
+        status = tracing::field::Empty,
+        latency_ms = tracing::field::Empty,
+        bytes_out = tracing::field::Empty,
+    );
+
+    async move {
+        tracing::debug!("started");
+        let response = next.run(request).await;
+
+        let span = tracing::Span::current();
+        span.record("status", response.status().as_u16());
+        span.record("latency_ms", started.elapsed().as_secs_f64() * 1000.0);
+        span.record(
+            "bytes_out",
+            body_size(response.headers(), response.body().size_hint()),
+        );
+
+        if response.status().is_server_error() {
+            tracing::error!("finished");
+        } else {
+            tracing::info!("finished");
+        }
+        response
+    }
+    .instrument(span)
+    .await
+}
+
+// ukuran body dari size hint atau Content-Length, 0 bila streaming tanpa panjang
+fn body_size(headers: &HeaderMap, hint: SizeHint) -> u64 {
+    hint.exact()
+        .or_else(|| {
+            headers
+                .get(header::CONTENT_LENGTH)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.parse().ok())
+        })
+        .unwrap_or(0)
+}
+
+// Request id dari header X-Request-Id, atau UUIDv7 baru bila tidak ada / tidak valid
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct RequestId(String);
+
+impl RequestId {
+    pub fn new() -> RequestId {
+        RequestId(Uuid::now_v7().to_string())
+    }
+
+    /// Accepts up to 128 visible ASCII characters from `[A-Za-z0-9._:-]`.
+    pub fn parse(value: &str) -> Option<RequestId> {
+        let valid = !value.is_empty()
+            && value.len() <= 128
+            && value
+                .bytes()
+                .all(|byte| byte.is_ascii_alphanumeric() || b"._:-".contains(&byte));
+
+        valid.then(|| RequestId(value.to_string()))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Default for RequestId {
+    fn default() -> Self {
+        RequestId::new()
+    }
+}
+
+impl fmt::Display for RequestId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl<S> FromRequestParts<S> for RequestId
+where
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        parts
+            .extensions
+            .get::<RequestId>()
+            .cloned()
+            .ok_or_else(|| anyhow::anyhow!("request_id_middleware is not installed").into())
+    }
+}
+
+pub async fn request_id_middleware(mut request: Request, next: Next) -> Response {
+    let request_id = request
+        .headers()
+        .get(&X_REQUEST_ID)
+        .and_then(|value| value.to_str().ok())
+        .and_then(RequestId::parse)
+        .unwrap_or_default();
+
+    // header yang valid selalu bisa diubah menjadi HeaderValue
+    let header = HeaderValue::from_str(request_id.as_str()).unwrap();
+    request
+        .headers_mut()
+        .insert(X_REQUEST_ID.clone(), header.clone());
+    request.extensions_mut().insert(request_id.clone());
+
+    let mut response = next.run(request).await;
+    response.headers_mut().insert(X_REQUEST_ID.clone(), header);
+    response.extensions_mut().insert(request_id);
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{middleware::from_fn, routing::get, Router};
+    use axum_test::TestServer;
+
+    use super::*;
+    use crate::logging::capture::capture;
+
+    fn server() -> TestServer {
+        async fn hello_world(request_id: RequestId) -> String {
+            format!("Hello {}", request_id)
+        }
+
+        let app = Router::new()
+            .route("/get", get(hello_world))
+            .layer(from_fn(request_id_middleware));
+
+        TestServer::new(app).unwrap()
+    }
+
+    // Request id baru
+    #[tokio::test]
+    async fn test_generated_request_id() {
+        let response = server().get("/get").await;
+        response.assert_status_ok();
+
+        let request_id = response.header("X-Request-Id");
+        let request_id = Uuid::parse_str(request_id.to_str().unwrap()).unwrap();
+        assert_eq!(request_id.get_version_num(), 7);
+        response.assert_text(format!("Hello {}", request_id));
+    }
+
+    // Request id dari client
+    #[tokio::test]
+    async fn test_propagated_request_id() {
+        let response = server()
+            .get("/get")
+            .add_header("X-Request-Id", "abc-123")
+            .await;
+        response.assert_status_ok();
+        response.assert_header("X-Request-Id", "abc-123");
+        response.assert_text("Hello abc-123");
+    }
+
+    // Request id tidak valid diganti
+    #[tokio::test]
+    async fn test_invalid_request_id() {
+        let server = server();
+
+        for invalid in ["bad id", "<script>", &"a".repeat(129)] {
+            let response = server.get("/get").add_header("X-Request-Id", invalid).await;
+            response.assert_status_ok();
+
+            let request_id = response.header("X-Request-Id");
+            assert_ne!(request_id, invalid);
+            assert!(Uuid::parse_str(request_id.to_str().unwrap()).is_ok());
+        }
+    }
+
+    // Log request
+    #[tokio::test]
+    async fn test_log_middleware() {
+        async fn product(axum::extract::Path(id): axum::extract::Path<u32>) -> String {
+            tracing::info!(id, "loading product");
+            format!("Product {}", id)
+        }
+
+        let app = Router::new()
+            .route("/products/{id}", get(product))
+            .layer(from_fn(log_middleware))
+            .layer(from_fn(request_id_middleware));
+        let server = TestServer::new(app).unwrap();
+
+        let (events, _guard) = capture();
+        let response = server
+            .post("/products/7")
+            .add_header("X-Request-Id", "abc-123")
+            .await;
+        response.assert_status(http::StatusCode::METHOD_NOT_ALLOWED);
+
+        let response = server
+            .get("/products/7")
+            .add_header("X-Request-Id", "abc-123")
+            .await;
+        response.assert_status_ok();
+
+        let finished: Vec<_> = events
+            .events()
+            .into_iter()
+            .filter(|event| event.message == "finished")
+            .collect();
+        assert_eq!(finished.len(), 2);
+
+        let event = &finished[1];
+        assert_eq!(event.level, tracing::Level::INFO);
+        assert_eq!(event.field("method"), Some("GET"));
+        assert_eq!(event.field("route"), Some("/products/{id}"));
+        assert_eq!(event.field("request_id"), Some("abc-123"));
+        assert_eq!(event.field("status"), Some("200"));
+        assert_eq!(event.field("bytes_in"), Some("0"));
+        assert_eq!(event.field("bytes_out"), Some("9"));
+        assert!(event.field("latency_ms").is_some());
+
+        // log dari handler ikut membawa request id
+        let event = events.find("loading product").unwrap();
+        assert_eq!(event.field("request_id"), Some("abc-123"));
+        assert_eq!(event.field("id"), Some("7"));
+    }
+}
@@ -0,0 +1,191 @@
+use std::collections::BTreeSet;
+
+use sqlx::migrate::{Migrate, Migrator};
+
+use crate::{
+    config::{Config, MigrateAction},
+    db::{self, DbPool},
+};
+
+// File SQL di-embed ke binary saat compile, satu direktori per backend
+#[cfg(not(feature = "postgres"))]
+pub static MIGRATOR: Migrator = sqlx::migrate!("./migrations/sqlite");
+#[cfg(feature = "postgres")]
+pub static MIGRATOR: Migrator = sqlx::migrate!("./migrations/postgres");
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MigrationStatus {
+    pub version: i64,
+    pub description: String,
+    pub applied: bool,
+}
+
+pub async fn status(pool: &DbPool) -> anyhow::Result<Vec<MigrationStatus>> {
+    let mut connection = pool.acquire().await?;
+    connection.ensure_migrations_table().await?;
+    let applied: BTreeSet<i64> = connection
+        .list_applied_migrations()
+        .await?
+        .into_iter()
+        .map(|migration| migration.version)
+        .collect();
+
+    Ok(MIGRATOR
+        .iter()
+        .filter(|migration| migration.migration_type.is_up_migration())
+        .map(|migration| MigrationStatus {
+            version: migration.version,
+            description: migration.description.to_string(),
+            applied: applied.contains(&migration.version),
+        })
+        .collect())
+}
+
+/// Applies every pending migration and returns the versions that were applied.
+pub async fn up(pool: &DbPool) -> anyhow::Result<Vec<i64>> {
+    let pending = status(pool)
+        .await?
+        .into_iter()
+        .filter(|migration| !migration.applied)
+        .map(|migration| migration.version)
+        .collect();
+
+    MIGRATOR.run(pool).await?;
+    Ok(pending)
+}
+
+/// Reverts the most recently applied migration, if any, and returns its version.
+pub async fn down(pool: &DbPool) -> anyhow::Result<Option<i64>> {
+    let applied: Vec<i64> = status(pool)
+        .await?
+        .into_iter()
+        .filter(|migration| migration.applied)
+        .map(|migration| migration.version)
+        .collect();
+
+    let Some((&last, rest)) = applied.split_last() else {
+        return Ok(None);
+    };
+
+    // undo membatalkan semua migration dengan versi di atas target
+    MIGRATOR
+        .undo(pool, rest.last().copied().unwrap_or(0))
+        .await?;
+    Ok(Some(last))
+}
+
+// Dipanggil oleh subcommand `migrate`, hasil dicetak ke stdout
+pub async fn run_command(config: &Config, action: MigrateAction) -> anyhow::Result<()> {
+    let pool = db::connect_lazy(&config.database)?;
+
+    match action {
+        MigrateAction::Up => {
+            let applied = up(&pool).await?;
+            if applied.is_empty() {
+                println!("No pending migrations");
+            }
+            for version in applied {
+                println!("Applied {}", version);
+            }
+        }
+        MigrateAction::Down => match down(&pool).await? {
+            Some(version) => println!("Reverted {}", version),
+            None => println!("No migrations to revert"),
+        },
+        MigrateAction::Status => {
+            for migration in status(&pool).await? {
+                let state = if migration.applied {
+                    "applied"
+                } else {
+                    "pending"
+                };
+                println!(
+                    "{:>4} {:<8} {}",
+                    migration.version, state, migration.description
+                );
+            }
+        }
+    }
+
+    pool.close().await;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::PathBuf};
+
+    use super::*;
+    use crate::config::DatabaseConfig;
+
+    // Database SQLite sementara di direktori temp
+    fn database_file(name: &str) -> (PathBuf, DatabaseConfig) {
+        let path =
+            std::env::temp_dir().join(format!("rust-axum-web-{}-{}.db", std::process::id(), name));
+        let _ = fs::remove_file(&path);
+
+        let config = DatabaseConfig {
+            url: format!("sqlite://{}?mode=rwc", path.display()),
+            ..DatabaseConfig::default()
+        };
+        (path, config)
+    }
+
+    async fn tables(pool: &DbPool) -> Vec<String> {
+        let rows: Vec<(String,)> = sqlx::query_as(
+            "SELECT name FROM sqlite_master WHERE type = 'table' \
+             AND name NOT LIKE 'sqlite_%' AND name NOT LIKE '_sqlx%' ORDER BY name",
+        )
+        .fetch_all(pool)
+        .await
+        .unwrap();
+        rows.into_iter().map(|(name,)| name).collect()
+    }
+
+    #[tokio::test]
+    async fn test_migrate_up_down() {
+        let (path, config) = database_file("migrate");
+        let pool = db::connect_lazy(&config).unwrap();
+        let versions: Vec<i64> = status(&pool)
+            .await
+            .unwrap()
+            .iter()
+            .map(|m| m.version)
+            .collect();
+
+        assert!(status(&pool).await.unwrap().iter().all(|m| !m.applied));
+        assert_eq!(up(&pool).await.unwrap(), versions);
+        assert!(up(&pool).await.unwrap().is_empty());
+        assert!(status(&pool).await.unwrap().iter().all(|m| m.applied));
+        assert!(tables(&pool).await.contains(&"products".to_string()));
+
+        let last = *versions.last().unwrap();
+        assert_eq!(down(&pool).await.unwrap(), Some(last));
+        let status = status(&pool).await.unwrap();
+        assert!(!status.last().unwrap().applied);
+        assert!(status[..status.len() - 1].iter().all(|m| m.applied));
+
+        while down(&pool).await.unwrap().is_some() {}
+        assert!(tables(&pool).await.is_empty());
+
+        pool.close().await;
+        fs::remove_file(path).unwrap();
+    }
+
+    // Schema tetap ada setelah pool ditutup dan dibuka lagi
+    #[tokio::test]
+    async fn test_migrations_persist() {
+        let (path, config) = database_file("persist");
+
+        let pool = db::connect_lazy(&config).unwrap();
+        up(&pool).await.unwrap();
+        pool.close().await;
+
+        let pool = db::connect_lazy(&config).unwrap();
+        assert!(status(&pool).await.unwrap().iter().all(|m| m.applied));
+        assert!(up(&pool).await.unwrap().is_empty());
+        pool.close().await;
+
+        fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,391 @@
+pub mod store;
+
+use std::fmt;
+
+use axum::{
+    extract::State,
+    routing::{delete, get, post, put, MethodRouter},
+    Json, Router,
+};
+use http::StatusCode;
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+use crate::{
+    error::{AppError, FieldError},
+    extract::{PathParams, ValidatedJson},
+    rbac::require_permission,
+    state::AppState,
+    validation::not_blank,
+};
+
+pub use self::store::{InMemoryProductStore, ProductStore, SqliteProductStore};
+
+// ID bertipe agar id produk dan kategori tidak tertukar
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct ProductId(pub i64);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct CategoryId(pub i64);
+
+impl fmt::Display for ProductId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl fmt::Display for CategoryId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+// Harga dalam satuan terkecil mata uang (tanpa desimal)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Product {
+    pub id: ProductId,
+    pub name: String,
+    pub price: i64,
+    pub category_ids: Vec<CategoryId>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Category {
+    pub id: CategoryId,
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct NewProduct {
+    #[validate(length(max = 100), custom(function = "not_blank"))]
+    pub name: String,
+    #[validate(range(min = 0))]
+    pub price: i64,
+    #[serde(default)]
+    pub category_ids: Vec<CategoryId>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct NewCategory {
+    #[validate(length(max = 100), custom(function = "not_blank"))]
+    pub name: String,
+}
+
+fn product_not_found(id: ProductId) -> AppError {
+    AppError::not_found("product_not_found", format!("Product {} not found", id))
+}
+
+fn category_not_found(id: CategoryId) -> AppError {
+    AppError::not_found("category_not_found", format!("Category {} not found", id))
+}
+
+// Route /api/products, kategori ada di /api/products/categories;
+// katalog bisa dibaca siapa saja, perubahan membutuhkan permission products:write
+pub fn router(state: &AppState) -> Router<AppState> {
+    // route_layer per method agar method yang tidak ada tetap 405, bukan 401
+    let write = |method_router: MethodRouter<AppState>| {
+        method_router.route_layer(require_permission(state, "products:write"))
+    };
+
+    Router::new()
+        .route("/", get(list_products).merge(write(post(create_product))))
+        .route(
+            "/{id}",
+            get(get_product).merge(write(put(update_product).delete(delete_product))),
+        )
+        .route(
+            "/{id}/categories/{id_category}",
+            get(get_product_in_category),
+        )
+        .route(
+            "/categories",
+            get(list_categories).merge(write(post(create_category))),
+        )
+        .route(
+            "/categories/{id_category}",
+            get(get_category).merge(write(delete(delete_category))),
+        )
+}
+
+// Kategori yang direferensikan harus ada
+async fn check_categories(state: &AppState, product: &NewProduct) -> Result<(), AppError> {
+    let mut errors = Vec::new();
+    for (index, id) in product.category_ids.iter().enumerate() {
+        if state.products.find_category(*id).await?.is_none() {
+            errors.push(FieldError::new(
+                format!("category_ids[{}]", index),
+                format!("category {} does not exist", id),
+            ));
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(AppError::unprocessable(
+            "unknown_category",
+            "Request validation failed",
+            errors,
+        ))
+    }
+}
+
+async fn list_products(State(state): State<AppState>) -> Result<Json<Vec<Product>>, AppError> {
+    Ok(Json(state.products.list_products().await?))
+}
+
+async fn create_product(
+    State(state): State<AppState>,
+    ValidatedJson(product): ValidatedJson<NewProduct>,
+) -> Result<(StatusCode, Json<Product>), AppError> {
+    check_categories(&state, &product).await?;
+    let product = state.products.create_product(product).await?;
+    Ok((StatusCode::CREATED, Json(product)))
+}
+
+async fn get_product(
+    State(state): State<AppState>,
+    PathParams(id): PathParams<ProductId>,
+) -> Result<Json<Product>, AppError> {
+    let product = state
+        .products
+        .find_product(id)
+        .await?
+        .ok_or_else(|| product_not_found(id))?;
+    Ok(Json(product))
+}
+
+async fn update_product(
+    State(state): State<AppState>,
+    PathParams(id): PathParams<ProductId>,
+    ValidatedJson(product): ValidatedJson<NewProduct>,
+) -> Result<Json<Product>, AppError> {
+    check_categories(&state, &product).await?;
+    let product = state
+        .products
+        .update_product(id, product)
+        .await?
+        .ok_or_else(|| product_not_found(id))?;
+    Ok(Json(product))
+}
+
+async fn delete_product(
+    State(state): State<AppState>,
+    PathParams(id): PathParams<ProductId>,
+) -> Result<StatusCode, AppError> {
+    if !state.products.delete_product(id).await? {
+        return Err(product_not_found(id));
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn get_product_in_category(
+    State(state): State<AppState>,
+    PathParams((id, id_category)): PathParams<(ProductId, CategoryId)>,
+) -> Result<Json<Product>, AppError> {
+    let product = state
+        .products
+        .find_product(id)
+        .await?
+        .ok_or_else(|| product_not_found(id))?;
+
+    if !product.category_ids.contains(&id_category) {
+        return Err(AppError::not_found(
+            "product_not_in_category",
+            format!("Product {} is not in category {}", id, id_category),
+        ));
+    }
+    Ok(Json(product))
+}
+
+async fn list_categories(State(state): State<AppState>) -> Result<Json<Vec<Category>>, AppError> {
+    Ok(Json(state.products.list_categories().await?))
+}
+
+async fn create_category(
+    State(state): State<AppState>,
+    ValidatedJson(category): ValidatedJson<NewCategory>,
+) -> Result<(StatusCode, Json<Category>), AppError> {
+    let category = state.products.create_category(category).await?;
+    Ok((StatusCode::CREATED, Json(category)))
+}
+
+async fn get_category(
+    State(state): State<AppState>,
+    PathParams(id): PathParams<CategoryId>,
+) -> Result<Json<Category>, AppError> {
+    let category = state
+        .products
+        .find_category(id)
+        .await?
+        .ok_or_else(|| category_not_found(id))?;
+    Ok(Json(category))
+}
+
+async fn delete_category(
+    State(state): State<AppState>,
+    PathParams(id): PathParams<CategoryId>,
+) -> Result<StatusCode, AppError> {
+    if !state.products.delete_category(id).await? {
+        return Err(category_not_found(id));
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[cfg(test)]
+mod tests {
+    use axum_test::TestServer;
+    use serde_json::json;
+
+    use super::*;
+    use crate::{
+        auth::store::User,
+        error::assert_error_text,
+        test_support::{bearer, TestApp},
+    };
+
+    // semua request memakai token admin, permission diuji di rbac
+    async fn server() -> TestServer {
+        let (mut server, state) = TestApp::new()
+            .config(|config| config.rbac.admins = vec!["admin".to_string()])
+            .user(User::new("admin", "rahasia").unwrap())
+            .server();
+        server.add_header("Authorization", bearer(&state, "admin").await);
+        server
+    }
+
+    async fn create_category(server: &TestServer, name: &str) -> Category {
+        let response = server
+            .post("/api/products/categories")
+            .json(&json!({"name": name}))
+            .await;
+        response.assert_status(StatusCode::CREATED);
+        response.json()
+    }
+
+    // CRUD produk
+    #[tokio::test]
+    async fn test_product_crud() {
+        let server = server().await;
+        let category = create_category(&server, "Elektronik").await;
+
+        let response = server
+            .post("/api/products")
+            .json(&json!({"name": "TV", "price": 5000000, "category_ids": [category.id]}))
+            .await;
+        response.assert_status(StatusCode::CREATED);
+        let product: Product = response.json();
+        assert_eq!(product.category_ids, vec![category.id]);
+
+        let response = server.get(&format!("/api/products/{}", product.id)).await;
+        response.assert_status_ok();
+        response.assert_json(&product);
+
+        let response = server
+            .put(&format!("/api/products/{}", product.id))
+            .json(&json!({"name": "Radio", "price": 250000}))
+            .await;
+        response.assert_status_ok();
+        let updated: Product = response.json();
+        assert_eq!(updated.name, "Radio");
+        assert!(updated.category_ids.is_empty());
+
+        server
+            .get("/api/products")
+            .await
+            .assert_json(&vec![updated]);
+
+        let path = format!("/api/products/{}", product.id);
+        server
+            .delete(&path)
+            .await
+            .assert_status(StatusCode::NO_CONTENT);
+        let response = server.get(&path).await;
+        response.assert_status_not_found();
+        assert_error_text(&response, format!("Product {} not found", product.id));
+    }
+
+    // Produk di dalam kategori
+    #[tokio::test]
+    async fn test_product_in_category() {
+        let server = server().await;
+        let elektronik = create_category(&server, "Elektronik").await;
+        let rumah = create_category(&server, "Rumah").await;
+
+        let product: Product = server
+            .post("/api/products")
+            .json(&json!({"name": "TV", "price": 5000000, "category_ids": [elektronik.id]}))
+            .await
+            .json();
+
+        let response = server
+            .get(&format!(
+                "/api/products/{}/categories/{}",
+                product.id, elektronik.id
+            ))
+            .await;
+        response.assert_status_ok();
+        response.assert_json(&product);
+
+        let response = server
+            .get(&format!(
+                "/api/products/{}/categories/{}",
+                product.id, rumah.id
+            ))
+            .await;
+        response.assert_status_not_found();
+        assert_error_text(
+            &response,
+            format!("Product {} is not in category {}", product.id, rumah.id),
+        );
+
+        let response = server
+            .get("/api/products/99/categories/1")
+            .add_header("Accept", "application/json")
+            .await;
+        response.assert_status_not_found();
+        assert_eq!(
+            response.json::<serde_json::Value>()["code"],
+            "product_not_found"
+        );
+    }
+
+    // Path dan body yang tidak valid
+    #[tokio::test]
+    async fn test_invalid_product_requests() {
+        let server = server().await;
+
+        let response = server.get("/api/products/abc").await;
+        response.assert_status_bad_request();
+        response.assert_text_contains("Cannot parse `abc`");
+
+        let response = server
+            .post("/api/products")
+            .json(&json!({"name": " ", "price": -1, "category_ids": [7]}))
+            .await;
+        response.assert_status(StatusCode::UNPROCESSABLE_ENTITY);
+        assert_error_text(
+            &response,
+            "Request validation failed\n\
+             name: must not be blank\n\
+             price: must be at least 0",
+        );
+
+        let response = server
+            .post("/api/products")
+            .json(&json!({"name": "TV", "price": 1, "category_ids": [7]}))
+            .await;
+        response.assert_status(StatusCode::UNPROCESSABLE_ENTITY);
+        assert_error_text(
+            &response,
+            "Request validation failed\ncategory_ids[0]: category 7 does not exist",
+        );
+
+        server
+            .delete("/api/products/categories/7")
+            .await
+            .assert_status_not_found();
+    }
+}
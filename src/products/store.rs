@@ -0,0 +1,427 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    sync::RwLock,
+};
+
+use async_trait::async_trait;
+use sqlx::SqlitePool;
+
+use super::{Category, CategoryId, NewCategory, NewProduct, Product, ProductId};
+
+// Sumber data produk dan kategori
+#[async_trait]
+pub trait ProductStore: Send + Sync {
+    async fn list_products(&self) -> anyhow::Result<Vec<Product>>;
+    async fn find_product(&self, id: ProductId) -> anyhow::Result<Option<Product>>;
+    async fn create_product(&self, product: NewProduct) -> anyhow::Result<Product>;
+    async fn update_product(
+        &self,
+        id: ProductId,
+        product: NewProduct,
+    ) -> anyhow::Result<Option<Product>>;
+    async fn delete_product(&self, id: ProductId) -> anyhow::Result<bool>;
+
+    async fn list_categories(&self) -> anyhow::Result<Vec<Category>>;
+    async fn find_category(&self, id: CategoryId) -> anyhow::Result<Option<Category>>;
+    async fn create_category(&self, category: NewCategory) -> anyhow::Result<Category>;
+    /// Deleting a category also removes it from every product.
+    async fn delete_category(&self, id: CategoryId) -> anyhow::Result<bool>;
+}
+
+// Implementasi in-memory, id dibuat berurutan seperti AUTOINCREMENT di SQLite
+#[derive(Debug, Default)]
+pub struct InMemoryProductStore {
+    inner: RwLock<Inner>,
+}
+
+#[derive(Debug, Default)]
+struct Inner {
+    last_product_id: i64,
+    last_category_id: i64,
+    products: BTreeMap<ProductId, Product>,
+    categories: BTreeMap<CategoryId, Category>,
+}
+
+impl InMemoryProductStore {
+    pub fn new() -> InMemoryProductStore {
+        InMemoryProductStore::default()
+    }
+}
+
+fn sorted(category_ids: Vec<CategoryId>) -> Vec<CategoryId> {
+    category_ids
+        .into_iter()
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect()
+}
+
+#[async_trait]
+impl ProductStore for InMemoryProductStore {
+    async fn list_products(&self) -> anyhow::Result<Vec<Product>> {
+        Ok(self
+            .inner
+            .read()
+            .unwrap()
+            .products
+            .values()
+            .cloned()
+            .collect())
+    }
+
+    async fn find_product(&self, id: ProductId) -> anyhow::Result<Option<Product>> {
+        Ok(self.inner.read().unwrap().products.get(&id).cloned())
+    }
+
+    async fn create_product(&self, product: NewProduct) -> anyhow::Result<Product> {
+        let mut inner = self.inner.write().unwrap();
+        inner.last_product_id += 1;
+
+        let product = Product {
+            id: ProductId(inner.last_product_id),
+            name: product.name,
+            price: product.price,
+            category_ids: sorted(product.category_ids),
+        };
+        inner.products.insert(product.id, product.clone());
+        Ok(product)
+    }
+
+    async fn update_product(
+        &self,
+        id: ProductId,
+        product: NewProduct,
+    ) -> anyhow::Result<Option<Product>> {
+        let mut inner = self.inner.write().unwrap();
+        Ok(inner.products.get_mut(&id).map(|existing| {
+            existing.name = product.name;
+            existing.price = product.price;
+            existing.category_ids = sorted(product.category_ids);
+            existing.clone()
+        }))
+    }
+
+    async fn delete_product(&self, id: ProductId) -> anyhow::Result<bool> {
+        Ok(self.inner.write().unwrap().products.remove(&id).is_some())
+    }
+
+    async fn list_categories(&self) -> anyhow::Result<Vec<Category>> {
+        Ok(self
+            .inner
+            .read()
+            .unwrap()
+            .categories
+            .values()
+            .cloned()
+            .collect())
+    }
+
+    async fn find_category(&self, id: CategoryId) -> anyhow::Result<Option<Category>> {
+        Ok(self.inner.read().unwrap().categories.get(&id).cloned())
+    }
+
+    async fn create_category(&self, category: NewCategory) -> anyhow::Result<Category> {
+        let mut inner = self.inner.write().unwrap();
+        inner.last_category_id += 1;
+
+        let category = Category {
+            id: CategoryId(inner.last_category_id),
+            name: category.name,
+        };
+        inner.categories.insert(category.id, category.clone());
+        Ok(category)
+    }
+
+    async fn delete_category(&self, id: CategoryId) -> anyhow::Result<bool> {
+        let mut inner = self.inner.write().unwrap();
+        if inner.categories.remove(&id).is_none() {
+            return Ok(false);
+        }
+
+        for product in inner.products.values_mut() {
+            product
+                .category_ids
+                .retain(|category_id| *category_id != id);
+        }
+        Ok(true)
+    }
+}
+
+// Implementasi SQLite, relasi produk-kategori disimpan di tabel product_categories;
+// tabel dibuat oleh migration di migrations/sqlite
+#[derive(Debug, Clone)]
+pub struct SqliteProductStore {
+    pool: SqlitePool,
+}
+
+impl SqliteProductStore {
+    pub fn new(pool: SqlitePool) -> SqliteProductStore {
+        SqliteProductStore { pool }
+    }
+
+    async fn category_ids(&self, id: ProductId) -> anyhow::Result<Vec<CategoryId>> {
+        let rows: Vec<(i64,)> = sqlx::query_as(
+            "SELECT category_id FROM product_categories WHERE product_id = ? ORDER BY category_id",
+        )
+        .bind(id.0)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.into_iter().map(|(id,)| CategoryId(id)).collect())
+    }
+
+    async fn save_product(
+        &self,
+        id: Option<ProductId>,
+        product: NewProduct,
+    ) -> anyhow::Result<Option<Product>> {
+        let category_ids = sorted(product.category_ids);
+        let mut tx = self.pool.begin().await?;
+
+        let id = match id {
+            Some(id) => {
+                let result = sqlx::query("UPDATE products SET name = ?, price = ? WHERE id = ?")
+                    .bind(&product.name)
+                    .bind(product.price)
+                    .bind(id.0)
+                    .execute(&mut *tx)
+                    .await?;
+                if result.rows_affected() == 0 {
+                    return Ok(None);
+                }
+
+                sqlx::query("DELETE FROM product_categories WHERE product_id = ?")
+                    .bind(id.0)
+                    .execute(&mut *tx)
+                    .await?;
+                id
+            }
+            None => {
+                let result = sqlx::query("INSERT INTO products (name, price) VALUES (?, ?)")
+                    .bind(&product.name)
+                    .bind(product.price)
+                    .execute(&mut *tx)
+                    .await?;
+                ProductId(result.last_insert_rowid())
+            }
+        };
+
+        for category_id in &category_ids {
+            sqlx::query("INSERT INTO product_categories (product_id, category_id) VALUES (?, ?)")
+                .bind(id.0)
+                .bind(category_id.0)
+                .execute(&mut *tx)
+                .await?;
+        }
+        tx.commit().await?;
+
+        Ok(Some(Product {
+            id,
+            name: product.name,
+            price: product.price,
+            category_ids,
+        }))
+    }
+}
+
+#[async_trait]
+impl ProductStore for SqliteProductStore {
+    async fn list_products(&self) -> anyhow::Result<Vec<Product>> {
+        let rows: Vec<(i64, String, i64)> =
+            sqlx::query_as("SELECT id, name, price FROM products ORDER BY id")
+                .fetch_all(&self.pool)
+                .await?;
+        let links: Vec<(i64, i64)> = sqlx::query_as(
+            "SELECT product_id, category_id FROM product_categories ORDER BY category_id",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut category_ids: BTreeMap<i64, Vec<CategoryId>> = BTreeMap::new();
+        for (product_id, category_id) in links {
+            category_ids
+                .entry(product_id)
+                .or_default()
+                .push(CategoryId(category_id));
+        }
+
+        Ok(rows
+            .into_iter()
+            .map(|(id, name, price)| Product {
+                id: ProductId(id),
+                name,
+                price,
+                category_ids: category_ids.remove(&id).unwrap_or_default(),
+            })
+            .collect())
+    }
+
+    async fn find_product(&self, id: ProductId) -> anyhow::Result<Option<Product>> {
+        let row: Option<(String, i64)> =
+            sqlx::query_as("SELECT name, price FROM products WHERE id = ?")
+                .bind(id.0)
+                .fetch_optional(&self.pool)
+                .await?;
+
+        match row {
+            Some((name, price)) => Ok(Some(Product {
+                id,
+                name,
+                price,
+                category_ids: self.category_ids(id).await?,
+            })),
+            None => Ok(None),
+        }
+    }
+
+    async fn create_product(&self, product: NewProduct) -> anyhow::Result<Product> {
+        let product = self.save_product(None, product).await?;
+        // insert tanpa id selalu menghasilkan produk
+        Ok(product.unwrap())
+    }
+
+    async fn update_product(
+        &self,
+        id: ProductId,
+        product: NewProduct,
+    ) -> anyhow::Result<Option<Product>> {
+        self.save_product(Some(id), product).await
+    }
+
+    async fn delete_product(&self, id: ProductId) -> anyhow::Result<bool> {
+        let result = sqlx::query("DELETE FROM products WHERE id = ?")
+            .bind(id.0)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn list_categories(&self) -> anyhow::Result<Vec<Category>> {
+        let rows: Vec<(i64, String)> =
+            sqlx::query_as("SELECT id, name FROM categories ORDER BY id")
+                .fetch_all(&self.pool)
+                .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|(id, name)| Category {
+                id: CategoryId(id),
+                name,
+            })
+            .collect())
+    }
+
+    async fn find_category(&self, id: CategoryId) -> anyhow::Result<Option<Category>> {
+        let row: Option<(String,)> = sqlx::query_as("SELECT name FROM categories WHERE id = ?")
+            .bind(id.0)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(row.map(|(name,)| Category { id, name }))
+    }
+
+    async fn create_category(&self, category: NewCategory) -> anyhow::Result<Category> {
+        let result = sqlx::query("INSERT INTO categories (name) VALUES (?)")
+            .bind(&category.name)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(Category {
+            id: CategoryId(result.last_insert_rowid()),
+            name: category.name,
+        })
+    }
+
+    async fn delete_category(&self, id: CategoryId) -> anyhow::Result<bool> {
+        let result = sqlx::query("DELETE FROM categories WHERE id = ?")
+            .bind(id.0)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::check_store_contract;
+
+    fn new_product(name: &str, price: i64, category_ids: &[i64]) -> NewProduct {
+        NewProduct {
+            name: name.to_string(),
+            price,
+            category_ids: category_ids.iter().copied().map(CategoryId).collect(),
+        }
+    }
+
+    // Kontrak ProductStore: category_ids selalu terurut, menghapus kategori
+    // melepasnya dari produk, update id yang tidak ada mengembalikan None
+    async fn exercise(store: &dyn ProductStore) {
+        let elektronik = store
+            .create_category(NewCategory {
+                name: "Elektronik".to_string(),
+            })
+            .await
+            .unwrap();
+        let rumah = store
+            .create_category(NewCategory {
+                name: "Rumah".to_string(),
+            })
+            .await
+            .unwrap();
+        assert_eq!(
+            store.list_categories().await.unwrap(),
+            vec![elektronik.clone(), rumah.clone()]
+        );
+
+        let tv = store
+            .create_product(new_product("TV", 5_000_000, &[rumah.id.0, elektronik.id.0]))
+            .await
+            .unwrap();
+        assert_eq!(tv.category_ids, vec![elektronik.id, rumah.id]);
+        assert_eq!(store.find_product(tv.id).await.unwrap(), Some(tv.clone()));
+
+        let updated = store
+            .update_product(tv.id, new_product("TV 43\"", 4_500_000, &[elektronik.id.0]))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(updated.name, "TV 43\"");
+        assert_eq!(updated.category_ids, vec![elektronik.id]);
+        assert_eq!(store.list_products().await.unwrap(), vec![updated.clone()]);
+        assert_eq!(
+            store
+                .update_product(ProductId(99), new_product("X", 1, &[]))
+                .await
+                .unwrap(),
+            None
+        );
+
+        assert!(store.delete_category(elektronik.id).await.unwrap());
+        assert!(!store.delete_category(elektronik.id).await.unwrap());
+        assert!(store
+            .find_product(tv.id)
+            .await
+            .unwrap()
+            .unwrap()
+            .category_ids
+            .is_empty());
+
+        assert!(store.delete_product(tv.id).await.unwrap());
+        assert_eq!(store.find_product(tv.id).await.unwrap(), None);
+        assert!(!store.delete_product(tv.id).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_store_contract() {
+        let pool = crate::db::sqlite_memory().await;
+        check_store_contract::<dyn ProductStore>(
+            vec![
+                ("in-memory", Box::new(InMemoryProductStore::new())),
+                ("sqlite", Box::new(SqliteProductStore::new(pool))),
+            ],
+            async |store| exercise(store).await,
+        )
+        .await;
+    }
+}
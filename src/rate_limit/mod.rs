@@ -0,0 +1,379 @@
+pub mod store;
+
+use std::time::Duration;
+
+use axum::{
+    extract::{FromRequestParts, MatchedPath, Request, State},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use http::{header, request::Parts, HeaderMap, HeaderName, HeaderValue};
+use sha2::{Digest, Sha256};
+use tokio::time::Instant;
+
+use crate::{
+    auth::AuthUser,
+    config::{RateLimitKey, RateLimitPolicy},
+    error::AppError,
+    extract::ClientIp,
+    state::AppState,
+};
+
+pub use self::store::{InMemoryRateLimitStore, RateLimitStore};
+
+// Header rate limit sesuai draft IETF "RateLimit header fields for HTTP"
+pub static RATELIMIT_LIMIT: HeaderName = HeaderName::from_static("ratelimit-limit");
+pub static RATELIMIT_REMAINING: HeaderName = HeaderName::from_static("ratelimit-remaining");
+pub static RATELIMIT_RESET: HeaderName = HeaderName::from_static("ratelimit-reset");
+pub static RATELIMIT_POLICY: HeaderName = HeaderName::from_static("ratelimit-policy");
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimitDecision {
+    pub allowed: bool,
+    pub limit: u32,
+    pub remaining: u32,
+    // waktu sampai kuota kembali penuh
+    pub reset: Duration,
+    // hanya ada bila request ditolak
+    pub retry_after: Option<Duration>,
+}
+
+/// Generic cell rate algorithm: one request every `period / requests`, with a burst of `requests`.
+///
+/// `tat` is the stored theoretical arrival time, `None` for an unknown key. Returns the new TAT
+/// to store, or `None` if the request is rejected.
+pub fn gcra(
+    policy: &RateLimitPolicy,
+    tat: Option<Instant>,
+    now: Instant,
+) -> (RateLimitDecision, Option<Instant>) {
+    let period = policy.period();
+    let interval = period / policy.requests;
+    let tat = tat.map_or(now, |tat| tat.max(now));
+    let next = tat + interval;
+    let limit_at = now + period;
+
+    if next > limit_at {
+        let decision = RateLimitDecision {
+            allowed: false,
+            limit: policy.requests,
+            remaining: 0,
+            reset: tat - now,
+            retry_after: Some(next - limit_at),
+        };
+        return (decision, None);
+    }
+
+    let remaining = (limit_at - next).as_nanos() / interval.as_nanos().max(1);
+    let decision = RateLimitDecision {
+        allowed: true,
+        limit: policy.requests,
+        remaining: remaining as u32,
+        reset: next - now,
+        retry_after: None,
+    };
+    (decision, Some(next))
+}
+
+// detik dibulatkan ke atas agar client tidak mencoba terlalu cepat
+fn seconds(duration: Duration) -> u64 {
+    duration.as_secs() + u64::from(duration.subsec_nanos() > 0)
+}
+
+// Kunci pengelompokan request; API key disimpan sebagai hash, bukan nilai aslinya,
+// dan hanya API key yang terdaftar di rate_limit.api_keys yang mendapat kuota sendiri
+async fn client_key(state: &AppState, parts: &mut Parts, key: RateLimitKey) -> String {
+    match key {
+        RateLimitKey::Ip => {}
+        RateLimitKey::User => {
+            if let Ok(user) = AuthUser::from_request_parts(parts, state).await {
+                return format!("user:{}", user.username);
+            }
+        }
+        RateLimitKey::ApiKey => {
+            let header = state.config.rate_limit.api_key_header.as_str();
+            let known = &state.config.rate_limit.api_keys;
+            let api_key = parts.headers.get(header).filter(|api_key| {
+                known
+                    .iter()
+                    .any(|known| known.as_bytes() == api_key.as_bytes())
+            });
+            if let Some(api_key) = api_key {
+                let digest = Sha256::digest(api_key.as_bytes());
+                let hex: String = digest[..16]
+                    .iter()
+                    .map(|byte| format!("{:02x}", byte))
+                    .collect();
+                return format!("api_key:{}", hex);
+            }
+        }
+    }
+    match ClientIp::from_parts(parts) {
+        ClientIp(Some(ip)) => format!("ip:{}", ip),
+        ClientIp(None) => "ip:unknown".to_string(),
+    }
+}
+
+fn insert_headers(headers: &mut HeaderMap, policy: &RateLimitPolicy, decision: &RateLimitDecision) {
+    headers.insert(RATELIMIT_LIMIT.clone(), HeaderValue::from(decision.limit));
+    headers.insert(
+        RATELIMIT_REMAINING.clone(),
+        HeaderValue::from(decision.remaining),
+    );
+    headers.insert(
+        RATELIMIT_RESET.clone(),
+        HeaderValue::from(seconds(decision.reset)),
+    );
+    let policy = format!("{};w={}", policy.requests, policy.period);
+    if let Ok(policy) = HeaderValue::try_from(policy) {
+        headers.insert(RATELIMIT_POLICY.clone(), policy);
+    }
+    if let Some(retry_after) = decision.retry_after {
+        headers.insert(header::RETRY_AFTER, HeaderValue::from(seconds(retry_after)));
+    }
+}
+
+// Middleware: policy dipilih berdasarkan template route, dipasang dengan route_layer
+// agar MatchedPath tersedia; setiap route punya kuota sendiri
+pub async fn rate_limit_middleware(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let config = &state.config.rate_limit;
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_string())
+        .unwrap_or_default();
+    let policy = config.routes.get(&route).or(config.default.as_ref());
+    let Some(policy) = policy.filter(|_| config.enabled) else {
+        return next.run(request).await;
+    };
+
+    let (mut parts, body) = request.into_parts();
+    let key = format!(
+        "{} {}",
+        route,
+        client_key(&state, &mut parts, policy.key).await
+    );
+    let request = Request::from_parts(parts, body);
+
+    let decision = match state.rate_limits.check(&key, policy).await {
+        Ok(decision) => decision,
+        Err(error) => {
+            // store yang tidak tersedia tidak boleh membuat seluruh API mati
+            tracing::warn!(%error, "rate limit store failed, request allowed");
+            return next.run(request).await;
+        }
+    };
+
+    let mut response = if decision.allowed {
+        next.run(request).await
+    } else {
+        let retry_after = seconds(decision.retry_after.unwrap_or_default());
+        AppError::too_many_requests(
+            "rate_limited",
+            format!("Too many requests, retry in {} seconds", retry_after),
+        )
+        .into_response()
+    };
+    insert_headers(response.headers_mut(), policy, &decision);
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::SocketAddr;
+
+    use axum::{extract::connect_info::MockConnectInfo, routing::get, Router};
+    use axum_test::TestServer;
+    use http::StatusCode;
+
+    use super::*;
+    use crate::{
+        auth::store::User,
+        test_support::{bearer, TestApp},
+    };
+
+    fn policy(requests: u32, period: u64, key: RateLimitKey) -> RateLimitPolicy {
+        RateLimitPolicy {
+            requests,
+            period,
+            key,
+        }
+    }
+
+    fn server(policy: RateLimitPolicy) -> (TestServer, AppState) {
+        let state = TestApp::new()
+            .config(|config| {
+                config.rate_limit.routes = [("/limited".to_string(), policy)].into();
+                config.rate_limit.api_keys = vec!["kunci-a".to_string(), "kunci-b".to_string()];
+            })
+            .user(User::new("aqil", "rahasia").unwrap())
+            .user(User::new("budi", "rahasia").unwrap())
+            .state();
+
+        let app = Router::new()
+            .route("/limited", get(|| async { "Limited" }))
+            .route_layer(axum::middleware::from_fn_with_state(
+                state.clone(),
+                rate_limit_middleware,
+            ))
+            .route("/free", get(|| async { "Free" }))
+            .with_state(state.clone())
+            .layer(MockConnectInfo(SocketAddr::from(([10, 0, 0, 1], 4000))));
+        (TestServer::new(app).unwrap(), state)
+    }
+
+    #[test]
+    fn test_gcra() {
+        let policy = policy(3, 60, RateLimitKey::Ip);
+        let now = Instant::now();
+
+        let (decision, tat) = gcra(&policy, None, now);
+        assert!(decision.allowed);
+        assert_eq!(decision.remaining, 2);
+        assert_eq!(decision.reset, Duration::from_secs(20));
+
+        let (decision, tat) = gcra(&policy, tat, now);
+        assert_eq!(decision.remaining, 1);
+        let (decision, tat) = gcra(&policy, tat, now);
+        assert_eq!(decision.remaining, 0);
+        assert_eq!(decision.reset, Duration::from_secs(60));
+
+        let (decision, rejected) = gcra(&policy, tat, now);
+        assert!(!decision.allowed);
+        assert_eq!(rejected, None);
+        assert_eq!(decision.retry_after, Some(Duration::from_secs(20)));
+
+        // satu request kembali tersedia setiap 20 detik
+        let later = now + Duration::from_secs(20);
+        let (decision, _) = gcra(&policy, tat, later);
+        assert!(decision.allowed);
+        assert_eq!(decision.remaining, 0);
+
+        // TAT yang sudah lewat sama dengan kunci baru
+        let (decision, _) = gcra(&policy, tat, now + Duration::from_secs(600));
+        assert_eq!(decision.remaining, 2);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_rate_limit_headers() {
+        let (server, _) = server(policy(2, 60, RateLimitKey::Ip));
+
+        let response = server.get("/limited").await;
+        response.assert_status_ok();
+        response.assert_header("RateLimit-Limit", "2");
+        response.assert_header("RateLimit-Remaining", "1");
+        response.assert_header("RateLimit-Reset", "30");
+        response.assert_header("RateLimit-Policy", "2;w=60");
+
+        server.get("/limited").await.assert_status_ok();
+        let response = server.get("/limited").await;
+        response.assert_status(StatusCode::TOO_MANY_REQUESTS);
+        response.assert_header("Retry-After", "30");
+        response.assert_header("RateLimit-Remaining", "0");
+        response.assert_text("Too many requests, retry in 30 seconds");
+
+        // route tanpa policy tidak dibatasi
+        let response = server.get("/free").await;
+        response.assert_status_ok();
+        assert!(response.maybe_header("RateLimit-Limit").is_none());
+
+        tokio::time::advance(Duration::from_secs(30)).await;
+        server.get("/limited").await.assert_status_ok();
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_rate_limit_user_key() {
+        let (server, state) = server(policy(1, 60, RateLimitKey::User));
+        let aqil = bearer(&state, "aqil").await;
+        let budi = bearer(&state, "budi").await;
+
+        server
+            .get("/limited")
+            .authorization(&aqil)
+            .await
+            .assert_status_ok();
+        server
+            .get("/limited")
+            .authorization(&aqil)
+            .await
+            .assert_status(StatusCode::TOO_MANY_REQUESTS);
+        server
+            .get("/limited")
+            .authorization(&budi)
+            .await
+            .assert_status_ok();
+
+        // tanpa login kembali ke IP
+        server.get("/limited").await.assert_status_ok();
+        server
+            .get("/limited")
+            .await
+            .assert_status(StatusCode::TOO_MANY_REQUESTS);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_rate_limit_api_key() {
+        let (server, _) = server(policy(1, 60, RateLimitKey::ApiKey));
+        server
+            .get("/limited")
+            .add_header("X-API-Key", "kunci-a")
+            .await
+            .assert_status_ok();
+        server
+            .get("/limited")
+            .add_header("X-API-Key", "kunci-a")
+            .await
+            .assert_status(StatusCode::TOO_MANY_REQUESTS);
+        server
+            .get("/limited")
+            .add_header("X-API-Key", "kunci-b")
+            .await
+            .assert_status_ok();
+
+        // API key yang tidak dikenal dihitung per IP, bukan kuota baru per nilai header
+        server
+            .get("/limited")
+            .add_header("X-API-Key", "acak-1")
+            .await
+            .assert_status_ok();
+        server
+            .get("/limited")
+            .add_header("X-API-Key", "acak-2")
+            .await
+            .assert_status(StatusCode::TOO_MANY_REQUESTS);
+    }
+
+    // Percobaan login dibatasi per IP lewat build_app
+    #[tokio::test]
+    async fn test_login_rate_limit() {
+        let (server, _) = TestApp::new()
+            .config(|config| {
+                config.rate_limit.routes.insert(
+                    "/api/auth/login".to_string(),
+                    policy(2, 60, RateLimitKey::Ip),
+                );
+            })
+            .server();
+        let login = serde_json::json!({"username": "aqil", "password": "rahasia"});
+
+        for _ in 0..2 {
+            let response = server.post("/api/auth/login").json(&login).await;
+            response.assert_status(StatusCode::UNAUTHORIZED);
+        }
+        let response = server
+            .post("/api/auth/login")
+            .add_header("Accept", "application/json")
+            .json(&login)
+            .await;
+        response.assert_status(StatusCode::TOO_MANY_REQUESTS);
+        let problem: serde_json::Value = response.json();
+        assert_eq!(problem["code"], "rate_limited");
+
+        // route lain tidak terpengaruh
+        server.get("/api/products").await.assert_status_ok();
+    }
+}
@@ -0,0 +1,152 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    sync::Mutex,
+};
+
+use async_trait::async_trait;
+use tokio::time::Instant;
+
+use super::{gcra, RateLimitDecision};
+use crate::config::RateLimitPolicy;
+
+// Penyimpanan state rate limit per kunci, bisa diganti dengan Redis bila server lebih dari satu
+#[async_trait]
+pub trait RateLimitStore: Send + Sync {
+    /// Counts one request for `key` under `policy`; rejected requests are not counted.
+    async fn check(&self, key: &str, policy: &RateLimitPolicy)
+        -> anyhow::Result<RateLimitDecision>;
+}
+
+// Implementasi in-memory, menyimpan theoretical arrival time (TAT) GCRA per kunci
+#[derive(Debug)]
+pub struct InMemoryRateLimitStore {
+    max_keys: usize,
+    buckets: Mutex<Buckets>,
+}
+
+#[derive(Debug, Default)]
+struct Buckets {
+    tats: HashMap<String, Instant>,
+    // kunci terurut menurut TAT, agar kunci yang sudah pulih atau paling cepat
+    // pulih bisa dibuang tanpa scan seluruh map
+    by_tat: BTreeSet<(Instant, String)>,
+}
+
+impl Buckets {
+    fn insert(&mut self, key: &str, tat: Instant) {
+        if let Some(previous) = self.tats.insert(key.to_string(), tat) {
+            self.by_tat.remove(&(previous, key.to_string()));
+        }
+        self.by_tat.insert((tat, key.to_string()));
+    }
+
+    // Kunci dengan TAT yang sudah lewat sama dengan kunci baru, aman dibuang;
+    // bila masih penuh, buang kunci yang paling cepat kembali penuh. Setiap kunci
+    // hanya dibuang sekali sehingga biayanya teramortisasi per insert
+    fn evict(&mut self, max_keys: usize, now: Instant) {
+        while let Some((tat, _)) = self.by_tat.first() {
+            if *tat > now && self.tats.len() < max_keys {
+                break;
+            }
+            if let Some((_, key)) = self.by_tat.pop_first() {
+                self.tats.remove(&key);
+            }
+        }
+    }
+}
+
+impl InMemoryRateLimitStore {
+    pub fn new(max_keys: usize) -> InMemoryRateLimitStore {
+        InMemoryRateLimitStore {
+            max_keys,
+            buckets: Mutex::new(Buckets::default()),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.buckets.lock().unwrap().tats.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[async_trait]
+impl RateLimitStore for InMemoryRateLimitStore {
+    async fn check(
+        &self,
+        key: &str,
+        policy: &RateLimitPolicy,
+    ) -> anyhow::Result<RateLimitDecision> {
+        let now = Instant::now();
+        let mut buckets = self.buckets.lock().unwrap();
+        let (decision, tat) = gcra(policy, buckets.tats.get(key).copied(), now);
+
+        if let Some(tat) = tat {
+            if !buckets.tats.contains_key(key) {
+                buckets.evict(self.max_keys, now);
+            }
+            buckets.insert(key, tat);
+        }
+        Ok(decision)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::config::RateLimitKey;
+
+    fn policy(requests: u32, period: u64) -> RateLimitPolicy {
+        RateLimitPolicy {
+            requests,
+            period,
+            key: RateLimitKey::Ip,
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_in_memory_store() {
+        let store = InMemoryRateLimitStore::new(10);
+        let policy = policy(2, 60);
+
+        assert!(store.check("a", &policy).await.unwrap().allowed);
+        assert!(store.check("a", &policy).await.unwrap().allowed);
+        let decision = store.check("a", &policy).await.unwrap();
+        assert!(!decision.allowed);
+        assert_eq!(decision.retry_after, Some(Duration::from_secs(30)));
+
+        // kunci lain punya kuota sendiri
+        assert!(store.check("b", &policy).await.unwrap().allowed);
+
+        tokio::time::advance(Duration::from_secs(30)).await;
+        assert!(store.check("a", &policy).await.unwrap().allowed);
+        assert!(!store.check("a", &policy).await.unwrap().allowed);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_eviction() {
+        let store = InMemoryRateLimitStore::new(2);
+        let policy = policy(2, 60);
+
+        store.check("a", &policy).await.unwrap();
+        store.check("a", &policy).await.unwrap();
+        tokio::time::advance(Duration::from_secs(1)).await;
+        store.check("b", &policy).await.unwrap();
+        assert_eq!(store.len(), 2);
+
+        // penuh: "b" paling cepat kembali penuh sehingga dibuang lebih dulu
+        store.check("c", &policy).await.unwrap();
+        assert_eq!(store.len(), 2);
+        assert!(!store.check("a", &policy).await.unwrap().allowed);
+        assert_eq!(store.check("b", &policy).await.unwrap().remaining, 1);
+
+        // kunci yang kuotanya sudah pulih dibuang semua
+        tokio::time::advance(Duration::from_secs(60)).await;
+        store.check("d", &policy).await.unwrap();
+        assert_eq!(store.len(), 1);
+    }
+}
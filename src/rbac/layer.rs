@@ -0,0 +1,104 @@
+use std::{
+    convert::Infallible,
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use axum::{
+    extract::{FromRequestParts, Request},
+    response::{IntoResponse, Response},
+};
+use tower::{Layer, Service};
+
+use super::{user_roles, Permission};
+use crate::{auth::AuthUser, error::AppError, state::AppState};
+
+/// Layer that only lets through authenticated users holding `permission`.
+///
+/// Attach it with `route_layer` so unmatched paths and methods still get 404/405.
+pub fn require_permission(state: &AppState, permission: &str) -> RequirePermission {
+    RequirePermission {
+        state: state.clone(),
+        permission: permission.parse().expect("invalid permission"),
+    }
+}
+
+#[derive(Clone)]
+pub struct RequirePermission {
+    state: AppState,
+    permission: Permission,
+}
+
+impl<S> Layer<S> for RequirePermission {
+    type Service = RequirePermissionService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RequirePermissionService {
+            inner,
+            state: self.state.clone(),
+            permission: self.permission.clone(),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct RequirePermissionService<S> {
+    inner: S,
+    state: AppState,
+    permission: Permission,
+}
+
+impl<S> Service<Request> for RequirePermissionService<S>
+where
+    S: Service<Request, Response = Response, Error = Infallible> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = Response;
+    type Error = Infallible;
+    type Future = Pin<Box<dyn Future<Output = Result<Response, Infallible>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request) -> Self::Future {
+        // service yang sudah ready dipakai, clone-nya menggantikan untuk request berikutnya
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+        let state = self.state.clone();
+        let permission = self.permission.clone();
+
+        Box::pin(async move {
+            match authorize(&state, &permission, request).await {
+                Ok(request) => inner.call(request).await,
+                Err(error) => Ok(error.into_response()),
+            }
+        })
+    }
+}
+
+async fn authorize(
+    state: &AppState,
+    permission: &Permission,
+    request: Request,
+) -> Result<Request, AppError> {
+    let (mut parts, body) = request.into_parts();
+    let user = AuthUser::from_request_parts(&mut parts, state).await?;
+
+    let roles = user_roles(state, &user.username).await?;
+    if !roles
+        .permissions
+        .iter()
+        .any(|granted| granted.grants(permission))
+    {
+        return Err(AppError::forbidden(
+            "missing_permission",
+            format!("Permission {} is required", permission),
+        ));
+    }
+
+    // handler bisa langsung memakai AuthUser tanpa verifikasi ulang
+    parts.extensions.insert(user);
+    Ok(Request::from_parts(parts, body))
+}
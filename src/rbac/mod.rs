@@ -0,0 +1,572 @@
+pub mod layer;
+pub mod store;
+
+use std::{collections::BTreeMap, fmt, str::FromStr};
+
+use axum::{
+    extract::State,
+    routing::{get, put, MethodRouter},
+    Json, Router,
+};
+use http::StatusCode;
+use serde::{Deserialize, Serialize};
+
+use crate::{auth::AuthUser, error::AppError, extract::PathParams, state::AppState};
+
+pub use self::{
+    layer::{require_permission, RequirePermission},
+    store::{InMemoryRoleStore, RoleStore, SqliteRoleStore},
+};
+
+// Role bawaan yang selalu memiliki semua permission, tidak bisa didefinisikan ulang
+pub const ADMIN_ROLE: &str = "admin";
+
+// Permission berbentuk "<resource>:<action>", contoh "products:write";
+// "products:*" mencakup semua action pada products dan "*" mencakup semuanya
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Permission(String);
+
+#[derive(Debug, thiserror::Error)]
+#[error("invalid permission {0:?}, expected <resource>:<action> or *")]
+pub struct InvalidPermission(String);
+
+// nama resource, action dan role: huruf kecil, angka, _ dan -
+fn valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.len() <= 64
+        && name
+            .bytes()
+            .all(|byte| matches!(byte, b'a'..=b'z' | b'0'..=b'9' | b'_' | b'-'))
+}
+
+pub fn valid_role(role: &str) -> bool {
+    valid_name(role)
+}
+
+impl Permission {
+    pub fn all() -> Permission {
+        Permission("*".to_string())
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Returns true if holding this permission satisfies `required`.
+    pub fn grants(&self, required: &Permission) -> bool {
+        if self.0 == "*" || self == required {
+            return true;
+        }
+        match (self.0.split_once(':'), required.0.split_once(':')) {
+            (Some((resource, "*")), Some((required, _))) => resource == required,
+            _ => false,
+        }
+    }
+}
+
+impl TryFrom<String> for Permission {
+    type Error = InvalidPermission;
+
+    fn try_from(permission: String) -> Result<Self, Self::Error> {
+        let valid = permission == "*"
+            || matches!(
+                permission.split_once(':'),
+                Some((resource, action)) if valid_name(resource) && (action == "*" || valid_name(action))
+            );
+        if valid {
+            Ok(Permission(permission))
+        } else {
+            Err(InvalidPermission(permission))
+        }
+    }
+}
+
+impl FromStr for Permission {
+    type Err = InvalidPermission;
+
+    fn from_str(permission: &str) -> Result<Self, Self::Err> {
+        Permission::try_from(permission.to_string())
+    }
+}
+
+impl From<Permission> for String {
+    fn from(permission: Permission) -> Self {
+        permission.0
+    }
+}
+
+impl fmt::Display for Permission {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UserRoles {
+    pub username: String,
+    pub roles: Vec<String>,
+    // gabungan permission dari semua role
+    pub permissions: Vec<Permission>,
+}
+
+/// All known roles: the built-in admin role, roles from config and roles from the store.
+pub async fn roles(state: &AppState) -> anyhow::Result<BTreeMap<String, Vec<Permission>>> {
+    let mut roles = BTreeMap::from([(ADMIN_ROLE.to_string(), vec![Permission::all()])]);
+    for (role, permissions) in &state.config.rbac.roles {
+        roles.insert(role.clone(), permissions.clone());
+    }
+    for (role, permissions) in state.roles.roles().await? {
+        let granted = roles.entry(role).or_default();
+        granted.extend(permissions);
+        granted.sort();
+        granted.dedup();
+    }
+    Ok(roles)
+}
+
+/// Roles assigned to the user in the store, plus admin if the user is listed in `rbac.admins`.
+pub async fn user_roles(state: &AppState, username: &str) -> anyhow::Result<UserRoles> {
+    let mut roles = state.roles.user_roles(username).await?;
+    if state
+        .config
+        .rbac
+        .admins
+        .iter()
+        .any(|admin| admin == username)
+    {
+        roles.push(ADMIN_ROLE.to_string());
+    }
+    roles.sort();
+    roles.dedup();
+
+    let mut permissions = Vec::new();
+    for role in &roles {
+        if role == ADMIN_ROLE {
+            permissions.push(Permission::all());
+        }
+        if let Some(granted) = state.config.rbac.roles.get(role) {
+            permissions.extend(granted.iter().cloned());
+        }
+        permissions.extend(state.roles.permissions(role).await?);
+    }
+    permissions.sort();
+    permissions.dedup();
+
+    Ok(UserRoles {
+        username: username.to_string(),
+        roles,
+        permissions,
+    })
+}
+
+// Route /api/admin: melihat role butuh roles:read, mengubahnya butuh roles:write
+pub fn router(state: &AppState) -> Router<AppState> {
+    let read = |method_router: MethodRouter<AppState>| {
+        method_router.route_layer(require_permission(state, "roles:read"))
+    };
+    let write = |method_router: MethodRouter<AppState>| {
+        method_router.route_layer(require_permission(state, "roles:write"))
+    };
+
+    Router::new()
+        .route("/roles", read(get(list_roles)))
+        .route("/users/{username}/roles", read(get(get_user_roles)))
+        .route(
+            "/users/{username}/roles/{role}",
+            write(put(assign_role).delete(revoke_role)),
+        )
+}
+
+async fn list_roles(
+    State(state): State<AppState>,
+) -> Result<Json<BTreeMap<String, Vec<Permission>>>, AppError> {
+    Ok(Json(roles(&state).await?))
+}
+
+async fn get_user_roles(
+    State(state): State<AppState>,
+    PathParams(username): PathParams<String>,
+) -> Result<Json<UserRoles>, AppError> {
+    if state.users.find_by_username(&username).await?.is_none() {
+        return Err(user_not_found(&username));
+    }
+    Ok(Json(user_roles(&state, &username).await?))
+}
+
+async fn assign_role(
+    State(state): State<AppState>,
+    caller: AuthUser,
+    PathParams((username, role)): PathParams<(String, String)>,
+) -> Result<StatusCode, AppError> {
+    if state.users.find_by_username(&username).await?.is_none() {
+        return Err(user_not_found(&username));
+    }
+    let Some(permissions) = roles(&state).await?.remove(&role) else {
+        return Err(AppError::not_found(
+            "role_not_found",
+            format!("Role {} not found", role),
+        ));
+    };
+
+    ensure_manageable(&state, &caller, &role, &permissions).await?;
+
+    // assign ulang role yang sudah dimiliki bukan error
+    state.roles.assign(&username, &role).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn revoke_role(
+    State(state): State<AppState>,
+    caller: AuthUser,
+    PathParams((username, role)): PathParams<(String, String)>,
+) -> Result<StatusCode, AppError> {
+    // admin dari rbac.admins tetap admin selama config-nya tidak diubah
+    if role == ADMIN_ROLE && state.config.rbac.admins.contains(&username) {
+        return Err(AppError::conflict(
+            "role_from_config",
+            format!(
+                "Role {} of user {} comes from rbac.admins and cannot be revoked",
+                role, username
+            ),
+        ));
+    }
+    let permissions = roles(&state).await?.remove(&role).unwrap_or_default();
+    ensure_manageable(&state, &caller, &role, &permissions).await?;
+
+    if !state.roles.revoke(&username, &role).await? {
+        return Err(AppError::not_found(
+            "role_not_assigned",
+            format!("User {} does not have role {}", username, role),
+        ));
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// roles:write saja tidak cukup untuk memberi atau mencabut role: pemanggil harus
+// sudah memegang semua permission role tersebut, kalau tidak pemegang roles:write
+// bisa menaikkan hak aksesnya sendiri atau mencabut admin
+async fn ensure_manageable(
+    state: &AppState,
+    caller: &AuthUser,
+    role: &str,
+    permissions: &[Permission],
+) -> Result<(), AppError> {
+    let held = user_roles(state, &caller.username).await?.permissions;
+    let missing = permissions
+        .iter()
+        .find(|required| !held.iter().any(|permission| permission.grants(required)));
+    if let Some(missing) = missing {
+        return Err(AppError::forbidden(
+            "role_escalation",
+            format!("Permission {} is required to manage role {}", missing, role),
+        ));
+    }
+    Ok(())
+}
+
+fn user_not_found(username: &str) -> AppError {
+    AppError::not_found("user_not_found", format!("User {} not found", username))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use axum_test::TestServer;
+    use serde_json::json;
+
+    use super::*;
+    use crate::{
+        auth::store::User,
+        error::assert_error_text,
+        test_support::{self, bearer, TestApp},
+    };
+
+    fn permission(permission: &str) -> Permission {
+        permission.parse().unwrap()
+    }
+
+    fn server() -> (TestServer, AppState) {
+        let roles = InMemoryRoleStore::new();
+        roles.grant("auditor", permission("roles:read"));
+        roles.grant("manager", permission("roles:write"));
+        roles.grant("manager", permission("products:write"));
+
+        let mut app = TestApp::new().config(|config| {
+            config.rbac.admins = vec!["aqil".to_string()];
+            config.rbac.roles =
+                BTreeMap::from([("editor".to_string(), vec![permission("products:write")])]);
+        });
+        for username in ["aqil", "budi", "citra"] {
+            app = app.user(User {
+                username: username.to_string(),
+                password_hash: "hash".to_string(),
+                email: None,
+                display_name: None,
+                locked: false,
+                avatar: None,
+                token_version: 0,
+            });
+        }
+
+        let state = app.state().with_roles(Arc::new(roles));
+        (test_support::server(&state), state)
+    }
+
+    #[test]
+    fn test_permission() {
+        assert!(permission("products:write").grants(&permission("products:write")));
+        assert!(!permission("products:read").grants(&permission("products:write")));
+        assert!(permission("products:*").grants(&permission("products:write")));
+        assert!(!permission("products:*").grants(&permission("roles:write")));
+        assert!(Permission::all().grants(&permission("roles:write")));
+
+        for invalid in [
+            "",
+            "products",
+            "products:",
+            ":write",
+            "Products:write",
+            "*:write",
+        ] {
+            assert!(invalid.parse::<Permission>().is_err(), "{}", invalid);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_user_roles() {
+        let (_, state) = server();
+        state.roles.assign("budi", "editor").await.unwrap();
+        state.roles.assign("budi", "auditor").await.unwrap();
+
+        let roles = user_roles(&state, "budi").await.unwrap();
+        assert_eq!(roles.roles, vec!["auditor", "editor"]);
+        assert_eq!(
+            roles.permissions,
+            vec![permission("products:write"), permission("roles:read")]
+        );
+
+        let roles = user_roles(&state, "aqil").await.unwrap();
+        assert_eq!(roles.roles, vec![ADMIN_ROLE]);
+        assert_eq!(roles.permissions, vec![Permission::all()]);
+
+        assert!(user_roles(&state, "citra").await.unwrap().roles.is_empty());
+    }
+
+    // Admin meng-assign role, user lain mendapat 403
+    #[tokio::test]
+    async fn test_assign_roles() {
+        let (server, state) = server();
+        let admin = bearer(&state, "aqil").await;
+
+        let response = server.get("/api/admin/roles").authorization(&admin).await;
+        response.assert_status_ok();
+        response.assert_json(&json!({
+            "admin": ["*"],
+            "auditor": ["roles:read"],
+            "editor": ["products:write"],
+            "manager": ["products:write", "roles:write"],
+        }));
+
+        server
+            .put("/api/admin/users/budi/roles/editor")
+            .authorization(&admin)
+            .await
+            .assert_status(StatusCode::NO_CONTENT);
+        let response = server
+            .get("/api/admin/users/budi/roles")
+            .authorization(&admin)
+            .await;
+        response.assert_json(&UserRoles {
+            username: "budi".to_string(),
+            roles: vec!["editor".to_string()],
+            permissions: vec![permission("products:write")],
+        });
+
+        let response = server
+            .put("/api/admin/users/budi/roles/kasir")
+            .authorization(&admin)
+            .await;
+        response.assert_status_not_found();
+        assert_error_text(&response, "Role kasir not found");
+
+        let response = server
+            .put("/api/admin/users/dewi/roles/editor")
+            .authorization(&admin)
+            .await;
+        response.assert_status_not_found();
+        assert_error_text(&response, "User dewi not found");
+
+        // editor tidak boleh mengatur role
+        let response = server
+            .put("/api/admin/users/citra/roles/admin")
+            .authorization(bearer(&state, "budi").await)
+            .await;
+        response.assert_status(StatusCode::FORBIDDEN);
+        assert_error_text(&response, "Permission roles:write is required");
+
+        server
+            .delete("/api/admin/users/budi/roles/editor")
+            .authorization(&admin)
+            .await
+            .assert_status(StatusCode::NO_CONTENT);
+        server
+            .delete("/api/admin/users/budi/roles/editor")
+            .authorization(&admin)
+            .await
+            .assert_status_not_found();
+        assert!(user_roles(&state, "budi").await.unwrap().roles.is_empty());
+
+        server
+            .get("/api/admin/roles")
+            .await
+            .assert_status(StatusCode::UNAUTHORIZED);
+    }
+
+    // roles:read cukup untuk melihat, roles:write untuk mengubah, "*" untuk memberi admin
+    #[tokio::test]
+    async fn test_role_permissions() {
+        let (server, state) = server();
+        state.roles.assign("budi", "auditor").await.unwrap();
+        state.roles.assign("citra", "manager").await.unwrap();
+        let auditor = bearer(&state, "budi").await;
+        let manager = bearer(&state, "citra").await;
+
+        server
+            .get("/api/admin/roles")
+            .authorization(&auditor)
+            .await
+            .assert_status_ok();
+        server
+            .get("/api/admin/users/citra/roles")
+            .authorization(&auditor)
+            .await
+            .assert_status_ok();
+        let response = server
+            .put("/api/admin/users/citra/roles/editor")
+            .authorization(&auditor)
+            .await;
+        response.assert_status(StatusCode::FORBIDDEN);
+        assert_error_text(&response, "Permission roles:write is required");
+
+        let response = server.get("/api/admin/roles").authorization(&manager).await;
+        response.assert_status(StatusCode::FORBIDDEN);
+        assert_error_text(&response, "Permission roles:read is required");
+        server
+            .put("/api/admin/users/budi/roles/editor")
+            .authorization(&manager)
+            .await
+            .assert_status(StatusCode::NO_CONTENT);
+
+        // manager tidak bisa memberi role dengan permission yang tidak ia miliki,
+        // termasuk menjadikan dirinya admin
+        let response = server
+            .put("/api/admin/users/citra/roles/admin")
+            .authorization(&manager)
+            .await;
+        response.assert_status(StatusCode::FORBIDDEN);
+        assert_error_text(&response, "Permission * is required to manage role admin");
+        let response = server
+            .put("/api/admin/users/citra/roles/auditor")
+            .authorization(&manager)
+            .await;
+        response.assert_status(StatusCode::FORBIDDEN);
+        assert_error_text(
+            &response,
+            "Permission roles:read is required to manage role auditor",
+        );
+        assert_eq!(
+            user_roles(&state, "citra").await.unwrap().roles,
+            vec!["manager"]
+        );
+
+        server
+            .put("/api/admin/users/citra/roles/admin")
+            .authorization(bearer(&state, "aqil").await)
+            .await
+            .assert_status(StatusCode::NO_CONTENT);
+    }
+
+    // mencabut role butuh permission yang sama dengan memberinya,
+    // admin dari rbac.admins tidak bisa dicabut lewat API
+    #[tokio::test]
+    async fn test_revoke_roles() {
+        let (server, state) = server();
+        state.roles.assign("budi", "manager").await.unwrap();
+        state.roles.assign("citra", ADMIN_ROLE).await.unwrap();
+        state.roles.assign("citra", "auditor").await.unwrap();
+        let manager = bearer(&state, "budi").await;
+
+        for (role, required) in [(ADMIN_ROLE, "*"), ("auditor", "roles:read")] {
+            let response = server
+                .delete(&format!("/api/admin/users/citra/roles/{}", role))
+                .authorization(&manager)
+                .await;
+            response.assert_status(StatusCode::FORBIDDEN);
+            assert_error_text(
+                &response,
+                format!(
+                    "Permission {} is required to manage role {}",
+                    required, role
+                ),
+            );
+        }
+        assert_eq!(
+            user_roles(&state, "citra").await.unwrap().roles,
+            vec![ADMIN_ROLE, "auditor"]
+        );
+
+        let response = server
+            .delete("/api/admin/users/aqil/roles/admin")
+            .authorization(bearer(&state, "citra").await)
+            .await;
+        response.assert_status(StatusCode::CONFLICT);
+        assert_error_text(
+            &response,
+            "Role admin of user aqil comes from rbac.admins and cannot be revoked",
+        );
+
+        server
+            .delete("/api/admin/users/citra/roles/admin")
+            .authorization(bearer(&state, "aqil").await)
+            .await
+            .assert_status(StatusCode::NO_CONTENT);
+    }
+
+    // products:write hanya melindungi route yang mengubah katalog
+    #[tokio::test]
+    async fn test_products_write_permission() {
+        let (server, state) = server();
+        let product = json!({"name": "TV", "price": 5000000});
+
+        server.get("/api/products").await.assert_status_ok();
+        server
+            .post("/api/products")
+            .json(&product)
+            .await
+            .assert_status(StatusCode::UNAUTHORIZED);
+
+        let response = server
+            .post("/api/products")
+            .authorization(bearer(&state, "budi").await)
+            .json(&product)
+            .await;
+        response.assert_status(StatusCode::FORBIDDEN);
+        assert_error_text(&response, "Permission products:write is required");
+
+        state.roles.assign("budi", "editor").await.unwrap();
+        server
+            .post("/api/products")
+            .authorization(bearer(&state, "budi").await)
+            .json(&product)
+            .await
+            .assert_status(StatusCode::CREATED);
+
+        // method yang tidak ada tetap 405, bukan 401
+        server
+            .patch("/api/products")
+            .await
+            .assert_status(StatusCode::METHOD_NOT_ALLOWED);
+    }
+}
@@ -0,0 +1,255 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    sync::RwLock,
+};
+
+use async_trait::async_trait;
+use sqlx::SqlitePool;
+
+use super::Permission;
+
+// Role milik setiap user dan permission role yang didefinisikan di database;
+// role dari config digabung oleh rbac::roles
+#[async_trait]
+pub trait RoleStore: Send + Sync {
+    async fn user_roles(&self, username: &str) -> anyhow::Result<Vec<String>>;
+    /// Returns false if the user already had the role.
+    async fn assign(&self, username: &str, role: &str) -> anyhow::Result<bool>;
+    /// Returns false if the user did not have the role.
+    async fn revoke(&self, username: &str, role: &str) -> anyhow::Result<bool>;
+    /// Removes every role of the user, called when the account is deleted.
+    async fn revoke_all(&self, username: &str) -> anyhow::Result<()>;
+    async fn permissions(&self, role: &str) -> anyhow::Result<Vec<Permission>>;
+    async fn roles(&self) -> anyhow::Result<BTreeMap<String, Vec<Permission>>>;
+}
+
+// Implementasi in-memory; permission role diisi lewat grant karena trait hanya membacanya
+#[derive(Debug, Default)]
+pub struct InMemoryRoleStore {
+    user_roles: RwLock<HashMap<String, BTreeSet<String>>>,
+    roles: RwLock<BTreeMap<String, BTreeSet<Permission>>>,
+}
+
+impl InMemoryRoleStore {
+    pub fn new() -> InMemoryRoleStore {
+        InMemoryRoleStore::default()
+    }
+
+    pub fn grant(&self, role: &str, permission: Permission) {
+        self.roles
+            .write()
+            .unwrap()
+            .entry(role.to_string())
+            .or_default()
+            .insert(permission);
+    }
+}
+
+#[async_trait]
+impl RoleStore for InMemoryRoleStore {
+    async fn user_roles(&self, username: &str) -> anyhow::Result<Vec<String>> {
+        let user_roles = self.user_roles.read().unwrap();
+        Ok(user_roles
+            .get(username)
+            .map(|roles| roles.iter().cloned().collect())
+            .unwrap_or_default())
+    }
+
+    async fn assign(&self, username: &str, role: &str) -> anyhow::Result<bool> {
+        let mut user_roles = self.user_roles.write().unwrap();
+        Ok(user_roles
+            .entry(username.to_string())
+            .or_default()
+            .insert(role.to_string()))
+    }
+
+    async fn revoke(&self, username: &str, role: &str) -> anyhow::Result<bool> {
+        let mut user_roles = self.user_roles.write().unwrap();
+        Ok(user_roles
+            .get_mut(username)
+            .is_some_and(|roles| roles.remove(role)))
+    }
+
+    async fn revoke_all(&self, username: &str) -> anyhow::Result<()> {
+        self.user_roles.write().unwrap().remove(username);
+        Ok(())
+    }
+
+    async fn permissions(&self, role: &str) -> anyhow::Result<Vec<Permission>> {
+        let roles = self.roles.read().unwrap();
+        Ok(roles
+            .get(role)
+            .map(|permissions| permissions.iter().cloned().collect())
+            .unwrap_or_default())
+    }
+
+    async fn roles(&self) -> anyhow::Result<BTreeMap<String, Vec<Permission>>> {
+        let roles = self.roles.read().unwrap();
+        Ok(roles
+            .iter()
+            .map(|(role, permissions)| (role.clone(), permissions.iter().cloned().collect()))
+            .collect())
+    }
+}
+
+// Implementasi SQLite, tabel user_roles dan role_permissions dibuat oleh migration di migrations/sqlite
+#[derive(Debug, Clone)]
+pub struct SqliteRoleStore {
+    pool: SqlitePool,
+}
+
+impl SqliteRoleStore {
+    pub fn new(pool: SqlitePool) -> SqliteRoleStore {
+        SqliteRoleStore { pool }
+    }
+
+    pub async fn grant(&self, role: &str, permission: &Permission) -> anyhow::Result<()> {
+        sqlx::query(
+            "INSERT INTO role_permissions (role, permission) VALUES (?, ?) \
+             ON CONFLICT DO NOTHING",
+        )
+        .bind(role)
+        .bind(permission.as_str())
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl RoleStore for SqliteRoleStore {
+    async fn user_roles(&self, username: &str) -> anyhow::Result<Vec<String>> {
+        let rows: Vec<(String,)> =
+            sqlx::query_as("SELECT role FROM user_roles WHERE username = ? ORDER BY role")
+                .bind(username)
+                .fetch_all(&self.pool)
+                .await?;
+        Ok(rows.into_iter().map(|(role,)| role).collect())
+    }
+
+    async fn assign(&self, username: &str, role: &str) -> anyhow::Result<bool> {
+        let result = sqlx::query(
+            "INSERT INTO user_roles (username, role) VALUES (?, ?) ON CONFLICT DO NOTHING",
+        )
+        .bind(username)
+        .bind(role)
+        .execute(&self.pool)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn revoke(&self, username: &str, role: &str) -> anyhow::Result<bool> {
+        let result = sqlx::query("DELETE FROM user_roles WHERE username = ? AND role = ?")
+            .bind(username)
+            .bind(role)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn revoke_all(&self, username: &str) -> anyhow::Result<()> {
+        sqlx::query("DELETE FROM user_roles WHERE username = ?")
+            .bind(username)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn permissions(&self, role: &str) -> anyhow::Result<Vec<Permission>> {
+        let rows: Vec<(String,)> = sqlx::query_as(
+            "SELECT permission FROM role_permissions WHERE role = ? ORDER BY permission",
+        )
+        .bind(role)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(rows
+            .into_iter()
+            .map(|(permission,)| Permission::try_from(permission))
+            .collect::<Result<_, _>>()?)
+    }
+
+    async fn roles(&self) -> anyhow::Result<BTreeMap<String, Vec<Permission>>> {
+        let rows: Vec<(String, String)> = sqlx::query_as(
+            "SELECT role, permission FROM role_permissions ORDER BY role, permission",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut roles: BTreeMap<String, Vec<Permission>> = BTreeMap::new();
+        for (role, permission) in rows {
+            roles
+                .entry(role)
+                .or_default()
+                .push(Permission::try_from(permission)?);
+        }
+        Ok(roles)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::check_store_contract;
+
+    fn permission(permission: &str) -> Permission {
+        permission.parse().unwrap()
+    }
+
+    // Kontrak RoleStore: assign dan revoke melaporkan apakah ada perubahan;
+    // role "editor" sudah diberi permission products:write dan products:read
+    async fn exercise(store: &dyn RoleStore) {
+        assert!(store.user_roles("aqil").await.unwrap().is_empty());
+
+        assert!(store.assign("aqil", "editor").await.unwrap());
+        assert!(!store.assign("aqil", "editor").await.unwrap());
+        assert!(store.assign("aqil", "auditor").await.unwrap());
+        assert!(store.assign("budi", "editor").await.unwrap());
+        assert_eq!(
+            store.user_roles("aqil").await.unwrap(),
+            vec!["auditor", "editor"]
+        );
+
+        assert!(store.revoke("aqil", "auditor").await.unwrap());
+        assert!(!store.revoke("aqil", "auditor").await.unwrap());
+        assert!(!store.revoke("citra", "editor").await.unwrap());
+        assert_eq!(store.user_roles("aqil").await.unwrap(), vec!["editor"]);
+
+        store.revoke_all("aqil").await.unwrap();
+        assert!(store.user_roles("aqil").await.unwrap().is_empty());
+        assert_eq!(store.user_roles("budi").await.unwrap(), vec!["editor"]);
+
+        let editor = vec![permission("products:read"), permission("products:write")];
+        assert_eq!(store.permissions("editor").await.unwrap(), editor);
+        assert!(store.permissions("auditor").await.unwrap().is_empty());
+        assert_eq!(
+            store.roles().await.unwrap(),
+            BTreeMap::from([("editor".to_string(), editor)])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_store_contract() {
+        let in_memory = InMemoryRoleStore::new();
+        in_memory.grant("editor", permission("products:write"));
+        in_memory.grant("editor", permission("products:read"));
+
+        let sqlite = SqliteRoleStore::new(crate::db::sqlite_memory().await);
+        sqlite
+            .grant("editor", &permission("products:write"))
+            .await
+            .unwrap();
+        sqlite
+            .grant("editor", &permission("products:read"))
+            .await
+            .unwrap();
+
+        check_store_contract::<dyn RoleStore>(
+            vec![
+                ("in-memory", Box::new(in_memory)),
+                ("sqlite", Box::new(sqlite)),
+            ],
+            async |store| exercise(store).await,
+        )
+        .await;
+    }
+}
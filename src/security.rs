@@ -0,0 +1,531 @@
+use std::{
+    collections::BTreeMap,
+    fmt,
+    future::Future,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+
+use axum::{
+    extract::{FromRequestParts, Request, State},
+    middleware::Next,
+    response::Response,
+};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use http::{header, request::Parts, HeaderMap, HeaderName, HeaderValue};
+use rand::RngCore;
+use tower::{Layer, Service};
+
+use crate::{
+    config::{FrameOptions, SecurityHeadersConfig},
+    error::AppError,
+};
+
+// Directive CSP yang didukung, urutannya menjadi urutan di header
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Directive {
+    DefaultSrc,
+    ScriptSrc,
+    StyleSrc,
+    ImgSrc,
+    ConnectSrc,
+    FontSrc,
+    MediaSrc,
+    ObjectSrc,
+    FrameSrc,
+    WorkerSrc,
+    ManifestSrc,
+    FrameAncestors,
+    BaseUri,
+    FormAction,
+}
+
+impl Directive {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Directive::DefaultSrc => "default-src",
+            Directive::ScriptSrc => "script-src",
+            Directive::StyleSrc => "style-src",
+            Directive::ImgSrc => "img-src",
+            Directive::ConnectSrc => "connect-src",
+            Directive::FontSrc => "font-src",
+            Directive::MediaSrc => "media-src",
+            Directive::ObjectSrc => "object-src",
+            Directive::FrameSrc => "frame-src",
+            Directive::WorkerSrc => "worker-src",
+            Directive::ManifestSrc => "manifest-src",
+            Directive::FrameAncestors => "frame-ancestors",
+            Directive::BaseUri => "base-uri",
+            Directive::FormAction => "form-action",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Source {
+    None,
+    SelfOrigin,
+    UnsafeInline,
+    UnsafeEval,
+    StrictDynamic,
+    // 'nonce-...' diisi nonce per request, sama dengan yang didapat handler lewat CspNonce
+    Nonce,
+    // contoh: "data:" atau "https:"
+    Scheme(String),
+    // contoh: "https://cdn.example.com" atau "*.example.com"
+    Host(String),
+}
+
+// nilai source tidak boleh memecah header menjadi directive lain
+fn valid_source(value: &str) -> bool {
+    !value.is_empty()
+        && value
+            .bytes()
+            .all(|byte| byte.is_ascii_graphic() && !b";,'".contains(&byte))
+}
+
+impl Source {
+    /// Panics if `scheme` contains whitespace, `;`, `,` or quotes.
+    pub fn scheme(scheme: &str) -> Source {
+        let scheme = scheme.trim_end_matches(':');
+        assert!(valid_source(scheme), "invalid CSP scheme {:?}", scheme);
+        Source::Scheme(format!("{}:", scheme))
+    }
+
+    /// Panics if `host` contains whitespace, `;`, `,` or quotes.
+    pub fn host(host: &str) -> Source {
+        assert!(valid_source(host), "invalid CSP host {:?}", host);
+        Source::Host(host.to_string())
+    }
+
+    fn write(&self, out: &mut String, nonce: &CspNonce) {
+        match self {
+            Source::None => out.push_str("'none'"),
+            Source::SelfOrigin => out.push_str("'self'"),
+            Source::UnsafeInline => out.push_str("'unsafe-inline'"),
+            Source::UnsafeEval => out.push_str("'unsafe-eval'"),
+            Source::StrictDynamic => out.push_str("'strict-dynamic'"),
+            Source::Nonce => {
+                out.push_str("'nonce-");
+                out.push_str(nonce.as_str());
+                out.push('\'');
+            }
+            Source::Scheme(value) | Source::Host(value) => out.push_str(value),
+        }
+    }
+}
+
+/// Typed Content-Security-Policy builder; an empty policy sends no header.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ContentSecurityPolicy {
+    directives: BTreeMap<Directive, Vec<Source>>,
+    upgrade_insecure_requests: bool,
+}
+
+impl ContentSecurityPolicy {
+    /// Policy for JSON responses: nothing may be loaded and the response may not be framed.
+    pub fn strict() -> ContentSecurityPolicy {
+        ContentSecurityPolicy::default()
+            .directive(Directive::DefaultSrc, [Source::None])
+            .directive(Directive::FrameAncestors, [Source::None])
+            .directive(Directive::BaseUri, [Source::None])
+            .directive(Directive::FormAction, [Source::None])
+    }
+
+    /// Sets the sources of `directive`, replacing any set earlier.
+    pub fn directive(
+        mut self,
+        directive: Directive,
+        sources: impl IntoIterator<Item = Source>,
+    ) -> ContentSecurityPolicy {
+        self.directives
+            .insert(directive, sources.into_iter().collect());
+        self
+    }
+
+    pub fn upgrade_insecure_requests(mut self) -> ContentSecurityPolicy {
+        self.upgrade_insecure_requests = true;
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.directives.is_empty() && !self.upgrade_insecure_requests
+    }
+
+    pub fn render(&self, nonce: &CspNonce) -> String {
+        let mut out = String::new();
+        for (directive, sources) in &self.directives {
+            if !out.is_empty() {
+                out.push_str("; ");
+            }
+            out.push_str(directive.as_str());
+            // directive tanpa source sama dengan 'none'
+            if sources.is_empty() {
+                out.push_str(" 'none'");
+            }
+            for source in sources {
+                out.push(' ');
+                source.write(&mut out, nonce);
+            }
+        }
+        if self.upgrade_insecure_requests {
+            if !out.is_empty() {
+                out.push_str("; ");
+            }
+            out.push_str("upgrade-insecure-requests");
+        }
+        out
+    }
+}
+
+// Nonce acak per request untuk <script nonce="..."> dan <style nonce="...">
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CspNonce(String);
+
+impl CspNonce {
+    pub fn generate() -> CspNonce {
+        let mut bytes = [0u8; 16];
+        rand::thread_rng().fill_bytes(&mut bytes);
+        CspNonce(URL_SAFE_NO_PAD.encode(bytes))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for CspNonce {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl<S> FromRequestParts<S> for CspNonce
+where
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        parts
+            .extensions
+            .get::<CspNonce>()
+            .cloned()
+            .ok_or_else(|| anyhow::anyhow!("security_headers_middleware is not installed").into())
+    }
+}
+
+/// Header values built once from [`SecurityHeadersConfig`] and applied by
+/// [`security_headers_middleware`]. Headers already set by a handler are kept.
+#[derive(Debug, Clone)]
+pub struct SecurityHeaders {
+    headers: Vec<(HeaderName, HeaderValue)>,
+    csp: ContentSecurityPolicy,
+}
+
+impl SecurityHeaders {
+    /// Returns `None` when disabled; the default policy is [`ContentSecurityPolicy::strict`].
+    pub fn from_config(config: &SecurityHeadersConfig) -> Option<SecurityHeaders> {
+        if !config.enabled {
+            return None;
+        }
+
+        let mut headers = vec![(
+            header::X_CONTENT_TYPE_OPTIONS,
+            HeaderValue::from_static("nosniff"),
+        )];
+        if config.hsts_max_age > 0 {
+            let mut hsts = format!("max-age={}", config.hsts_max_age);
+            if config.hsts_include_subdomains {
+                hsts.push_str("; includeSubDomains");
+            }
+            if config.hsts_preload {
+                hsts.push_str("; preload");
+            }
+            headers.push((
+                header::STRICT_TRANSPORT_SECURITY,
+                HeaderValue::try_from(hsts).unwrap(),
+            ));
+        }
+        let frame_options = match config.frame_options {
+            FrameOptions::Deny => "DENY",
+            FrameOptions::SameOrigin => "SAMEORIGIN",
+        };
+        headers.push((
+            header::X_FRAME_OPTIONS,
+            HeaderValue::from_static(frame_options),
+        ));
+
+        // nilai kosong berarti header tidak dikirim, selain itu sudah divalidasi oleh Config::validate
+        let optional = [
+            (header::REFERRER_POLICY, &config.referrer_policy),
+            (
+                HeaderName::from_static("permissions-policy"),
+                &config.permissions_policy,
+            ),
+        ];
+        for (name, value) in optional {
+            if !value.is_empty() {
+                headers.push((
+                    name,
+                    HeaderValue::try_from(value.as_str()).expect("invalid header value"),
+                ));
+            }
+        }
+
+        Some(SecurityHeaders {
+            headers,
+            csp: ContentSecurityPolicy::strict(),
+        })
+    }
+
+    /// Replaces the policy used for routes without a [`content_security_policy`] layer.
+    pub fn content_security_policy(mut self, policy: ContentSecurityPolicy) -> SecurityHeaders {
+        self.csp = policy;
+        self
+    }
+
+    fn apply(&self, headers: &mut HeaderMap, policy: &ContentSecurityPolicy, nonce: &CspNonce) {
+        for (name, value) in &self.headers {
+            if !headers.contains_key(name) {
+                headers.insert(name.clone(), value.clone());
+            }
+        }
+
+        if policy.is_empty() || headers.contains_key(header::CONTENT_SECURITY_POLICY) {
+            return;
+        }
+        // source sudah divalidasi saat policy dibuat
+        if let Ok(csp) = HeaderValue::try_from(policy.render(nonce)) {
+            headers.insert(header::CONTENT_SECURITY_POLICY, csp);
+        }
+    }
+}
+
+// Middleware: nonce dibuat sebelum handler agar bisa diambil lewat extractor CspNonce,
+// header dipasang setelah handler sehingga policy per route ikut terbaca
+pub async fn security_headers_middleware(
+    State(security): State<Arc<SecurityHeaders>>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    let nonce = CspNonce::generate();
+    request.extensions_mut().insert(nonce.clone());
+
+    let mut response = next.run(request).await;
+    let route_policy = response.extensions_mut().remove::<RoutePolicy>();
+    let policy = route_policy
+        .as_ref()
+        .map_or(&security.csp, |RoutePolicy(policy)| policy);
+    security.apply(response.headers_mut(), policy, &nonce);
+    response
+}
+
+// Policy per route, dibawa lewat extension response sampai ke security_headers_middleware
+#[derive(Debug, Clone)]
+struct RoutePolicy(Arc<ContentSecurityPolicy>);
+
+/// Layer that replaces the default Content-Security-Policy for the routes it wraps.
+///
+/// Attach it with `route_layer` or on a single method router; the headers are still written
+/// by [`security_headers_middleware`], which must be installed around the router.
+pub fn content_security_policy(policy: ContentSecurityPolicy) -> ContentSecurityPolicyLayer {
+    ContentSecurityPolicyLayer {
+        policy: Arc::new(policy),
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ContentSecurityPolicyLayer {
+    policy: Arc<ContentSecurityPolicy>,
+}
+
+impl<S> Layer<S> for ContentSecurityPolicyLayer {
+    type Service = ContentSecurityPolicyService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        ContentSecurityPolicyService {
+            inner,
+            policy: self.policy.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ContentSecurityPolicyService<S> {
+    inner: S,
+    policy: Arc<ContentSecurityPolicy>,
+}
+
+impl<S> Service<Request> for ContentSecurityPolicyService<S>
+where
+    S: Service<Request, Response = Response>,
+    S::Future: Send + 'static,
+{
+    type Response = Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Response, S::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request) -> Self::Future {
+        let future = self.inner.call(request);
+        let policy = RoutePolicy(self.policy.clone());
+
+        Box::pin(async move {
+            let mut response = future.await?;
+            response.extensions_mut().insert(policy);
+            Ok(response)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{middleware::from_fn_with_state, response::Html, routing::get, Router};
+    use axum_test::TestServer;
+
+    use super::*;
+    use crate::{build_app, AppState, Config};
+
+    #[test]
+    fn test_csp_builder() {
+        let nonce = CspNonce("abc".to_string());
+        assert_eq!(
+            ContentSecurityPolicy::strict().render(&nonce),
+            "default-src 'none'; frame-ancestors 'none'; base-uri 'none'; form-action 'none'"
+        );
+
+        let policy = ContentSecurityPolicy::default()
+            .directive(Directive::ScriptSrc, [Source::SelfOrigin, Source::Nonce])
+            .directive(Directive::DefaultSrc, [Source::SelfOrigin])
+            .directive(
+                Directive::ImgSrc,
+                [
+                    Source::SelfOrigin,
+                    Source::scheme("data"),
+                    Source::host("https://cdn.example.com"),
+                ],
+            )
+            .directive(Directive::ObjectSrc, [])
+            .upgrade_insecure_requests();
+        assert_eq!(
+            policy.render(&nonce),
+            "default-src 'self'; script-src 'self' 'nonce-abc'; \
+             img-src 'self' data: https://cdn.example.com; object-src 'none'; \
+             upgrade-insecure-requests"
+        );
+
+        assert!(ContentSecurityPolicy::default().is_empty());
+        assert_eq!(ContentSecurityPolicy::default().render(&nonce), "");
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid CSP host")]
+    fn test_invalid_source() {
+        Source::host("example.com; script-src *");
+    }
+
+    fn server() -> TestServer {
+        async fn page(nonce: CspNonce) -> Html<String> {
+            Html(format!("<script nonce=\"{}\"></script>", nonce))
+        }
+
+        let page_policy = ContentSecurityPolicy::default()
+            .directive(Directive::DefaultSrc, [Source::SelfOrigin])
+            .directive(Directive::ScriptSrc, [Source::SelfOrigin, Source::Nonce]);
+        let security = SecurityHeaders::from_config(&SecurityHeadersConfig::default()).unwrap();
+
+        let app = Router::new()
+            .route("/page", get(page))
+            .route_layer(content_security_policy(page_policy))
+            .route("/", get(|| async { "Hello" }))
+            .route(
+                "/framed",
+                get(|| async { ([("X-Frame-Options", "SAMEORIGIN")], "Framed") }),
+            )
+            .layer(from_fn_with_state(
+                Arc::new(security),
+                security_headers_middleware,
+            ));
+        TestServer::new(app).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_security_headers() {
+        let server = server();
+
+        let response = server.get("/").await;
+        response.assert_status_ok();
+        response.assert_header(
+            "Strict-Transport-Security",
+            "max-age=31536000; includeSubDomains",
+        );
+        response.assert_header("X-Content-Type-Options", "nosniff");
+        response.assert_header("X-Frame-Options", "DENY");
+        response.assert_header("Referrer-Policy", "strict-origin-when-cross-origin");
+        response.assert_header(
+            "Permissions-Policy",
+            "camera=(), microphone=(), geolocation=(), payment=()",
+        );
+        response.assert_header(
+            "Content-Security-Policy",
+            "default-src 'none'; frame-ancestors 'none'; base-uri 'none'; form-action 'none'",
+        );
+
+        // header dari handler tidak ditimpa
+        let response = server.get("/framed").await;
+        response.assert_header("X-Frame-Options", "SAMEORIGIN");
+
+        // 404 juga mendapat header
+        let response = server.get("/missing").await;
+        response.assert_status_not_found();
+        response.assert_header("X-Content-Type-Options", "nosniff");
+    }
+
+    // Policy per route memakai nonce yang sama dengan yang diterima handler
+    #[tokio::test]
+    async fn test_route_policy_nonce() {
+        let server = server();
+
+        let mut nonces = Vec::new();
+        for _ in 0..2 {
+            let response = server.get("/page").await;
+            response.assert_status_ok();
+            let body = response.text();
+            let nonce = body
+                .strip_prefix("<script nonce=\"")
+                .and_then(|rest| rest.strip_suffix("\"></script>"))
+                .unwrap()
+                .to_string();
+            response.assert_header(
+                "Content-Security-Policy",
+                format!("default-src 'self'; script-src 'self' 'nonce-{}'", nonce),
+            );
+            nonces.push(nonce);
+        }
+        assert_ne!(nonces[0], nonces[1]);
+    }
+
+    #[tokio::test]
+    async fn test_build_app() {
+        let server = TestServer::new(build_app(AppState::new(Config::default()).unwrap())).unwrap();
+        let response = server.get("/api/products").await;
+        response.assert_status_ok();
+        response.assert_header("X-Frame-Options", "DENY");
+        response.assert_header(
+            "Content-Security-Policy",
+            "default-src 'none'; frame-ancestors 'none'; base-uri 'none'; form-action 'none'",
+        );
+
+        let mut config = Config::default();
+        config.security_headers.enabled = false;
+        let server = TestServer::new(build_app(AppState::new(config).unwrap())).unwrap();
+        let response = server.get("/api/products").await;
+        assert!(response.maybe_header("X-Content-Type-Options").is_none());
+        assert!(response.maybe_header("Content-Security-Policy").is_none());
+    }
+}
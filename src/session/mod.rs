@@ -0,0 +1,396 @@
+pub mod store;
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use axum::{
+    extract::{FromRequestParts, Request, State},
+    middleware::Next,
+    response::Response,
+};
+use axum_extra::extract::{cookie::Cookie, CookieJar};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use http::{header, request::Parts, HeaderValue};
+use rand::{rngs::OsRng, RngCore};
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::{config::SessionConfig, cookies::CookieKeys, error::AppError, state::AppState};
+
+pub use self::store::{spawn_purge, InMemorySessionStore, SessionStore, SqliteSessionStore};
+
+pub type SessionData = HashMap<String, serde_json::Value>;
+
+// Key untuk username yang sedang login
+pub const SESSION_USER: &str = "username";
+
+// Key untuk User::token_version saat login, dicek oleh AuthUser
+pub const SESSION_TOKEN_VERSION: &str = "token_version";
+
+// Session untuk request saat ini; perubahan disimpan oleh session_middleware
+// setelah handler selesai
+#[derive(Debug, Clone)]
+pub struct Session(Arc<Mutex<Inner>>);
+
+#[derive(Debug, Default)]
+struct Inner {
+    id: Option<String>,
+    data: SessionData,
+    changed: bool,
+    // id lama yang harus dihapus dari store (rotate / destroy)
+    stale: Vec<String>,
+}
+
+impl Session {
+    fn new(id: Option<String>, data: SessionData) -> Session {
+        Session(Arc::new(Mutex::new(Inner {
+            id,
+            data,
+            ..Inner::default()
+        })))
+    }
+
+    /// The current session ID, `None` until the session is first saved.
+    pub fn id(&self) -> Option<String> {
+        self.0.lock().unwrap().id.clone()
+    }
+
+    /// Returns `None` if the key is missing or holds a value of another type.
+    pub fn get<T: DeserializeOwned>(&self, key: &str) -> Option<T> {
+        let inner = self.0.lock().unwrap();
+        let value = inner.data.get(key)?.clone();
+        serde_json::from_value(value).ok()
+    }
+
+    pub fn insert<T: Serialize>(&self, key: &str, value: T) -> Result<(), AppError> {
+        let value = serde_json::to_value(value).map_err(anyhow::Error::from)?;
+        let mut inner = self.0.lock().unwrap();
+        inner.data.insert(key.to_string(), value);
+        inner.changed = true;
+        Ok(())
+    }
+
+    pub fn remove(&self, key: &str) {
+        let mut inner = self.0.lock().unwrap();
+        if inner.data.remove(key).is_some() {
+            inner.changed = true;
+        }
+    }
+
+    /// Keeps the data but moves it to a fresh ID; call after a privilege change such as login.
+    pub fn rotate(&self) {
+        let mut inner = self.0.lock().unwrap();
+        if let Some(id) = inner.id.take() {
+            inner.stale.push(id);
+        }
+        inner.changed = true;
+    }
+
+    /// Clears the data and removes the session from the store.
+    pub fn destroy(&self) {
+        let mut inner = self.0.lock().unwrap();
+        if let Some(id) = inner.id.take() {
+            inner.stale.push(id);
+        }
+        inner.data.clear();
+        inner.changed = true;
+    }
+}
+
+impl<S> FromRequestParts<S> for Session
+where
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        parts
+            .extensions
+            .get::<Session>()
+            .cloned()
+            .ok_or_else(|| anyhow::anyhow!("session_middleware is not installed").into())
+    }
+}
+
+// 256 bit acak, tidak bisa ditebak dan tidak berisi data apa pun
+fn new_session_id() -> String {
+    let mut bytes = [0u8; 32];
+    OsRng.fill_bytes(&mut bytes);
+    URL_SAFE_NO_PAD.encode(bytes)
+}
+
+// id session ditandatangani dengan cookie key, id yang dikarang client tidak pernah dimuat dari store
+fn session_cookie(
+    config: &SessionConfig,
+    keys: &CookieKeys,
+    value: String,
+    max_age: u64,
+) -> HeaderValue {
+    let cookie = Cookie::build((config.cookie_name.clone(), value))
+        .path("/")
+        .http_only(true)
+        .secure(config.secure)
+        .same_site(config.same_site.into())
+        .max_age(cookie::time::Duration::seconds(
+            i64::try_from(max_age).unwrap_or(i64::MAX),
+        ))
+        .build();
+
+    // nama cookie sudah divalidasi, id dan tanda tangan hanya berisi karakter base64
+    HeaderValue::from_str(&keys.sign(cookie).to_string()).unwrap()
+}
+
+// Middleware: muat session dari cookie, simpan perubahan setelah handler selesai
+pub async fn session_middleware(
+    State(state): State<AppState>,
+    mut request: Request,
+    next: Next,
+) -> Result<Response, AppError> {
+    let config = &state.config.session;
+    let jar = CookieJar::from_headers(request.headers());
+    let cookie = jar.get(&config.cookie_name).cloned();
+    let had_cookie = cookie.is_some();
+
+    // cookie dengan tanda tangan salah diperlakukan seperti tidak ada session
+    let id = cookie
+        .and_then(|cookie| state.cookie_keys.verify(cookie))
+        .map(|cookie| cookie.value().to_string());
+    let session = match id {
+        Some(id) => match state.sessions.load(&id).await? {
+            Some(data) => Session::new(Some(id), data),
+            None => Session::new(None, SessionData::new()),
+        },
+        None => Session::new(None, SessionData::new()),
+    };
+
+    request.extensions_mut().insert(session.clone());
+    let mut response = next.run(request).await;
+
+    let (id, data, stale) = {
+        let mut inner = session.0.lock().unwrap();
+        if !inner.changed {
+            return Ok(response);
+        }
+        (
+            inner.id.take(),
+            inner.data.clone(),
+            std::mem::take(&mut inner.stale),
+        )
+    };
+
+    for id in &stale {
+        state.sessions.delete(id).await?;
+    }
+
+    if data.is_empty() {
+        // session kosong tidak disimpan, cookie di browser dihapus
+        if let Some(id) = id {
+            state.sessions.delete(&id).await?;
+        }
+        if had_cookie {
+            let cookie = session_cookie(config, &state.cookie_keys, String::new(), 0);
+            response.headers_mut().append(header::SET_COOKIE, cookie);
+        }
+    } else {
+        let id = id.unwrap_or_else(new_session_id);
+        state.sessions.save(&id, &data, config.ttl()).await?;
+        let cookie = session_cookie(config, &state.cookie_keys, id, config.ttl);
+        response.headers_mut().append(header::SET_COOKIE, cookie);
+    }
+
+    Ok(response)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use axum::{middleware::from_fn_with_state, routing::get, Router};
+    use axum_test::{TestResponse, TestServer};
+    use http::StatusCode;
+    use serde::Deserialize;
+    use serde_json::json;
+
+    use super::*;
+    use crate::{auth::store::User, build_app, test_support::TestApp};
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    struct Cart {
+        items: Vec<String>,
+    }
+
+    fn server() -> (TestServer, AppState, Arc<InMemorySessionStore>) {
+        async fn add(session: Session) -> Result<String, AppError> {
+            let mut cart: Cart = session.get("cart").unwrap_or(Cart { items: vec![] });
+            cart.items.push("Buku".to_string());
+            session.insert("cart", &cart)?;
+            Ok(format!("Items {}", cart.items.len()))
+        }
+
+        async fn clear(session: Session) -> &'static str {
+            session.remove("cart");
+            "Cleared"
+        }
+
+        let sessions = Arc::new(InMemorySessionStore::new());
+        let state = TestApp::new()
+            .user(User::new("aqil", "rahasia").unwrap())
+            .state()
+            .with_sessions(sessions.clone());
+
+        let app = Router::new()
+            .route("/cart", get(add).delete(clear))
+            .layer(from_fn_with_state(state.clone(), session_middleware))
+            .with_state(state.clone())
+            .merge(build_app(state.clone()));
+
+        (TestServer::new(app).unwrap(), state, sessions)
+    }
+
+    fn session_id(response: &TestResponse) -> String {
+        let cookie = response.header("Set-Cookie");
+        let cookie = Cookie::parse(cookie.to_str().unwrap().to_string()).unwrap();
+        cookie.value().to_string()
+    }
+
+    // id di store, tanpa tanda tangan yang ada di nilai cookie
+    fn stored_id(state: &AppState, cookie: &str) -> String {
+        let cookie = Cookie::new("session_id", cookie.to_string());
+        state
+            .cookie_keys
+            .verify(cookie)
+            .unwrap()
+            .value()
+            .to_string()
+    }
+
+    fn with_session(id: &str) -> (http::HeaderName, HeaderValue) {
+        (
+            header::COOKIE,
+            HeaderValue::from_str(&format!("session_id={}", id)).unwrap(),
+        )
+    }
+
+    // Nilai bertipe disimpan di server, cookie hanya berisi id
+    #[tokio::test]
+    async fn test_session_values() {
+        let (server, state, sessions) = server();
+
+        let response = server.get("/cart").await;
+        response.assert_text("Items 1");
+        let cookie = response.header("Set-Cookie");
+        let cookie = cookie.to_str().unwrap();
+        assert!(cookie.contains("HttpOnly"));
+        assert!(cookie.contains("Secure"));
+        assert!(cookie.contains("SameSite=Lax"));
+        assert!(cookie.contains("Path=/"));
+        let id = session_id(&response);
+        let stored = stored_id(&state, &id);
+        assert_eq!(stored.len(), 43);
+        assert!(id.ends_with(&stored));
+
+        let (name, value) = with_session(&id);
+        let response = server
+            .get("/cart")
+            .add_header(name.clone(), value.clone())
+            .await;
+        response.assert_text("Items 2");
+        assert_eq!(session_id(&response), id);
+
+        // request tanpa perubahan tidak menulis cookie
+        let response = server
+            .get("/")
+            .add_header(name.clone(), value.clone())
+            .await;
+        assert!(response.maybe_header("Set-Cookie").is_none());
+
+        // session kosong dihapus
+        let response = server.delete("/cart").add_header(name, value).await;
+        assert!(response
+            .header("Set-Cookie")
+            .to_str()
+            .unwrap()
+            .contains("Max-Age=0"));
+        assert!(sessions.is_empty());
+
+        // id yang tidak dikenal mulai dari session baru
+        let (name, value) = with_session("palsu");
+        let response = server.get("/cart").add_header(name, value).await;
+        response.assert_text("Items 1");
+        assert_ne!(session_id(&response), "palsu");
+    }
+
+    // Cookie tanpa tanda tangan yang valid tidak bisa memakai session orang lain
+    #[tokio::test]
+    async fn test_session_cookie_signed() {
+        let (server, state, _) = server();
+        let id = session_id(&server.get("/cart").await);
+        let stored = stored_id(&state, &id);
+
+        let (name, value) = with_session(&stored);
+        let response = server.get("/cart").add_header(name, value).await;
+        response.assert_text("Items 1");
+
+        let signature = &id[..id.len() - stored.len()];
+        let tampered = format!("{}AAAA{}", signature, &stored[4..]);
+        let (name, value) = with_session(&tampered);
+        let response = server.get("/cart").add_header(name, value).await;
+        response.assert_text("Items 1");
+
+        let (name, value) = with_session(&id);
+        let response = server.get("/cart").add_header(name, value).await;
+        response.assert_text("Items 2");
+    }
+
+    // Login merotasi id session, logout menghapusnya
+    #[tokio::test]
+    async fn test_session_login_logout() {
+        let (server, state, sessions) = server();
+
+        let response = server.get("/cart").await;
+        let anonymous = session_id(&response);
+
+        let (name, value) = with_session(&anonymous);
+        let response = server
+            .post("/api/auth/login")
+            .add_header(name, value)
+            .json(&json!({"username": "aqil", "password": "rahasia"}))
+            .await;
+        response.assert_status_ok();
+        let id = session_id(&response);
+        assert_ne!(id, anonymous);
+        let stored = stored_id(&state, &id);
+        let anonymous = stored_id(&state, &anonymous);
+        assert!(sessions.load(&anonymous).await.unwrap().is_none());
+
+        // data sebelum login tetap ada setelah rotasi
+        let data = sessions.load(&stored).await.unwrap().unwrap();
+        assert_eq!(data[SESSION_USER], json!("aqil"));
+        assert_eq!(data["cart"], json!({"items": ["Buku"]}));
+
+        // session login bisa dipakai sebagai pengganti token
+        let (name, value) = with_session(&id);
+        let response = server
+            .get("/api/users/me")
+            .add_header(name.clone(), value.clone())
+            .await;
+        response.assert_status_ok();
+        response.assert_text_contains("\"username\":\"aqil\"");
+
+        let response = server
+            .post("/api/auth/logout")
+            .add_header(name.clone(), value.clone())
+            .await;
+        response.assert_status(StatusCode::NO_CONTENT);
+        assert!(response
+            .header("Set-Cookie")
+            .to_str()
+            .unwrap()
+            .contains("Max-Age=0"));
+        assert!(sessions.load(&stored).await.unwrap().is_none());
+
+        let response = server.get("/api/users/me").add_header(name, value).await;
+        response.assert_status(StatusCode::UNAUTHORIZED);
+    }
+}
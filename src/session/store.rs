@@ -0,0 +1,272 @@
+use std::{
+    collections::HashMap,
+    future::Future,
+    sync::{Arc, RwLock},
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use sqlx::SqlitePool;
+use tokio::{
+    task::JoinHandle,
+    time::{Instant, MissedTickBehavior},
+};
+
+use super::SessionData;
+use crate::auth::token::now;
+
+// Penyimpanan data session, kedaluwarsa diatur oleh store
+#[async_trait]
+pub trait SessionStore: Send + Sync {
+    /// Returns the session data, or `None` if the session is unknown or expired.
+    async fn load(&self, id: &str) -> anyhow::Result<Option<SessionData>>;
+    async fn save(&self, id: &str, data: &SessionData, ttl: Duration) -> anyhow::Result<()>;
+    async fn delete(&self, id: &str) -> anyhow::Result<()>;
+    /// Removes expired sessions and returns how many were deleted.
+    async fn delete_expired(&self) -> anyhow::Result<u64>;
+}
+
+/// Calls [`SessionStore::delete_expired`] every `interval` until `shutdown` completes.
+pub fn spawn_purge<F>(
+    store: Arc<dyn SessionStore>,
+    interval: Duration,
+    shutdown: F,
+) -> JoinHandle<()>
+where
+    F: Future<Output = ()> + Send + 'static,
+{
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval_at(Instant::now() + interval, interval);
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        tokio::pin!(shutdown);
+
+        loop {
+            tokio::select! {
+                _ = &mut shutdown => break,
+                _ = ticker.tick() => {}
+            }
+            match store.delete_expired().await {
+                Ok(deleted) => tracing::debug!(deleted, "expired sessions purged"),
+                Err(error) => tracing::warn!(%error, "failed to purge expired sessions"),
+            }
+        }
+    })
+}
+
+// Implementasi in-memory; session kedaluwarsa tetap ada di map sampai delete_expired
+#[derive(Debug, Default)]
+pub struct InMemorySessionStore {
+    sessions: RwLock<HashMap<String, (SessionData, Instant)>>,
+}
+
+impl InMemorySessionStore {
+    pub fn new() -> InMemorySessionStore {
+        InMemorySessionStore::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.sessions.read().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[async_trait]
+impl SessionStore for InMemorySessionStore {
+    async fn load(&self, id: &str) -> anyhow::Result<Option<SessionData>> {
+        let sessions = self.sessions.read().unwrap();
+        Ok(sessions
+            .get(id)
+            .filter(|(_, expires_at)| *expires_at > Instant::now())
+            .map(|(data, _)| data.clone()))
+    }
+
+    async fn save(&self, id: &str, data: &SessionData, ttl: Duration) -> anyhow::Result<()> {
+        let expires_at = Instant::now()
+            .checked_add(ttl)
+            .ok_or_else(|| anyhow::anyhow!("session ttl {:?} is too large", ttl))?;
+        self.sessions
+            .write()
+            .unwrap()
+            .insert(id.to_string(), (data.clone(), expires_at));
+        Ok(())
+    }
+
+    async fn delete(&self, id: &str) -> anyhow::Result<()> {
+        self.sessions.write().unwrap().remove(id);
+        Ok(())
+    }
+
+    async fn delete_expired(&self) -> anyhow::Result<u64> {
+        let now = Instant::now();
+        let mut sessions = self.sessions.write().unwrap();
+        let before = sessions.len();
+        sessions.retain(|_, (_, expires_at)| *expires_at > now);
+        Ok((before - sessions.len()) as u64)
+    }
+}
+
+// Implementasi SQLite, tabel sessions dibuat oleh migration di migrations/sqlite
+#[derive(Debug, Clone)]
+pub struct SqliteSessionStore {
+    pool: SqlitePool,
+}
+
+impl SqliteSessionStore {
+    pub fn new(pool: SqlitePool) -> SqliteSessionStore {
+        SqliteSessionStore { pool }
+    }
+}
+
+// expires_at disimpan sebagai detik unix INTEGER; nilai di luar i64 ditolak, bukan wrap ke negatif
+fn timestamp(seconds: u64) -> anyhow::Result<i64> {
+    Ok(i64::try_from(seconds)?)
+}
+
+#[async_trait]
+impl SessionStore for SqliteSessionStore {
+    async fn load(&self, id: &str) -> anyhow::Result<Option<SessionData>> {
+        let row: Option<(String,)> =
+            sqlx::query_as("SELECT data FROM sessions WHERE id = ? AND expires_at > ?")
+                .bind(id)
+                .bind(timestamp(now())?)
+                .fetch_optional(&self.pool)
+                .await?;
+
+        match row {
+            Some((data,)) => Ok(Some(serde_json::from_str(&data)?)),
+            None => Ok(None),
+        }
+    }
+
+    async fn save(&self, id: &str, data: &SessionData, ttl: Duration) -> anyhow::Result<()> {
+        sqlx::query(
+            "INSERT INTO sessions (id, data, expires_at) VALUES (?, ?, ?) \
+             ON CONFLICT (id) DO UPDATE SET data = excluded.data, expires_at = excluded.expires_at",
+        )
+        .bind(id)
+        .bind(serde_json::to_string(data)?)
+        .bind(timestamp(now().saturating_add(ttl.as_secs()))?)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn delete(&self, id: &str) -> anyhow::Result<()> {
+        sqlx::query("DELETE FROM sessions WHERE id = ?")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn delete_expired(&self) -> anyhow::Result<u64> {
+        let result = sqlx::query("DELETE FROM sessions WHERE expires_at <= ?")
+            .bind(timestamp(now())?)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::test_support::check_store_contract;
+
+    fn data(username: &str) -> SessionData {
+        SessionData::from([("username".to_string(), json!(username))])
+    }
+
+    // Kontrak SessionStore: save menimpa data lama, TTL nol langsung kedaluwarsa
+    // dan dibuang oleh delete_expired, TTL yang tidak bisa direpresentasikan menjadi error
+    async fn exercise(store: &dyn SessionStore) {
+        let hour = Duration::from_secs(3600);
+
+        store.save("a", &data("aqil"), hour).await.unwrap();
+        assert_eq!(store.load("a").await.unwrap(), Some(data("aqil")));
+
+        store.save("a", &data("budi"), hour).await.unwrap();
+        assert_eq!(store.load("a").await.unwrap(), Some(data("budi")));
+
+        store
+            .save("b", &data("citra"), Duration::ZERO)
+            .await
+            .unwrap();
+        assert_eq!(store.load("b").await.unwrap(), None);
+        assert!(store.save("c", &data("dewi"), Duration::MAX).await.is_err());
+
+        store.delete("a").await.unwrap();
+        assert_eq!(store.load("a").await.unwrap(), None);
+        assert_eq!(store.load("tidak-ada").await.unwrap(), None);
+        assert_eq!(store.delete_expired().await.unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_store_contract() {
+        let pool = crate::db::sqlite_memory().await;
+        check_store_contract::<dyn SessionStore>(
+            vec![
+                ("in-memory", Box::new(InMemorySessionStore::new())),
+                ("sqlite", Box::new(SqliteSessionStore::new(pool))),
+            ],
+            async |store| exercise(store).await,
+        )
+        .await;
+    }
+
+    // TTL in-memory memakai jam tokio agar bisa dimajukan di test
+    #[tokio::test(start_paused = true)]
+    async fn test_in_memory_ttl() {
+        let store = InMemorySessionStore::new();
+        store
+            .save("a", &data("aqil"), Duration::from_secs(60))
+            .await
+            .unwrap();
+
+        tokio::time::advance(Duration::from_secs(59)).await;
+        assert!(store.load("a").await.unwrap().is_some());
+
+        tokio::time::advance(Duration::from_secs(1)).await;
+        assert_eq!(store.load("a").await.unwrap(), None);
+
+        store
+            .save("b", &data("budi"), Duration::from_secs(60))
+            .await
+            .unwrap();
+        assert_eq!(store.delete_expired().await.unwrap(), 1);
+        assert_eq!(store.len(), 1);
+    }
+
+    // Task pembersih berjalan tiap interval dan berhenti saat shutdown
+    #[tokio::test(start_paused = true)]
+    async fn test_spawn_purge() {
+        let store = Arc::new(InMemorySessionStore::new());
+        let (shutdown, stop) = tokio::sync::oneshot::channel::<()>();
+        let purge = spawn_purge(store.clone(), Duration::from_secs(600), async move {
+            let _ = stop.await;
+        });
+
+        store
+            .save("a", &data("aqil"), Duration::from_secs(60))
+            .await
+            .unwrap();
+        store
+            .save("b", &data("budi"), Duration::from_secs(3600))
+            .await
+            .unwrap();
+
+        tokio::time::sleep(Duration::from_secs(599)).await;
+        assert_eq!(store.len(), 2);
+        tokio::time::sleep(Duration::from_secs(1)).await;
+        tokio::task::yield_now().await;
+        assert_eq!(store.len(), 1);
+
+        shutdown.send(()).unwrap();
+        purge.await.unwrap();
+    }
+}
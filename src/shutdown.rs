@@ -0,0 +1,172 @@
+use std::{
+    future::{Future, IntoFuture},
+    io,
+    net::SocketAddr,
+    time::Duration,
+};
+
+use axum::{serve, Router};
+use tokio::{net::TcpListener, sync::watch};
+
+use crate::health::Readiness;
+
+/// Completes on SIGINT (Ctrl+C) or, on unix, SIGTERM.
+pub async fn shutdown_signal() {
+    let ctrl_c = async {
+        tokio::signal::ctrl_c()
+            .await
+            .expect("failed to install Ctrl+C handler");
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("failed to install SIGTERM handler")
+            .recv()
+            .await;
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
+}
+
+/// Serves `app` until `signal` completes, then stops accepting connections and
+/// waits at most `drain_timeout` for in-flight requests before returning.
+pub async fn serve_with_shutdown<F>(
+    listener: TcpListener,
+    app: Router,
+    readiness: Readiness,
+    drain_timeout: Duration,
+    signal: F,
+) -> io::Result<()>
+where
+    F: Future<Output = ()> + Send + 'static,
+{
+    let (draining_tx, mut draining_rx) = watch::channel(false);
+
+    // ConnectInfo dibutuhkan rate limiter untuk membaca alamat client
+    let server = serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .with_graceful_shutdown(async move {
+        signal.await;
+        readiness.set_ready(false);
+        let _ = draining_tx.send(true);
+    })
+    .into_future();
+    tokio::pin!(server);
+
+    tokio::select! {
+        result = &mut server => return result,
+        _ = draining_rx.wait_for(|draining| *draining) => {}
+    }
+
+    tracing::info!(?drain_timeout, "shutting down, draining connections");
+    match tokio::time::timeout(drain_timeout, server).await {
+        Ok(result) => result,
+        Err(_) => {
+            tracing::warn!("drain deadline exceeded, dropping remaining connections");
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Instant;
+
+    use axum::routing::get;
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpStream,
+        sync::oneshot,
+    };
+
+    use super::*;
+
+    async fn slow() -> &'static str {
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        "Slow response"
+    }
+
+    async fn start(
+        drain_timeout: Duration,
+    ) -> (
+        std::net::SocketAddr,
+        Readiness,
+        oneshot::Sender<()>,
+        tokio::task::JoinHandle<io::Result<()>>,
+    ) {
+        let app = Router::new().route("/slow", get(slow));
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+
+        let readiness = Readiness::new();
+        readiness.set_ready(true);
+
+        let (tx, rx) = oneshot::channel::<()>();
+        let server = tokio::spawn(serve_with_shutdown(
+            listener,
+            app,
+            readiness.clone(),
+            drain_timeout,
+            async move {
+                let _ = rx.await;
+            },
+        ));
+
+        (address, readiness, tx, server)
+    }
+
+    async fn send_slow_request(address: std::net::SocketAddr) -> TcpStream {
+        let mut stream = TcpStream::connect(address).await.unwrap();
+        stream
+            .write_all(b"GET /slow HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n")
+            .await
+            .unwrap();
+        stream
+    }
+
+    // Graceful shutdown
+    #[tokio::test]
+    async fn test_in_flight_request_completes() {
+        let (address, readiness, shutdown, server) = start(Duration::from_secs(5)).await;
+
+        let mut stream = send_slow_request(address).await;
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        shutdown.send(()).unwrap();
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        assert!(!readiness.is_ready());
+        assert!(TcpStream::connect(address).await.is_err());
+
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        assert!(response.starts_with("HTTP/1.1 200 OK"));
+        assert!(response.ends_with("Slow response"));
+
+        server.await.unwrap().unwrap();
+    }
+
+    // Drain deadline
+    #[tokio::test]
+    async fn test_drain_deadline() {
+        let (address, _readiness, shutdown, server) = start(Duration::from_millis(50)).await;
+
+        let _stream = send_slow_request(address).await;
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        let started = Instant::now();
+        shutdown.send(()).unwrap();
+        server.await.unwrap().unwrap();
+
+        assert!(started.elapsed() < Duration::from_millis(250));
+    }
+}
@@ -0,0 +1,138 @@
+use std::sync::Arc;
+
+use crate::{
+    auth::{
+        lockout::{InMemoryLoginAttemptStore, LoginAttemptStore},
+        store::{InMemoryUserStore, UserStore},
+        token::TokenSigner,
+    },
+    config::Config,
+    cookies::CookieKeys,
+    db::{self, DatabaseCheck, DbPool, PoolCollector},
+    files::{FileStorage, InMemoryFileStorage},
+    health::{DiskSpaceCheck, HealthRegistry, Probe, Readiness},
+    metrics::Metrics,
+    products::{InMemoryProductStore, ProductStore},
+    rate_limit::{InMemoryRateLimitStore, RateLimitStore},
+    rbac::{InMemoryRoleStore, RoleStore},
+    session::{InMemorySessionStore, SessionStore},
+};
+
+// State yang dibagikan ke semua handler
+#[derive(Clone)]
+pub struct AppState {
+    pub config: Arc<Config>,
+    pub readiness: Readiness,
+    pub db: DbPool,
+    pub users: Arc<dyn UserStore>,
+    pub products: Arc<dyn ProductStore>,
+    pub roles: Arc<dyn RoleStore>,
+    pub sessions: Arc<dyn SessionStore>,
+    pub files: Arc<dyn FileStorage>,
+    // thumbnail disimpan terpisah dari file asli agar ID-nya tidak bisa dipakai sebagai file asli
+    pub thumbnails: Arc<dyn FileStorage>,
+    pub rate_limits: Arc<dyn RateLimitStore>,
+    pub login_attempts: Arc<dyn LoginAttemptStore>,
+    pub tokens: TokenSigner,
+    pub cookie_keys: CookieKeys,
+    pub metrics: Metrics,
+    pub health: HealthRegistry,
+}
+
+impl AppState {
+    /// Fails if the database URL or a cookie key is invalid, e.g. for a `Config` that skipped `validate`.
+    pub fn new(config: Config) -> anyhow::Result<AppState> {
+        let tokens = match &config.auth.token_secret {
+            Some(secret) => TokenSigner::new(secret.as_bytes(), config.auth.token_ttl()),
+            None => TokenSigner::random(config.auth.token_ttl()),
+        };
+        let cookie_keys = CookieKeys::from_config(&config.cookie)?;
+        let max_keys = config.rate_limit.max_keys;
+        let max_login_keys = config.lockout.max_keys;
+
+        let db = db::connect_lazy(&config.database)?;
+
+        let metrics = Metrics::new();
+        metrics
+            .registry()
+            .register(Box::new(PoolCollector::new(db.clone())))
+            .unwrap();
+
+        let health = HealthRegistry::new();
+        health.register(
+            "database",
+            Probe::Readiness,
+            config.health.timeout(),
+            DatabaseCheck { pool: db.clone() },
+        );
+        if config.health.min_free_disk_mb > 0 {
+            health.register(
+                "disk",
+                Probe::Readiness,
+                config.health.timeout(),
+                DiskSpaceCheck::new(
+                    config.health.disk_path.clone(),
+                    config.health.min_free_disk_mb,
+                ),
+            );
+        }
+
+        Ok(AppState {
+            config: Arc::new(config),
+            readiness: Readiness::new(),
+            db,
+            users: Arc::new(InMemoryUserStore::new()),
+            products: Arc::new(InMemoryProductStore::new()),
+            roles: Arc::new(InMemoryRoleStore::new()),
+            sessions: Arc::new(InMemorySessionStore::new()),
+            files: Arc::new(InMemoryFileStorage::new()),
+            thumbnails: Arc::new(InMemoryFileStorage::new()),
+            rate_limits: Arc::new(InMemoryRateLimitStore::new(max_keys)),
+            login_attempts: Arc::new(InMemoryLoginAttemptStore::new(max_login_keys)),
+            tokens,
+            cookie_keys,
+            metrics,
+            health,
+        })
+    }
+
+    pub fn with_users(mut self, users: Arc<dyn UserStore>) -> AppState {
+        self.users = users;
+        self
+    }
+
+    pub fn with_products(mut self, products: Arc<dyn ProductStore>) -> AppState {
+        self.products = products;
+        self
+    }
+
+    pub fn with_roles(mut self, roles: Arc<dyn RoleStore>) -> AppState {
+        self.roles = roles;
+        self
+    }
+
+    pub fn with_sessions(mut self, sessions: Arc<dyn SessionStore>) -> AppState {
+        self.sessions = sessions;
+        self
+    }
+
+    pub fn with_files(mut self, files: Arc<dyn FileStorage>) -> AppState {
+        self.files = files;
+        self
+    }
+
+    pub fn with_thumbnails(mut self, thumbnails: Arc<dyn FileStorage>) -> AppState {
+        self.thumbnails = thumbnails;
+        self
+    }
+
+    pub fn with_rate_limits(mut self, rate_limits: Arc<dyn RateLimitStore>) -> AppState {
+        self.rate_limits = rate_limits;
+        self
+    }
+
+    pub fn with_login_attempts(mut self, login_attempts: Arc<dyn LoginAttemptStore>) -> AppState {
+        self.login_attempts = login_attempts;
+        self
+    }
+}
@@ -0,0 +1,99 @@
+// Fixture bersama untuk test di semua modul
+
+use std::{net::SocketAddr, panic::AssertUnwindSafe, sync::Arc};
+
+use axum::extract::connect_info::MockConnectInfo;
+use axum_test::TestServer;
+use futures::FutureExt;
+
+use crate::{
+    auth::store::{InMemoryUserStore, User},
+    build_app, AppState, Config,
+};
+
+/// Builds the application under test: [`build_app`] on an [`AppState`] with in-memory stores.
+#[derive(Default)]
+pub(crate) struct TestApp {
+    config: Config,
+    users: Vec<User>,
+}
+
+impl TestApp {
+    pub(crate) fn new() -> TestApp {
+        TestApp::default()
+    }
+
+    pub(crate) fn with_config(config: Config) -> TestApp {
+        TestApp {
+            config,
+            users: Vec::new(),
+        }
+    }
+
+    pub(crate) fn config(mut self, configure: impl FnOnce(&mut Config)) -> TestApp {
+        configure(&mut self.config);
+        self
+    }
+
+    pub(crate) fn user(mut self, user: User) -> TestApp {
+        self.users.push(user);
+        self
+    }
+
+    /// The state alone, for tests that mount their own router or register extra checks.
+    pub(crate) fn state(self) -> AppState {
+        let users = InMemoryUserStore::new();
+        for user in self.users {
+            users.insert(user);
+        }
+        AppState::new(self.config)
+            .unwrap()
+            .with_users(Arc::new(users))
+    }
+
+    pub(crate) fn server(self) -> (TestServer, AppState) {
+        let state = self.state();
+        (server(&state), state)
+    }
+}
+
+/// Serves [`build_app`] for `state`; every request comes from 10.0.0.1.
+pub(crate) fn server(state: &AppState) -> TestServer {
+    let app =
+        build_app(state.clone()).layer(MockConnectInfo(SocketAddr::from(([10, 0, 0, 1], 4000))));
+    TestServer::new(app).unwrap()
+}
+
+/// Authorization header value with a token for the current version of a stored user.
+pub(crate) async fn bearer(state: &AppState, username: &str) -> String {
+    let user = state
+        .users
+        .find_by_username(username)
+        .await
+        .unwrap()
+        .unwrap();
+    format!(
+        "Bearer {}",
+        state.tokens.issue(username, user.token_version)
+    )
+}
+
+/// Runs the same store contract against every backend of a store trait.
+///
+/// A failing assertion is re-raised with the backend name, so the test output shows
+/// which implementation broke the contract.
+pub(crate) async fn check_store_contract<T: ?Sized>(
+    backends: Vec<(&str, Box<T>)>,
+    contract: impl AsyncFn(&T),
+) {
+    for (name, store) in backends {
+        if let Err(panic) = AssertUnwindSafe(contract(&*store)).catch_unwind().await {
+            let message = panic
+                .downcast_ref::<String>()
+                .map(String::as_str)
+                .or_else(|| panic.downcast_ref::<&str>().copied())
+                .unwrap_or("contract panicked");
+            panic!("{} store broke the contract: {}", name, message);
+        }
+    }
+}
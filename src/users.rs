@@ -0,0 +1,735 @@
+use std::sync::LazyLock;
+
+use axum::{
+    extract::{multipart::MultipartRejection, DefaultBodyLimit, Multipart, State},
+    routing::{get, post, put},
+    Json, Router,
+};
+use http::StatusCode;
+use regex::Regex;
+use serde::{Deserialize, Deserializer, Serialize};
+use validator::Validate;
+
+use crate::{
+    auth::{password, store::User, AuthError, AuthUser},
+    error::AppError,
+    extract::ValidatedJson,
+    files::{self, FileId},
+    session::Session,
+    state::AppState,
+};
+
+// Nama field multipart untuk foto profil
+pub const AVATAR_FIELD: &str = "profile";
+
+static USERNAME: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^[A-Za-z0-9_.-]+$").unwrap());
+
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct RegisterRequest {
+    #[validate(length(min = 3, max = 64), regex(path = *USERNAME))]
+    pub username: String,
+    #[validate(length(min = 8, max = 1024))]
+    pub password: String,
+    #[validate(email)]
+    pub email: Option<String>,
+    #[validate(length(min = 1, max = 100))]
+    pub display_name: Option<String>,
+}
+
+// Field yang tidak dikirim tidak diubah, null menghapus nilainya
+#[derive(Debug, Default, Serialize, Deserialize, Validate)]
+pub struct UpdateProfileRequest {
+    #[serde(
+        default,
+        deserialize_with = "nullable",
+        skip_serializing_if = "Option::is_none"
+    )]
+    #[validate(email)]
+    pub email: Option<Option<String>>,
+    #[serde(
+        default,
+        deserialize_with = "nullable",
+        skip_serializing_if = "Option::is_none"
+    )]
+    #[validate(length(min = 1, max = 100))]
+    pub display_name: Option<Option<String>>,
+}
+
+// Membedakan field yang tidak dikirim (None) dari null (Some(None))
+fn nullable<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::deserialize(deserializer).map(Some)
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct ChangePasswordRequest {
+    #[validate(length(min = 1, max = 1024))]
+    pub current_password: String,
+    #[validate(length(min = 8, max = 1024))]
+    pub new_password: String,
+}
+
+// Data user yang aman dikirim ke client (tanpa hash password)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Profile {
+    pub username: String,
+    pub email: Option<String>,
+    pub display_name: Option<String>,
+    // gambar tersedia di /api/files/{avatar}
+    pub avatar: Option<FileId>,
+}
+
+impl From<User> for Profile {
+    fn from(user: User) -> Self {
+        Profile {
+            username: user.username,
+            email: user.email,
+            display_name: user.display_name,
+            avatar: user.avatar,
+        }
+    }
+}
+
+// Route /api/users
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/", post(register))
+        .route(
+            "/me",
+            get(profile).patch(update_profile).delete(delete_account),
+        )
+        .route("/me/password", put(change_password))
+        // batas ukuran diatur oleh upload.max_size di files::upload
+        .route(
+            "/me/avatar",
+            put(upload_avatar).layer(DefaultBodyLimit::disable()),
+        )
+}
+
+fn user_not_found() -> AppError {
+    AppError::not_found("user_not_found", "User not found")
+}
+
+// Akun bisa terhapus setelah token diverifikasi oleh AuthUser
+async fn current_user(state: &AppState, user: &AuthUser) -> Result<User, AppError> {
+    state
+        .users
+        .find_by_username(&user.username)
+        .await?
+        .ok_or_else(user_not_found)
+}
+
+// Akun bisa terhapus di antara current_user dan update
+fn ensure_updated(updated: bool) -> Result<(), AppError> {
+    if !updated {
+        return Err(user_not_found());
+    }
+    Ok(())
+}
+
+async fn register(
+    State(state): State<AppState>,
+    ValidatedJson(request): ValidatedJson<RegisterRequest>,
+) -> Result<(StatusCode, Json<Profile>), AppError> {
+    let user = User {
+        username: request.username,
+        password_hash: password::hash(request.password).await?,
+        email: request.email,
+        display_name: request.display_name,
+        locked: false,
+        avatar: None,
+        token_version: User::new_token_version(),
+    };
+
+    let user = state.users.create(user).await?;
+    Ok((StatusCode::CREATED, Json(user.into())))
+}
+
+async fn profile(State(state): State<AppState>, user: AuthUser) -> Result<Json<Profile>, AppError> {
+    Ok(Json(current_user(&state, &user).await?.into()))
+}
+
+async fn update_profile(
+    State(state): State<AppState>,
+    user: AuthUser,
+    ValidatedJson(request): ValidatedJson<UpdateProfileRequest>,
+) -> Result<Json<Profile>, AppError> {
+    let mut user = current_user(&state, &user).await?;
+    if let Some(email) = request.email {
+        user.email = email;
+    }
+    if let Some(display_name) = request.display_name {
+        user.display_name = display_name;
+    }
+
+    let updated = state
+        .users
+        .update_profile(
+            &user.username,
+            user.email.as_deref(),
+            user.display_name.as_deref(),
+        )
+        .await?;
+    ensure_updated(updated)?;
+    Ok(Json(user.into()))
+}
+
+// Semua token dan session akun ini, termasuk yang sedang dipakai, tidak berlaku lagi
+async fn change_password(
+    State(state): State<AppState>,
+    user: AuthUser,
+    session: Session,
+    ValidatedJson(request): ValidatedJson<ChangePasswordRequest>,
+) -> Result<StatusCode, AppError> {
+    let user = current_user(&state, &user).await?;
+    if !password::verify(request.current_password, user.password_hash).await? {
+        return Err(AuthError::WrongPassword.into());
+    }
+
+    let password_hash = password::hash(request.new_password).await?;
+    let updated = state
+        .users
+        .set_password(&user.username, &password_hash, User::new_token_version())
+        .await?;
+    ensure_updated(updated)?;
+    session.destroy();
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn upload_avatar(
+    State(state): State<AppState>,
+    user: AuthUser,
+    multipart: Result<Multipart, MultipartRejection>,
+) -> Result<Json<Profile>, AppError> {
+    let mut user = current_user(&state, &user).await?;
+    let file = files::upload(&state, multipart?, AVATAR_FIELD).await?;
+    files::thumbnail::spawn_generate_all(
+        state.files.clone(),
+        state.thumbnails.clone(),
+        file.id.clone(),
+        state.config.upload.thumbnail_sizes.clone(),
+    );
+
+    let previous = user.avatar.replace(file.id.clone());
+    let updated = state
+        .users
+        .set_avatar(&user.username, &file.id)
+        .await
+        .map_err(AppError::from)
+        .and_then(ensure_updated);
+    if let Err(error) = updated {
+        files::delete(&state, &file.id).await?;
+        return Err(error);
+    }
+    if let Some(previous) = previous {
+        files::delete(&state, &previous).await?;
+    }
+    Ok(Json(user.into()))
+}
+
+// Token dan session lain ditolak oleh AuthUser: user-nya sudah tidak ada, dan akun baru
+// dengan username yang sama mendapat token_version acak yang lain
+async fn delete_account(
+    State(state): State<AppState>,
+    user: AuthUser,
+    session: Session,
+) -> Result<StatusCode, AppError> {
+    let user = current_user(&state, &user).await?;
+    if !state.users.delete(&user.username).await? {
+        return Err(user_not_found());
+    }
+    session.destroy();
+    // username yang didaftarkan ulang tidak mewarisi role lama
+    state.roles.revoke_all(&user.username).await?;
+    if let Some(avatar) = user.avatar {
+        files::delete(&state, &avatar).await?;
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use tokio::sync::mpsc;
+
+    use axum_test::{
+        multipart::{MultipartForm, Part},
+        TestServer,
+    };
+    use serde_json::json;
+
+    use super::*;
+    use crate::{
+        auth::{
+            store::{InMemoryUserStore, UserStore, UserStoreError},
+            LoginResponse,
+        },
+        error::assert_error_text,
+        files::{FileStorage, FileWriter, InMemoryFileStorage, StoredFile},
+        test_support::{self, TestApp},
+    };
+
+    const PNG: &[u8] = b"\x89PNG\r\n\x1a\n\x00\x00\x00\rIHDR";
+
+    fn avatar(data: &'static [u8]) -> MultipartForm {
+        // Content-Type dari client sengaja dibuat salah, tipe ditentukan dari isi file
+        MultipartForm::new().add_part(
+            AVATAR_FIELD,
+            Part::bytes(data)
+                .file_name("foto.png")
+                .mime_type("image/png"),
+        )
+    }
+
+    async fn register_and_login(server: &TestServer, username: &str, email: &str) -> String {
+        server
+            .post("/api/users")
+            .json(&json!({"username": username, "password": "rahasia123", "email": email}))
+            .await
+            .assert_status(StatusCode::CREATED);
+
+        let response: LoginResponse = server
+            .post("/api/auth/login")
+            .json(&json!({"username": username, "password": "rahasia123"}))
+            .await
+            .json();
+        format!("Bearer {}", response.token)
+    }
+
+    // Registrasi dan profil
+    #[tokio::test]
+    async fn test_register_and_profile() {
+        let (server, _) = TestApp::new().server();
+        let token = register_and_login(&server, "aqil", "aqil@example.com").await;
+
+        let response = server.get("/api/users/me").authorization(&token).await;
+        response.assert_status_ok();
+        response.assert_json(&Profile {
+            username: "aqil".to_string(),
+            email: Some("aqil@example.com".to_string()),
+            display_name: None,
+            avatar: None,
+        });
+
+        let response = server
+            .patch("/api/users/me")
+            .authorization(&token)
+            .json(&json!({"display_name": "Aqil"}))
+            .await;
+        response.assert_status_ok();
+        let profile: Profile = response.json();
+        assert_eq!(profile.display_name.as_deref(), Some("Aqil"));
+        assert_eq!(profile.email.as_deref(), Some("aqil@example.com"));
+
+        // null menghapus nilai, field yang tidak dikirim tetap
+        let response = server
+            .patch("/api/users/me")
+            .authorization(&token)
+            .json(&json!({"email": null}))
+            .await;
+        response.assert_status_ok();
+        let profile: Profile = response.json();
+        assert_eq!(profile.email, None);
+        assert_eq!(profile.display_name.as_deref(), Some("Aqil"));
+
+        let response = server
+            .patch("/api/users/me")
+            .authorization(&token)
+            .json(&json!({"display_name": ""}))
+            .await;
+        response.assert_status(StatusCode::UNPROCESSABLE_ENTITY);
+
+        server
+            .get("/api/users/me")
+            .await
+            .assert_status(StatusCode::UNAUTHORIZED);
+    }
+
+    // Username dan email harus unik
+    #[tokio::test]
+    async fn test_uniqueness_conflicts() {
+        let (server, _) = TestApp::new().server();
+        let token = register_and_login(&server, "aqil", "aqil@example.com").await;
+        register_and_login(&server, "budi", "budi@example.com").await;
+
+        let response = server
+            .post("/api/users")
+            .json(&json!({"username": "aqil", "password": "rahasia123"}))
+            .await;
+        response.assert_status(StatusCode::CONFLICT);
+        assert_error_text(&response, "Username is already taken");
+
+        let response = server
+            .post("/api/users")
+            .add_header("Accept", "application/json")
+            .json(&json!({"username": "citra", "password": "rahasia123", "email": "budi@example.com"}))
+            .await;
+        response.assert_status(StatusCode::CONFLICT);
+        assert_eq!(response.json::<serde_json::Value>()["code"], "email_taken");
+
+        let response = server
+            .patch("/api/users/me")
+            .authorization(&token)
+            .json(&json!({"email": "budi@example.com"}))
+            .await;
+        response.assert_status(StatusCode::CONFLICT);
+        assert_error_text(&response, "Email is already registered");
+    }
+
+    // Ganti password dan hapus akun mencabut token dan session yang sudah ada
+    #[tokio::test]
+    async fn test_change_password_and_delete() {
+        let (server, _) = TestApp::new().server();
+        let token = register_and_login(&server, "aqil", "aqil@example.com").await;
+        let cookies = server
+            .post("/api/auth/login")
+            .json(&json!({"username": "aqil", "password": "rahasia123"}))
+            .await
+            .cookies();
+        let revoked = "Login is no longer valid, please log in again";
+
+        let response = server
+            .put("/api/users/me/password")
+            .authorization(&token)
+            .json(&json!({"current_password": "salah", "new_password": "rahasia456"}))
+            .await;
+        response.assert_status(StatusCode::UNAUTHORIZED);
+        assert_error_text(&response, "Wrong password");
+
+        server
+            .put("/api/users/me/password")
+            .authorization(&token)
+            .json(&json!({"current_password": "rahasia123", "new_password": "rahasia456"}))
+            .await
+            .assert_status(StatusCode::NO_CONTENT);
+
+        let response = server.get("/api/users/me").authorization(&token).await;
+        response.assert_status(StatusCode::UNAUTHORIZED);
+        assert_error_text(&response, revoked);
+        let response = server.get("/api/users/me").add_cookies(cookies).await;
+        response.assert_status(StatusCode::UNAUTHORIZED);
+        assert_error_text(&response, revoked);
+
+        let response: LoginResponse = server
+            .post("/api/auth/login")
+            .json(&json!({"username": "aqil", "password": "rahasia456"}))
+            .await
+            .json();
+        let token = format!("Bearer {}", response.token);
+        server
+            .get("/api/users/me")
+            .authorization(&token)
+            .await
+            .assert_status_ok();
+
+        server
+            .delete("/api/users/me")
+            .authorization(&token)
+            .await
+            .assert_status(StatusCode::NO_CONTENT);
+
+        let response = server.get("/api/users/me").authorization(&token).await;
+        response.assert_status(StatusCode::UNAUTHORIZED);
+        assert_error_text(&response, revoked);
+    }
+
+    // Token akun yang dihapus tidak berlaku untuk akun baru dengan username yang sama
+    #[tokio::test]
+    async fn test_deleted_account_token() {
+        let (server, _) = TestApp::new().server();
+        let token = register_and_login(&server, "aqil", "aqil@example.com").await;
+        server
+            .delete("/api/users/me")
+            .authorization(&token)
+            .await
+            .assert_status(StatusCode::NO_CONTENT);
+
+        let new_token = register_and_login(&server, "aqil", "aqil@example.com").await;
+        let response = server.get("/api/users/me").authorization(&token).await;
+        response.assert_status(StatusCode::UNAUTHORIZED);
+        assert_error_text(&response, "Login is no longer valid, please log in again");
+        server
+            .get("/api/users/me")
+            .authorization(&new_token)
+            .await
+            .assert_status_ok();
+    }
+
+    #[tokio::test]
+    async fn test_register_validation() {
+        let (server, _) = TestApp::new().server();
+        let response = server
+            .post("/api/users")
+            .json(&json!({"username": "a b", "password": "pendek", "email": "bukan email"}))
+            .await;
+        response.assert_status(StatusCode::UNPROCESSABLE_ENTITY);
+        assert_error_text(
+            &response,
+            "Request validation failed\n\
+             email: must be a valid email address\n\
+             password: length must be between 8 and 1024\n\
+             username: has an invalid format",
+        );
+    }
+
+    // Upload foto profil dan ambil kembali lewat /api/files
+    #[tokio::test]
+    async fn test_avatar_upload() {
+        let (server, _) = TestApp::new().server();
+        let token = register_and_login(&server, "aqil", "aqil@example.com").await;
+
+        let response = server
+            .put("/api/users/me/avatar")
+            .authorization(&token)
+            .multipart(avatar(PNG))
+            .await;
+        response.assert_status_ok();
+        let id = response.json::<Profile>().avatar.unwrap();
+
+        let url = format!("/api/files/{}", id);
+        let response = server.get(&url).await;
+        response.assert_status_ok();
+        response.assert_header("Content-Type", "image/png");
+        response.assert_header("Cache-Control", "public, max-age=31536000, immutable");
+        assert_eq!(response.as_bytes().as_ref(), PNG);
+
+        let etag = response.header("ETag");
+        let response = server.get(&url).add_header("If-None-Match", etag).await;
+        response.assert_status(StatusCode::NOT_MODIFIED);
+        assert!(response.as_bytes().is_empty());
+
+        // foto lama dihapus saat diganti
+        let response = server
+            .put("/api/users/me/avatar")
+            .authorization(&token)
+            .multipart(avatar(b"GIF89a\x01\x00\x01\x00"))
+            .await;
+        assert_ne!(response.json::<Profile>().avatar.unwrap(), id);
+        server.get(&url).await.assert_status_not_found();
+
+        server
+            .get("/api/files/bukan-id")
+            .await
+            .assert_status(StatusCode::BAD_REQUEST);
+    }
+
+    // Storage thumbnail yang memberi tahu test setiap kali background task menulis
+    struct SignalingStorage {
+        inner: InMemoryFileStorage,
+        written: mpsc::UnboundedSender<FileId>,
+    }
+
+    #[async_trait::async_trait]
+    impl FileStorage for SignalingStorage {
+        async fn create(&self, id: &FileId) -> anyhow::Result<Box<dyn FileWriter>> {
+            self.inner.create(id).await
+        }
+
+        async fn get(&self, id: &FileId) -> anyhow::Result<Option<StoredFile>> {
+            self.inner.get(id).await
+        }
+
+        async fn delete(&self, id: &FileId) -> anyhow::Result<bool> {
+            self.inner.delete(id).await
+        }
+
+        async fn put(&self, id: &FileId, file: &StoredFile) -> anyhow::Result<()> {
+            self.inner.put(id, file).await?;
+            let _ = self.written.send(id.clone());
+            Ok(())
+        }
+    }
+
+    // Thumbnail dibuat di background dan dipilih dengan ?size=
+    #[tokio::test]
+    async fn test_avatar_thumbnails() {
+        let (written, mut thumbnails_written) = mpsc::unbounded_channel();
+        let state = TestApp::new()
+            .state()
+            .with_thumbnails(Arc::new(SignalingStorage {
+                inner: InMemoryFileStorage::new(),
+                written,
+            }));
+        let server = test_support::server(&state);
+        let token = register_and_login(&server, "aqil", "aqil@example.com").await;
+
+        let mut png = Vec::new();
+        image::DynamicImage::new_rgb8(300, 150)
+            .write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png)
+            .unwrap();
+        let form = MultipartForm::new().add_part(AVATAR_FIELD, Part::bytes(png));
+        let response = server
+            .put("/api/users/me/avatar")
+            .authorization(&token)
+            .multipart(form)
+            .await;
+        let id = response.json::<Profile>().avatar.unwrap();
+
+        let thumbnails: Vec<FileId> = [64, 128, 256]
+            .iter()
+            .map(|size| files::thumbnail::thumbnail_id(&id, *size))
+            .collect();
+        for thumbnail in &thumbnails {
+            assert_eq!(thumbnails_written.recv().await.as_ref(), Some(thumbnail));
+            assert!(state.thumbnails.exists(thumbnail).await.unwrap());
+        }
+
+        let response = server
+            .get(&format!("/api/files/{}", id))
+            .add_query_param("size", 64)
+            .await;
+        response.assert_status_ok();
+        response.assert_header("Content-Type", "image/png");
+        response.assert_header("ETag", format!("\"{}-64\"", id));
+        let image = image::load_from_memory(response.as_bytes()).unwrap();
+        assert_eq!((image.width(), image.height()), (64, 32));
+
+        let response = server
+            .get(&format!("/api/files/{}", id))
+            .add_query_param("size", 100)
+            .await;
+        response.assert_status(StatusCode::BAD_REQUEST);
+        assert_error_text(&response, "Thumbnail size must be one of 64, 128, 256");
+
+        // thumbnail ikut terhapus bersama foto lama
+        server
+            .delete("/api/users/me")
+            .authorization(&token)
+            .await
+            .assert_status(StatusCode::NO_CONTENT);
+        for thumbnail in &thumbnails {
+            assert!(!state.thumbnails.exists(thumbnail).await.unwrap());
+        }
+    }
+
+    // Store yang meniru akun terhapus di antara current_user dan update
+    struct VanishingUsers(InMemoryUserStore);
+
+    #[async_trait::async_trait]
+    impl UserStore for VanishingUsers {
+        async fn find_by_username(&self, username: &str) -> anyhow::Result<Option<User>> {
+            self.0.find_by_username(username).await
+        }
+
+        async fn create(&self, user: User) -> Result<User, UserStoreError> {
+            self.0.create(user).await
+        }
+
+        async fn update_profile(
+            &self,
+            _username: &str,
+            _email: Option<&str>,
+            _display_name: Option<&str>,
+        ) -> Result<bool, UserStoreError> {
+            Ok(false)
+        }
+
+        async fn set_avatar(&self, _username: &str, _avatar: &FileId) -> anyhow::Result<bool> {
+            Ok(false)
+        }
+
+        async fn set_password(
+            &self,
+            _username: &str,
+            _password_hash: &str,
+            _token_version: i64,
+        ) -> anyhow::Result<bool> {
+            Ok(false)
+        }
+
+        async fn delete(&self, username: &str) -> anyhow::Result<bool> {
+            self.0.delete(username).await
+        }
+    }
+
+    #[tokio::test]
+    async fn test_user_deleted_during_update() {
+        let users = InMemoryUserStore::new();
+        users.insert(User::new("aqil", "rahasia123").unwrap());
+        let state = TestApp::new()
+            .state()
+            .with_users(Arc::new(VanishingUsers(users)));
+        let server = test_support::server(&state);
+        let token = format!(
+            "Bearer {}",
+            server
+                .post("/api/auth/login")
+                .json(&json!({"username": "aqil", "password": "rahasia123"}))
+                .await
+                .json::<LoginResponse>()
+                .token
+        );
+
+        let response = server
+            .patch("/api/users/me")
+            .authorization(&token)
+            .json(&json!({"display_name": "Aqil"}))
+            .await;
+        response.assert_status_not_found();
+        assert_error_text(&response, "User not found");
+
+        server
+            .put("/api/users/me/password")
+            .authorization(&token)
+            .json(&json!({"current_password": "rahasia123", "new_password": "rahasia456"}))
+            .await
+            .assert_status_not_found();
+
+        server
+            .put("/api/users/me/avatar")
+            .authorization(&token)
+            .multipart(avatar(PNG))
+            .await
+            .assert_status_not_found();
+    }
+
+    #[tokio::test]
+    async fn test_avatar_rejected() {
+        let (server, _) = TestApp::new()
+            .config(|config| config.upload.max_size = 32)
+            .server();
+        let token = register_and_login(&server, "aqil", "aqil@example.com").await;
+
+        let response = server
+            .put("/api/users/me/avatar")
+            .authorization(&token)
+            .multipart(avatar(b"<svg onload=\"alert(1)\"></svg>"))
+            .await;
+        response.assert_status(StatusCode::UNSUPPORTED_MEDIA_TYPE);
+        response.assert_text_contains("File type must be one of image/png");
+
+        let response = server
+            .put("/api/users/me/avatar")
+            .authorization(&token)
+            .multipart(avatar(&[0x89; 64]))
+            .await;
+        response.assert_status(StatusCode::PAYLOAD_TOO_LARGE);
+        assert_error_text(&response, "File must not exceed 32 bytes");
+
+        let response = server
+            .put("/api/users/me/avatar")
+            .authorization(&token)
+            .multipart(MultipartForm::new().add_text("username", "aqil"))
+            .await;
+        response.assert_status(StatusCode::BAD_REQUEST);
+        assert_error_text(&response, "Unexpected multipart field username");
+
+        let response = server
+            .put("/api/users/me/avatar")
+            .authorization(&token)
+            .json(&json!({}))
+            .await;
+        response.assert_status(StatusCode::UNSUPPORTED_MEDIA_TYPE);
+
+        let profile: Profile = server
+            .get("/api/users/me")
+            .authorization(&token)
+            .await
+            .json();
+        assert_eq!(profile.avatar, None);
+    }
+}
@@ -0,0 +1,174 @@
+use validator::{ValidationError, ValidationErrors, ValidationErrorsKind};
+
+use crate::error::{AppError, FieldError};
+
+pub use validator::Validate;
+
+// Semua error field dikembalikan sekaligus dalam satu response 422
+impl From<ValidationErrors> for AppError {
+    fn from(errors: ValidationErrors) -> Self {
+        let mut fields = Vec::new();
+        flatten(&errors, "", &mut fields);
+        fields.sort_by(|a, b| a.field.cmp(&b.field));
+
+        AppError::unprocessable("validation_failed", "Request validation failed", fields)
+    }
+}
+
+pub fn validate<T: Validate>(value: &T) -> Result<(), AppError> {
+    value.validate().map_err(AppError::from)
+}
+
+// Custom rule: string tidak boleh kosong atau hanya berisi spasi
+pub fn not_blank(value: &str) -> Result<(), ValidationError> {
+    if value.trim().is_empty() {
+        let mut error = ValidationError::new("not_blank");
+        error.message = Some("must not be blank".into());
+        return Err(error);
+    }
+    Ok(())
+}
+
+fn flatten(errors: &ValidationErrors, prefix: &str, fields: &mut Vec<FieldError>) {
+    for (field, kind) in errors.errors() {
+        let path = if prefix.is_empty() {
+            field.to_string()
+        } else {
+            format!("{}.{}", prefix, field)
+        };
+
+        match kind {
+            ValidationErrorsKind::Field(errors) => {
+                for error in errors {
+                    fields.push(FieldError::new(path.clone(), describe(error)));
+                }
+            }
+            ValidationErrorsKind::Struct(errors) => flatten(errors, &path, fields),
+            ValidationErrorsKind::List(items) => {
+                for (index, errors) in items {
+                    flatten(errors, &format!("{}[{}]", path, index), fields);
+                }
+            }
+        }
+    }
+}
+
+fn describe(error: &ValidationError) -> String {
+    if let Some(message) = &error.message {
+        return message.to_string();
+    }
+
+    let param = |name: &str| error.params.get(name).map(|value| value.to_string());
+
+    match error.code.as_ref() {
+        "length" => match (param("min"), param("max"), param("equal")) {
+            (_, _, Some(equal)) => format!("length must be exactly {}", equal),
+            (Some(min), Some(max), _) => format!("length must be between {} and {}", min, max),
+            (Some(min), None, _) => format!("length must be at least {}", min),
+            (None, Some(max), _) => format!("length must be at most {}", max),
+            _ => "has an invalid length".to_string(),
+        },
+        "range" => match (param("min"), param("max")) {
+            (Some(min), Some(max)) => format!("must be between {} and {}", min, max),
+            (Some(min), None) => format!("must be at least {}", min),
+            (None, Some(max)) => format!("must be at most {}", max),
+            _ => "is out of range".to_string(),
+        },
+        "email" => "must be a valid email address".to_string(),
+        "regex" => "has an invalid format".to_string(),
+        "required" => "is required".to_string(),
+        code => format!("failed the {} rule", code),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::LazyLock;
+
+    use regex::Regex;
+    use serde::Deserialize;
+
+    use super::*;
+
+    static SKU: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^[A-Z]{3}-\d{3}$").unwrap());
+
+    fn even(value: u32) -> Result<(), ValidationError> {
+        if !value.is_multiple_of(2) {
+            return Err(ValidationError::new("even"));
+        }
+        Ok(())
+    }
+
+    #[derive(Debug, Deserialize, Validate)]
+    struct Product {
+        #[validate(length(min = 3, max = 20))]
+        name: String,
+        #[validate(regex(path = *SKU))]
+        sku: String,
+        #[validate(email)]
+        owner: String,
+        #[validate(range(min = 1, max = 100), custom(function = "even"))]
+        stock: u32,
+        #[validate(required)]
+        category: Option<String>,
+        #[validate(nested)]
+        tags: Vec<Tag>,
+    }
+
+    #[derive(Debug, Deserialize, Validate)]
+    struct Tag {
+        #[validate(custom(function = "not_blank"))]
+        name: String,
+    }
+
+    #[test]
+    fn test_validation_errors() {
+        let product = Product {
+            name: "TV".to_string(),
+            sku: "tv-1".to_string(),
+            owner: "aqil".to_string(),
+            stock: 101,
+            category: None,
+            tags: vec![
+                Tag {
+                    name: "elektronik".to_string(),
+                },
+                Tag {
+                    name: "  ".to_string(),
+                },
+            ],
+        };
+
+        let error = validate(&product).unwrap_err();
+        let AppError::Unprocessable { errors, .. } = error else {
+            panic!("expected 422");
+        };
+
+        assert_eq!(
+            errors,
+            vec![
+                FieldError::new("category", "is required"),
+                FieldError::new("name", "length must be between 3 and 20"),
+                FieldError::new("owner", "must be a valid email address"),
+                FieldError::new("sku", "has an invalid format"),
+                FieldError::new("stock", "must be between 1 and 100"),
+                FieldError::new("stock", "failed the even rule"),
+                FieldError::new("tags[1].name", "must not be blank"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_validation_success() {
+        let product = Product {
+            name: "Televisi".to_string(),
+            sku: "TVS-001".to_string(),
+            owner: "aqil@example.com".to_string(),
+            stock: 10,
+            category: Some("elektronik".to_string()),
+            tags: vec![],
+        };
+
+        assert!(validate(&product).is_ok());
+    }
+}